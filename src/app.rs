@@ -1,12 +1,30 @@
-use log::{debug, info, trace, warn};
-use std::time::Instant;
+use anyhow::Context;
+use clap::builder::PossibleValuesParser;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use log::{debug, error, info, trace, warn};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 use crate::cli::Cli;
 use crate::commands;
-use crate::config::Config;
+use crate::config::{self, Config, ConfigFormat};
+use crate::executor::{self, RunOptions};
+use crate::exit_codes::{exit_with, DeployError};
 use crate::logging;
+use crate::output;
+use crate::report;
 use crate::run;
-use crate::settings::{get_settings, Settings, DEFAULT_SETTINGS_PATH};
+use crate::schema;
+use crate::settings::{
+    env_path, get_settings, glob_matches, Settings, CONFIG_ENV_VAR, DEFAULT_CONFIG_FILE,
+    DEFAULT_SETTINGS_PATH,
+};
+use crate::storage;
+use crate::telemetry;
+use crate::timings;
+use crate::util;
 
 /// Глобальные настройки приложения
 #[derive(Debug)]
@@ -17,6 +35,12 @@ pub struct AppContext {
     /// Конфигурация деплоя
     pub config: Config,
 
+    /// Путь к файлу конфигурации деплоя
+    pub config_path: String,
+
+    /// Хеш загруженной конфигурации деплоя
+    pub config_hash: u64,
+
     /// Режим выполнения (параллельный или последовательный)
     pub parallel_execution: bool,
 }
@@ -29,15 +53,25 @@ pub fn initialize(cli: &Cli) -> Result<AppContext, String> {
         Err(e) => return Err(format!("Ошибка загрузки настроек: {}", e)),
     };
 
-    // Настройка логирования
-    if let Err(e) = logging::setup_logger(&settings.log_file, cli.verbose) {
+    // Настройка логирования; проверка работоспособности пишет журнал только в файл,
+    // чтобы ее вывод не засорял логи оркестратора, а форматирование конфигурации -
+    // чтобы ее вывод можно было сохранить в файл
+    let console = !matches!(
+        cli.command,
+        crate::cli::Command::Probe { .. }
+            | crate::cli::Command::Config {
+                action: crate::cli::ConfigAction::Fmt { in_place: false }
+            }
+    );
+    if let Err(e) = logging::setup_logger(&settings.log_file, cli.verbose, console) {
         return Err(format!("Ошибка настройки логирования: {}", e));
     }
 
     info!("Запуск Deploy Commander v{}", env!("CARGO_PKG_VERSION"));
 
     // Загрузка конфигурации деплоя
-    let config = match load_config(&cli.config) {
+    let config_path = resolve_config_path(cli);
+    let config = match load_config(&config_path, cli.strict_config, cli.config_format) {
         Ok(cfg) => cfg,
         Err(e) => return Err(e),
     };
@@ -55,18 +89,112 @@ pub fn initialize(cli: &Cli) -> Result<AppContext, String> {
 
     Ok(AppContext {
         settings,
+        config_hash: config::hash(&config),
         config,
+        config_path,
         parallel_execution,
     })
 }
 
+/// Определяет путь к файлу конфигурации
+///
+/// Флаг `--config` имеет приоритет над переменной окружения `DC_CONFIG`,
+/// которая имеет приоритет над значением по умолчанию.
+///
+/// # Параметры
+///
+/// * `cli` - Аргументы командной строки
+///
+/// # Возвращаемое значение
+///
+/// Путь к файлу конфигурации
+pub fn resolve_config_path(cli: &Cli) -> String {
+    cli.config
+        .clone()
+        .or_else(|| env_path(CONFIG_ENV_VAR))
+        .unwrap_or_else(|| DEFAULT_CONFIG_FILE.to_string())
+}
+
+/// Подкоманды, у которых значения `--deployment` дополняются именами деплоев из конфигурации
+const DEPLOYMENT_COMPLETION_SUBCOMMANDS: [&str; 5] =
+    ["run", "verify", "history", "clear-history", "prune-history"];
+
+/// Выводит скрипт автодополнения для указанной командной оболочки
+///
+/// Если файл конфигурации существует, значения `--deployment` дополняются
+/// именами деплоев из него.
+///
+/// # Параметры
+///
+/// * `shell` - Командная оболочка
+/// * `config_path` - Путь к файлу конфигурации
+pub fn print_completions(shell: Shell, config_path: &str) {
+    let mut command = Cli::command();
+
+    // Загружаем конфигурацию только если она существует, чтобы не создавать пустой файл
+    let deployment_names: Vec<String> = if Path::new(config_path).exists() {
+        Config::load(config_path)
+            .map(|cfg| cfg.deployments.into_iter().map(|d| d.name).collect())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    if !deployment_names.is_empty() {
+        for subcommand in DEPLOYMENT_COMPLETION_SUBCOMMANDS {
+            let mut values = deployment_names.clone();
+            if subcommand == "run" {
+                values.push("all".to_string());
+                values.push("failed".to_string());
+            }
+
+            command = command.mut_subcommand(subcommand, |sc| {
+                sc.mut_arg("deployment", |arg| {
+                    arg.value_parser(PossibleValuesParser::new(values))
+                })
+            });
+        }
+    }
+
+    let bin_name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, bin_name, &mut io::stdout());
+}
+
 /// Проверяет наличие необходимых внешних команд
-pub async fn check_dependencies() {
+///
+/// Отсутствие команд считается ошибкой перед запуском деплоя, если указан
+/// флаг `run --require-commands` или включена настройка `require_commands`.
+/// С флагом `--offline` проверка не выполняется.
+///
+/// # Параметры
+///
+/// * `cli` - Аргументы командной строки
+/// * `app_context` - Контекст приложения
+pub async fn check_dependencies(cli: &Cli, app_context: &AppContext) {
+    if cli.offline {
+        debug!("Проверка внешних команд пропущена (--offline)");
+        return;
+    }
+
     trace!("Проверка наличия необходимых внешних команд");
     let start_time = Instant::now();
 
+    let require = match &cli.command {
+        crate::cli::Command::Run {
+            require_commands,
+            dry_run,
+            print_env,
+            ..
+        } => !dry_run && !print_env && (*require_commands || app_context.settings.require_commands),
+        _ => false,
+    };
+
     if let Err(e) = commands::check_required_commands().await {
-        warn!("Некоторые команды недоступны: {}", e);
+        if require {
+            error!("{}. Деплой не будет запущен", e);
+            exit_with(DeployError::CommandFailure);
+        }
+        warn!("{}. Некоторые операции могут быть недоступны", e);
     }
 
     let duration = start_time.elapsed();
@@ -77,11 +205,21 @@ pub async fn check_dependencies() {
 }
 
 /// Загружает конфигурацию
-pub fn load_config(config_path: &str) -> Result<Config, String> {
+///
+/// # Параметры
+///
+/// * `config_path` - Путь к файлу конфигурации
+/// * `strict` - Считать неизвестные поля конфигурации ошибкой
+/// * `format` - Формат конфигурации (по умолчанию определяется по расширению)
+pub fn load_config(
+    config_path: &str,
+    strict: bool,
+    format: Option<ConfigFormat>,
+) -> Result<Config, String> {
     info!("Загрузка конфигурации из файла: {}", config_path);
     let start_time = Instant::now();
 
-    let result = match Config::load(config_path) {
+    let result = match Config::load_with_options(config_path, strict, format) {
         Ok(cfg) => {
             let duration = start_time.elapsed();
             info!(
@@ -97,54 +235,887 @@ pub fn load_config(config_path: &str) -> Result<Config, String> {
     result
 }
 
-/// Запускает указанный деплой с конкретным событием
+/// Запускает указанный деплой с выбранными событиями
 ///
 /// # Параметры
 ///
 /// * `app_context` - Контекст приложения
 /// * `deployment` - Имя деплоя
-/// * `event` - Опциональное имя события
-async fn handle_run_command(app_context: &AppContext, deployment: &str, event: &Option<String>) {
+/// * `events` - Имена событий в порядке выполнения (пустой список - все события)
+/// * `tags` - Теги для отбора деплоев при запуске `all` (пустой список - все деплои)
+/// * `yes` - Не запрашивать подтверждение для защищенных деплоев
+/// * `options` - Параметры запуска из командной строки
+async fn handle_run_command(
+    app_context: &AppContext,
+    deployment: &str,
+    events: &[String],
+    tags: &[String],
+    yes: bool,
+    options: &RunOptions,
+) {
     let history_path = &app_context.settings.history_file;
 
+    if !tags.is_empty() && deployment != "all" {
+        warn!("Флаг --tag учитывается только при --deployment all");
+    }
+
+    // Заменяем алиас на имя деплоя из настроек
+    let deployment = if deployment == "all"
+        || deployment == "failed"
+        || config::is_deployment_pattern(deployment)
+    {
+        deployment
+    } else {
+        resolve_deployment_alias(app_context, deployment)
+    };
+
     trace!(
-        "Запуск команды для деплоя '{}', событие: {:?}",
+        "Запуск команды для деплоя '{}', события: {:?}",
         deployment,
-        event
+        events
     );
 
-    // Проверяем на специальное значение "all"
+    // Повтор доступен только для одного события одного деплоя
+    let special =
+        deployment == "all" || deployment == "failed" || config::is_deployment_pattern(deployment);
+    if options.repeat.is_some() && (special || events.len() != 1) {
+        error!("Флаг --repeat требует одного деплоя и одного события (--event)");
+        exit_with(DeployError::Config);
+    }
+
+    // В режиме dry-run только выводим план выполнения
+    if options.dry_run {
+        let config = match deployment {
+            "all" => all_deployments_config(app_context, tags),
+            "failed" => failed_deployments_config(app_context),
+            pattern if config::is_deployment_pattern(pattern) => {
+                matching_deployments_config(app_context, pattern)
+            }
+            name => {
+                ensure_deployment_exists(app_context, name);
+                let mut config = app_context.config.clone();
+                config.deployments.retain(|d| d.name == name);
+                config
+            }
+        };
+
+        info!("Вывод плана выполнения без запуска команд");
+        if !run::show_plan(
+            &config,
+            history_path,
+            events,
+            app_context.parallel_execution,
+            options,
+        )
+        .await
+        {
+            exit_with(DeployError::CommandFailure);
+        }
+        return;
+    }
+
+    // Проверяем на специальные значения "all" и "failed"
     if deployment == "all" {
         info!("Запуск всех доступных деплоев из конфигурации");
+        let config = all_deployments_config(app_context, tags);
+        ensure_run_allowed(history_path, &config.deployments, options.force).await;
+        confirm_protected_deployments(app_context, &config, yes);
+        run_deployments(app_context, &config, events, options).await;
+    } else if deployment == "failed" {
+        let config = failed_deployments_config(app_context);
+        if config.deployments.is_empty() {
+            info!("Нет деплоев, завершившихся с ошибкой при последнем полном запуске");
+            return;
+        }
+        ensure_run_allowed(history_path, &config.deployments, options.force).await;
+        confirm_protected_deployments(app_context, &config, yes);
+
+        info!(
+            "Повторный запуск деплоев, завершившихся с ошибкой: {}",
+            config
+                .deployments
+                .iter()
+                .map(|d| d.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        run_deployments(app_context, &config, events, options).await;
+    } else if config::is_deployment_pattern(deployment) {
+        let config = matching_deployments_config(app_context, deployment);
+        ensure_run_allowed(history_path, &config.deployments, options.force).await;
+        confirm_protected_deployments(app_context, &config, yes);
+
+        info!(
+            "Запуск деплоев, соответствующих шаблону '{}': {}",
+            deployment,
+            deployment_names(&config).join(", ")
+        );
+        run_deployments(app_context, &config, events, options).await;
+    } else if events.is_empty() {
+        ensure_deployment_exists(app_context, deployment);
+        ensure_run_allowed(
+            history_path,
+            app_context.config.find_deployment(deployment),
+            options.force,
+        )
+        .await;
+        confirm_protected_deployment(app_context, deployment, yes);
+
+        // Если событие не указано, запускаем все события последовательно
+        info!("Запуск всех событий для деплоя '{}'", deployment);
+        run::run_all_events(&app_context.config, deployment, history_path, options).await;
+    } else {
+        ensure_deployment_exists(app_context, deployment);
+        ensure_run_allowed(
+            history_path,
+            app_context.config.find_deployment(deployment),
+            options.force,
+        )
+        .await;
+        confirm_protected_deployment(app_context, deployment, yes);
+
+        if let Some(times) = options.repeat {
+            info!(
+                "Повтор события '{}' деплоя '{}' {} раз",
+                events[0], deployment, times
+            );
+            run::repeat_event(
+                &app_context.config,
+                deployment,
+                &events[0],
+                history_path,
+                options,
+            )
+            .await;
+            return;
+        }
+
+        // Запускаем указанные события в заданном порядке; при ошибке выполнение прерывается
+        for (index, event_name) in events.iter().enumerate() {
+            if index > 0 {
+                run::ensure_config_unchanged(options);
+            }
+            info!("Запуск деплоя '{}', событие '{}'", deployment, event_name);
+            run::run_event(
+                &app_context.config,
+                deployment,
+                event_name,
+                history_path,
+                options,
+            )
+            .await;
+        }
+    }
+}
+
+/// Запускает деплой, выбранный пользователем из нумерованного списка
+///
+/// Если события не указаны в командной строке, после деплоя предлагается выбрать
+/// событие (0 - все события). Выбор доступен только при вводе из терминала.
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `events` - Имена событий из командной строки (пустой список - выбор из списка)
+/// * `yes` - Не запрашивать подтверждение для защищенных деплоев
+/// * `options` - Параметры запуска из командной строки
+async fn handle_interactive_run(
+    app_context: &AppContext,
+    events: &[String],
+    yes: bool,
+    options: &RunOptions,
+) {
+    if !io::stdin().is_terminal() {
+        error!("Интерактивный выбор деплоя доступен только при вводе из терминала");
+        exit_with(DeployError::Config);
+    }
+
+    let deployments = deployment_names(&app_context.config);
+    if deployments.is_empty() {
+        error!("В конфигурации не найдено ни одного деплоя");
+        exit_with(DeployError::DeploymentNotFound);
+    }
+
+    output::line("Деплои:");
+    for (number, name) in deployments.iter().enumerate() {
+        output::line(format!("  {}. {}", number + 1, name));
+    }
+    let deployment = deployments[prompt_selection("Номер деплоя", 1, deployments.len()) - 1];
+
+    let mut events = events.to_vec();
+    if events.is_empty() {
+        if let Some(config) = app_context.config.find_deployment(deployment) {
+            let event_names: Vec<&str> = config
+                .ordered_events()
+                .into_iter()
+                .map(|e| e.name.as_str())
+                .collect();
+
+            output::line("События:");
+            output::line("  0. все события");
+            for (number, name) in event_names.iter().enumerate() {
+                output::line(format!("  {}. {}", number + 1, name));
+            }
+            let selected = prompt_selection("Номер события", 0, event_names.len());
+            if selected > 0 {
+                events.push(event_names[selected - 1].to_string());
+            }
+        }
+    }
+
+    handle_run_command(app_context, deployment, &events, &[], yes, options).await;
+}
+
+/// Запрашивает номер пункта списка, повторяя запрос при некорректном вводе
+///
+/// # Параметры
+///
+/// * `prompt` - Текст запроса
+/// * `min` - Минимальный допустимый номер
+/// * `max` - Максимальный допустимый номер
+///
+/// # Возвращаемое значение
+///
+/// Выбранный номер
+fn prompt_selection(prompt: &str, min: usize, max: usize) -> usize {
+    match read_selection(&mut io::stdin().lock(), prompt, min, max) {
+        Some(selected) => selected,
+        None => {
+            error!("Выбор не выполнен");
+            exit_with(DeployError::Interrupted);
+        }
+    }
+}
+
+/// Читает номер пункта списка из источника ввода, повторяя запрос при некорректном вводе
+///
+/// # Параметры
+///
+/// * `input` - Источник ввода
+/// * `prompt` - Текст запроса
+/// * `min` - Минимальный допустимый номер
+/// * `max` - Максимальный допустимый номер
+///
+/// # Возвращаемое значение
+///
+/// Выбранный номер или None, если ввод закончился или не может быть прочитан
+fn read_selection(input: &mut impl BufRead, prompt: &str, min: usize, max: usize) -> Option<usize> {
+    loop {
+        print!("{} ({}-{}): ", prompt, min, max);
+        let _ = io::stdout().flush();
+
+        let mut answer = String::new();
+        match input.read_line(&mut answer) {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => {}
+        }
+
+        match parse_selection(&answer, min, max) {
+            Some(selected) => return Some(selected),
+            None => output::line(format!("Введите число от {} до {}", min, max)),
+        }
+    }
+}
+
+/// Разбирает номер пункта списка, введенный пользователем
+///
+/// # Параметры
+///
+/// * `input` - Введенная строка
+/// * `min` - Минимальный допустимый номер
+/// * `max` - Максимальный допустимый номер
+///
+/// # Возвращаемое значение
+///
+/// Номер пункта или None, если ввод не является числом из диапазона
+fn parse_selection(input: &str, min: usize, max: usize) -> Option<usize> {
+    input
+        .trim()
+        .parse()
+        .ok()
+        .filter(|selected| (min..=max).contains(selected))
+}
+
+/// Запускает деплои из файла со списком имен
+///
+/// Все имена проверяются до начала запуска; деплои выполняются в порядке файла
+/// в выбранном режиме (параллельно или последовательно).
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `path` - Путь к файлу со списком деплоев
+/// * `events` - Имена событий в порядке выполнения (пустой список - все события)
+/// * `tags` - Теги из командной строки (не используются при запуске из файла)
+/// * `yes` - Не запрашивать подтверждение для защищенных деплоев
+/// * `options` - Параметры запуска из командной строки
+async fn handle_deployment_file_run(
+    app_context: &AppContext,
+    path: &str,
+    events: &[String],
+    tags: &[String],
+    yes: bool,
+    options: &RunOptions,
+) {
+    if !tags.is_empty() {
+        warn!("Флаг --tag учитывается только при --deployment all");
+    }
+
+    let config = deployment_file_config(app_context, path);
+
+    if options.dry_run {
+        info!("Вывод плана выполнения без запуска команд");
+        if !run::show_plan(
+            &config,
+            &app_context.settings.history_file,
+            events,
+            app_context.parallel_execution,
+            options,
+        )
+        .await
+        {
+            exit_with(DeployError::CommandFailure);
+        }
+        return;
+    }
+
+    ensure_run_allowed(
+        &app_context.settings.history_file,
+        &config.deployments,
+        options.force,
+    )
+    .await;
+    confirm_protected_deployments(app_context, &config, yes);
+
+    info!(
+        "Запуск деплоев из файла {}: {}",
+        path,
+        deployment_names(&config).join(", ")
+    );
+    run_deployments(app_context, &config, events, options).await;
+}
+
+/// Читает имена деплоев из файла со списком
+///
+/// Пустые строки и строки, начинающиеся с `#`, пропускаются.
+///
+/// # Параметры
+///
+/// * `path` - Путь к файлу со списком деплоев
+///
+/// # Возвращаемое значение
+///
+/// Имена деплоев в порядке файла
+fn read_deployment_list(path: &str) -> anyhow::Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Не удалось прочитать файл со списком деплоев {}", path))?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+/// Формирует конфигурацию из деплоев, перечисленных в файле
+///
+/// Завершает программу с ошибкой, если файл не удалось прочитать, он пуст
+/// или содержит имена несуществующих деплоев. Повторные имена пропускаются.
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `path` - Путь к файлу со списком деплоев
+///
+/// # Возвращаемое значение
+///
+/// Копия конфигурации с деплоями в порядке файла
+fn deployment_file_config(app_context: &AppContext, path: &str) -> Config {
+    let names = match read_deployment_list(path) {
+        Ok(names) => names,
+        Err(e) => {
+            error!("{:#}", e);
+            exit_with(DeployError::Config);
+        }
+    };
+
+    if names.is_empty() {
+        error!("Файл со списком деплоев {} не содержит имен", path);
+        exit_with(DeployError::DeploymentNotFound);
+    }
+
+    let mut missing = false;
+    let mut deployments = Vec::new();
+    for name in &names {
+        let name = app_context.settings.resolve_deployment_alias(name);
+        match app_context.config.find_deployment(name) {
+            Some(_)
+                if deployments
+                    .iter()
+                    .any(|d: &config::Deployment| d.name == name) =>
+            {
+                warn!(
+                    "Деплой '{}' указан в файле {} повторно, пропускаем",
+                    name, path
+                );
+            }
+            Some(deployment) => deployments.push(deployment.clone()),
+            None => {
+                error!("{}", app_context.config.deployment_not_found_message(name));
+                missing = true;
+            }
+        }
+    }
+
+    if missing {
+        exit_with(DeployError::DeploymentNotFound);
+    }
+
+    let mut config = app_context.config.clone();
+    config.deployments = deployments;
+    config
+}
+
+/// Завершает программу, если деплой с указанным именем отсутствует в конфигурации
+///
+/// В сообщении об ошибке предлагается ближайшее по написанию имя деплоя.
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `name` - Имя деплоя
+fn ensure_deployment_exists(app_context: &AppContext, name: &str) {
+    if app_context.config.find_deployment(name).is_none() {
+        error!("{}", app_context.config.deployment_not_found_message(name));
+        exit_with(DeployError::DeploymentNotFound);
+    }
+}
+
+/// Проверяет, что деплои можно запускать сейчас
+///
+/// Запуск запрещен вне окна обслуживания деплоя (`maintenance_window`), раньше
+/// минимального интервала после последнего успешного запуска (`min_interval_secs`)
+/// и при незафиксированных изменениях git в рабочей директории (`require_clean_git`).
+/// С флагом `--force` нарушения только выводятся как предупреждения, иначе
+/// программа завершается с ошибкой.
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории деплоев
+/// * `deployments` - Деплои для запуска
+/// * `force` - Разрешить запуск несмотря на ограничения
+async fn ensure_run_allowed<'a>(
+    history_path: &str,
+    deployments: impl IntoIterator<Item = &'a config::Deployment>,
+    force: bool,
+) {
+    let now = chrono::Local::now();
+
+    for deployment in deployments {
+        let violations = [
+            maintenance_window_violation(deployment, now.naive_local()),
+            min_interval_violation(history_path, deployment, now.timestamp()),
+            dirty_git_violation(deployment).await,
+        ];
+
+        for violation in violations.into_iter().flatten() {
+            if force {
+                warn!("{} (запуск разрешен флагом --force)", violation);
+            } else {
+                error!("{}. Используйте --force для запуска", violation);
+                exit_with(DeployError::Config);
+            }
+        }
+    }
+}
+
+/// Проверяет, что текущее время попадает в окно обслуживания деплоя
+///
+/// # Параметры
+///
+/// * `deployment` - Деплой для запуска
+/// * `now` - Текущие локальные дата и время
+///
+/// # Возвращаемое значение
+///
+/// Описание нарушения или None, если запуск разрешен
+fn maintenance_window_violation(
+    deployment: &config::Deployment,
+    now: chrono::NaiveDateTime,
+) -> Option<String> {
+    let window = match deployment.maintenance_window() {
+        Ok(window) => window?,
+        Err(e) => {
+            error!("{:#}", e);
+            exit_with(DeployError::Config);
+        }
+    };
+
+    if window.contains(now) {
+        return None;
+    }
+
+    Some(format!(
+        "Деплой '{}' можно запускать только в окне обслуживания '{}', сейчас {}",
+        deployment.name,
+        deployment.maintenance_window.as_deref().unwrap_or_default(),
+        now.format("%a %H:%M")
+    ))
+}
+
+/// Проверяет, что рабочая директория деплоя не содержит незафиксированных изменений git
+///
+/// Если директория не является репозиторием git, проверка пропускается с предупреждением.
+///
+/// # Параметры
+///
+/// * `deployment` - Деплой для запуска
+///
+/// # Возвращаемое значение
+///
+/// Описание нарушения или None, если запуск разрешен
+async fn dirty_git_violation(deployment: &config::Deployment) -> Option<String> {
+    if !deployment.require_clean_git.unwrap_or(false) {
+        return None;
+    }
+
+    let working_dir = deployment.working_dir.as_deref();
+    let Some(changes) = commands::git_uncommitted_changes(working_dir).await else {
+        warn!(
+            "Директория '{}' деплоя '{}' не является репозиторием git, проверка require_clean_git пропущена",
+            working_dir.unwrap_or("."),
+            deployment.name
+        );
+        return None;
+    };
+
+    if changes.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "Рабочая директория деплоя '{}' содержит незафиксированные изменения git ({} файлов): {}",
+        deployment.name,
+        changes.len(),
+        changes.join(", ")
+    ))
+}
+
+/// Проверяет, что с последнего успешного запуска деплоя прошел минимальный интервал
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории деплоев
+/// * `deployment` - Деплой для запуска
+/// * `now` - Текущее время (UNIX timestamp)
+///
+/// # Возвращаемое значение
+///
+/// Описание нарушения или None, если запуск разрешен
+fn min_interval_violation(
+    history_path: &str,
+    deployment: &config::Deployment,
+    now: i64,
+) -> Option<String> {
+    let min_interval = deployment.min_interval_secs?;
+
+    let last_success = match storage::last_success_timestamp(history_path, &deployment.name) {
+        Ok(last_success) => last_success?,
+        Err(e) => {
+            warn!(
+                "Не удалось проверить min_interval_secs деплоя '{}': {:#}",
+                deployment.name, e
+            );
+            return None;
+        }
+    };
+
+    let elapsed = u64::try_from(now)
+        .unwrap_or_default()
+        .saturating_sub(last_success);
+    if elapsed >= min_interval {
+        return None;
+    }
+
+    Some(format!(
+        "Деплой '{}' успешно выполнялся {} с назад, повторный запуск возможен через {} с (min_interval_secs: {})",
+        deployment.name,
+        elapsed,
+        min_interval - elapsed,
+        min_interval
+    ))
+}
+
+/// Запрашивает подтверждение запуска для защищенных деплоев из конфигурации
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `config` - Конфигурация с деплоями для запуска
+/// * `yes` - Подтверждение уже получено флагом `--yes`
+fn confirm_protected_deployments(app_context: &AppContext, config: &Config, yes: bool) {
+    for deployment in &config.deployments {
+        confirm_protected_deployment(app_context, &deployment.name, yes);
+    }
+}
+
+/// Запрашивает ввод имени защищенного деплоя для подтверждения запуска
+///
+/// Завершает программу, если введенное имя не совпадает с именем деплоя.
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `deployment` - Имя деплоя
+/// * `yes` - Подтверждение уже получено флагом `--yes`
+fn confirm_protected_deployment(app_context: &AppContext, deployment: &str, yes: bool) {
+    if !app_context.settings.is_protected_deployment(deployment) {
+        return;
+    }
+
+    if yes {
+        info!(
+            "Запуск защищенного деплоя '{}' подтвержден флагом --yes",
+            deployment
+        );
+        return;
+    }
+
+    print!(
+        "Деплой '{}' защищен. Введите его имя для подтверждения запуска: ",
+        deployment
+    );
+    let _ = io::stdout().flush();
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() || answer.trim() != deployment {
+        error!("Запуск защищенного деплоя '{}' не подтвержден", deployment);
+        exit_with(DeployError::Interrupted);
+    }
+
+    info!("Запуск защищенного деплоя '{}' подтвержден", deployment);
+}
+
+/// Запускает все деплои из переданной конфигурации
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `config` - Конфигурация с деплоями для запуска
+/// * `events` - Имена событий в порядке выполнения (пустой список - все события)
+/// * `options` - Параметры запуска из командной строки
+async fn run_deployments(
+    app_context: &AppContext,
+    config: &Config,
+    events: &[String],
+    options: &RunOptions,
+) {
+    let history_path = &app_context.settings.history_file;
+
+    if events.is_empty() {
         // Передаем опцию parallel_execution для определения режима выполнения
         run::run_all_deployments(
-            &app_context.config,
+            config,
             history_path,
-            event.as_deref(),
+            None,
             app_context.parallel_execution,
+            options,
         )
         .await;
     } else {
-        // Если событие указано, запускаем только его
-        if let Some(event_name) = event {
-            info!("Запуск деплоя '{}', событие '{}'", deployment, event_name);
-            run::run_event(&app_context.config, deployment, event_name, history_path).await;
-        } else {
-            // Если событие не указано, запускаем все события последовательно
-            info!("Запуск всех событий для деплоя '{}'", deployment);
-            run::run_all_events(&app_context.config, deployment, history_path).await;
+        // Выполняем указанные события по очереди для всех деплоев
+        for (index, event_name) in events.iter().enumerate() {
+            if index > 0 {
+                run::ensure_config_unchanged(options);
+            }
+            run::run_all_deployments(
+                config,
+                history_path,
+                Some(event_name),
+                app_context.parallel_execution,
+                options,
+            )
+            .await;
         }
     }
 }
 
+/// Формирует конфигурацию из деплоев для запуска `all`
+///
+/// Деплои с `exclude_from_all: true` исключаются, они запускаются только по имени.
+/// При указании тегов остаются деплои, у которых есть хотя бы один из них.
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `tags` - Теги для отбора деплоев (пустой список - без отбора)
+///
+/// # Возвращаемое значение
+///
+/// Копия конфигурации без исключенных деплоев
+fn all_deployments_config(app_context: &AppContext, tags: &[String]) -> Config {
+    let mut config = app_context.config.clone();
+    let (included, excluded): (Vec<_>, Vec<_>) = config
+        .deployments
+        .into_iter()
+        .partition(|d| !d.exclude_from_all.unwrap_or(false));
+
+    if !excluded.is_empty() {
+        info!(
+            "Деплои, исключенные из запуска all: {}",
+            excluded
+                .iter()
+                .map(|d| d.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    config.deployments = included;
+
+    if !tags.is_empty() {
+        config.deployments.retain(|d| has_any_tag(d, tags));
+        if config.deployments.is_empty() {
+            error!("Нет деплоев с тегами: {}", tags.join(", "));
+            exit_with(DeployError::DeploymentNotFound);
+        }
+        info!(
+            "Деплои с тегами {}: {}",
+            tags.join(", "),
+            deployment_names(&config).join(", ")
+        );
+    }
+
+    config
+}
+
+/// Проверяет, есть ли у деплоя хотя бы один из указанных тегов
+///
+/// # Параметры
+///
+/// * `deployment` - Деплой для проверки
+/// * `tags` - Теги для отбора
+///
+/// # Возвращаемое значение
+///
+/// true, если у деплоя есть хотя бы один из тегов
+fn has_any_tag(deployment: &config::Deployment, tags: &[String]) -> bool {
+    deployment
+        .tags
+        .as_ref()
+        .is_some_and(|deployment_tags| deployment_tags.iter().any(|tag| tags.contains(tag)))
+}
+
+/// Формирует конфигурацию из деплоев, последний полный запуск которых завершился ошибкой
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+///
+/// # Возвращаемое значение
+///
+/// Копия конфигурации, содержащая только деплои с ошибками
+fn failed_deployments_config(app_context: &AppContext) -> Config {
+    let failed = match storage::latest_failed_deployments(&app_context.settings.history_file) {
+        Ok(failed) => failed,
+        Err(e) => {
+            error!("Ошибка загрузки истории деплоев: {}", e);
+            exit_with(DeployError::Config);
+        }
+    };
+
+    for name in &failed {
+        if app_context.config.find_deployment(name).is_none() {
+            warn!(
+                "Деплой '{}' из истории не найден в конфигурации, пропускаем",
+                name
+            );
+        }
+    }
+
+    let mut config = app_context.config.clone();
+    config.deployments.retain(|d| failed.contains(&d.name));
+    config
+}
+
+/// Формирует конфигурацию из деплоев, имена которых соответствуют шаблону
+///
+/// Завершает программу с ошибкой, если ни один деплой не соответствует шаблону.
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `pattern` - Шаблон имени деплоя с `*` и `?`
+///
+/// # Возвращаемое значение
+///
+/// Копия конфигурации, содержащая только подходящие деплои
+fn matching_deployments_config(app_context: &AppContext, pattern: &str) -> Config {
+    let mut config = app_context.config.clone();
+    config
+        .deployments
+        .retain(|d| glob_matches(pattern, &d.name));
+
+    if config.deployments.is_empty() {
+        error!("Нет деплоев, соответствующих шаблону '{}'", pattern);
+        exit_with(DeployError::DeploymentNotFound);
+    }
+
+    config
+}
+
+/// Возвращает имена деплоев конфигурации
+fn deployment_names(config: &Config) -> Vec<&str> {
+    config.deployments.iter().map(|d| d.name.as_str()).collect()
+}
+
+/// Разрешает алиас деплоя из настроек в имя деплоя
+///
+/// Завершает программу с ошибкой, если алиас указывает на несуществующий деплой.
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `deployment` - Алиас или имя деплоя
+///
+/// # Возвращаемое значение
+///
+/// Имя деплоя
+fn resolve_deployment_alias<'a>(app_context: &'a AppContext, deployment: &'a str) -> &'a str {
+    let resolved = app_context.settings.resolve_deployment_alias(deployment);
+    if resolved == deployment {
+        return deployment;
+    }
+
+    if !app_context
+        .config
+        .deployments
+        .iter()
+        .any(|d| d.name == resolved)
+    {
+        error!(
+            "Алиас '{}' указывает на несуществующий деплой '{}'",
+            deployment, resolved
+        );
+        exit_with(DeployError::DeploymentNotFound);
+    }
+
+    info!("Алиас '{}' соответствует деплою '{}'", deployment, resolved);
+    resolved
+}
+
 /// Отображает список всех доступных деплоев
 ///
 /// # Параметры
 ///
 /// * `app_context` - Контекст приложения
-fn handle_list_command(app_context: &AppContext) {
+/// * `with_status` - Показать статус последнего запуска из истории
+/// * `filter` - Подстрока для отбора деплоев, событий и команд
+fn handle_list_command(app_context: &AppContext, with_status: bool, filter: Option<&str>) {
     info!("Отображение списка доступных деплоев");
-    run::list_deployments(&app_context.config);
+    let history_path = with_status.then_some(app_context.settings.history_file.as_str());
+    run::list_deployments(&app_context.config, history_path, filter);
 }
 
 /// Создает шаблон деплоя
@@ -163,10 +1134,30 @@ fn handle_create_command(deployment: &str, config_path: &str) {
 /// # Параметры
 ///
 /// * `app_context` - Контекст приложения
-/// * `deployment` - Имя деплоя
+/// * `deployment` - Имя деплоя или шаблон имени с `*` и `?`
 fn handle_verify_command(app_context: &AppContext, deployment: &str) {
-    info!("Проверка конфигурации деплоя '{}'", deployment);
-    run::verify_deployment_config(&app_context.config, deployment);
+    if !config::is_deployment_pattern(deployment) {
+        info!("Проверка конфигурации деплоя '{}'", deployment);
+        run::verify_deployment_config(&app_context.config, deployment);
+        return;
+    }
+
+    let names: Vec<&str> = app_context
+        .config
+        .matching_deployments(deployment)
+        .into_iter()
+        .map(|d| d.name.as_str())
+        .collect();
+    if names.is_empty() {
+        error!("Нет деплоев, соответствующих шаблону '{}'", deployment);
+        exit_with(DeployError::DeploymentNotFound);
+    }
+
+    info!(
+        "Проверка конфигурации деплоев, соответствующих шаблону '{}'",
+        deployment
+    );
+    run::verify_deployment_configs(&app_context.config, &names);
 }
 
 /// Отображает историю деплоя
@@ -175,13 +1166,37 @@ fn handle_verify_command(app_context: &AppContext, deployment: &str) {
 ///
 /// * `app_context` - Контекст приложения
 /// * `deployment` - Имя деплоя
+/// * `event` - Имя события для отбора записей
 /// * `limit` - Количество записей для отображения
-fn handle_history_command(app_context: &AppContext, deployment: &str, limit: usize) {
+fn handle_history_command(
+    app_context: &AppContext,
+    deployment: &str,
+    event: Option<&str>,
+    limit: usize,
+) {
+    info!(
+        "Отображение истории деплоя '{}' (событие: {}, лимит: {})",
+        deployment,
+        event.unwrap_or("все"),
+        limit
+    );
+    run::show_deployment_history(&app_context.settings.history_file, deployment, event, limit);
+}
+
+/// Показывает сравнение истории двух деплоев
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `a` - Имя первого деплоя
+/// * `b` - Имя второго деплоя
+/// * `limit` - Количество последних записей каждого деплоя для сравнения
+fn handle_history_diff_command(app_context: &AppContext, a: &str, b: &str, limit: usize) {
     info!(
-        "Отображение истории деплоя '{}' (лимит: {})",
-        deployment, limit
+        "Сравнение истории деплоев '{}' и '{}' (лимит: {})",
+        a, b, limit
     );
-    run::show_deployment_history(&app_context.settings.history_file, deployment, limit);
+    run::show_history_diff(&app_context.settings.history_file, a, b, limit);
 }
 
 /// Очищает историю деплоев
@@ -199,6 +1214,163 @@ fn handle_clear_history_command(app_context: &AppContext, deployment: &Option<St
     run::clear_deployment_history(&app_context.settings.history_file, deployment.as_deref());
 }
 
+/// Показывает изменения конфигурации деплоя с последнего запуска
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `deployment` - Имя деплоя
+fn handle_config_diff_command(app_context: &AppContext, deployment: &str) {
+    info!(
+        "Сравнение конфигурации деплоя '{}' с последним запуском",
+        deployment
+    );
+    run::show_config_diff(
+        &app_context.config,
+        &app_context.settings.history_file,
+        deployment,
+    );
+}
+
+/// Приводит конфигурацию к каноническому виду
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `format` - Формат конфигурации из командной строки (по умолчанию - по расширению)
+/// * `in_place` - Перезаписать файл конфигурации вместо вывода в стандартный вывод
+fn handle_config_fmt_command(
+    app_context: &AppContext,
+    format: Option<ConfigFormat>,
+    in_place: bool,
+) {
+    let path = &app_context.config_path;
+    let format = format.unwrap_or_else(|| ConfigFormat::from_path(path));
+
+    if in_place && path == config::STDIN_CONFIG_PATH {
+        error!("Конфигурацию из стандартного ввода нельзя перезаписать (--in-place)");
+        exit_with(DeployError::Config);
+    }
+
+    let content = match app_context.config.render(format) {
+        Ok(content) => content,
+        Err(e) => {
+            error!("{:#}", e);
+            exit_with(DeployError::Config);
+        }
+    };
+
+    if !in_place {
+        print!("{}", content);
+        return;
+    }
+
+    if let Err(e) = std::fs::write(path, content) {
+        error!("Не удалось записать конфигурацию в файл {}: {}", path, e);
+        exit_with(DeployError::Config);
+    }
+    info!("Конфигурация {} отформатирована", path);
+}
+
+/// Удаляет устаревшие записи истории деплоев
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `older_than` - Возраст записей для удаления
+/// * `deployment` - Опциональное имя деплоя
+fn handle_prune_history_command(
+    app_context: &AppContext,
+    older_than: Duration,
+    deployment: &Option<String>,
+) {
+    info!(
+        "Удаление записей истории старше {} секунд (деплой: {})",
+        older_than.as_secs(),
+        deployment.as_deref().unwrap_or("все")
+    );
+    run::prune_deployment_history(
+        &app_context.settings.history_file,
+        older_than,
+        deployment.as_deref(),
+    );
+}
+
+/// Выполняет событие проверки работоспособности деплоя
+///
+/// Код завершения программы отражает результат события: 0 при успехе,
+/// код ошибки выполнения команд при неудаче.
+///
+/// # Параметры
+///
+/// * `app_context` - Контекст приложения
+/// * `deployment` - Имя или алиас деплоя
+/// * `event` - Событие проверки (None - `probe_event` из настроек)
+async fn handle_probe_command(app_context: &AppContext, deployment: &str, event: Option<&str>) {
+    let deployment = resolve_deployment_alias(app_context, deployment);
+    ensure_deployment_exists(app_context, deployment);
+
+    let event = event.unwrap_or(&app_context.settings.probe_event);
+    info!(
+        "Проверка работоспособности деплоя '{}', событие '{}'",
+        deployment, event
+    );
+
+    run::run_event(
+        &app_context.config,
+        deployment,
+        event,
+        &app_context.settings.history_file,
+        &RunOptions::default(),
+    )
+    .await;
+}
+
+/// Выводит JSON Schema формата конфигурации
+///
+/// # Параметры
+///
+/// * `output` - Файл для сохранения схемы (None - стандартный вывод)
+pub fn handle_schema_command(output: Option<&str>) {
+    // Команда выполняется до настройки логирования, поэтому ошибка выводится напрямую
+    if let Err(e) = schema::write_schema(output) {
+        eprintln!("{:#}", e);
+        exit_with(DeployError::Config);
+    }
+}
+
+/// Проверяет файл настроек и выводит найденные проблемы
+///
+/// Команда выполняется до настройки логирования, поэтому результат выводится напрямую.
+/// При наличии проблем программа завершается с кодом ошибки конфигурации.
+pub fn handle_validate_settings_command() {
+    let settings = match get_settings(DEFAULT_SETTINGS_PATH) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            exit_with(DeployError::Config);
+        }
+    };
+
+    let issues = settings.validate();
+    if issues.is_empty() {
+        output::line(output::success(&format!(
+            "Настройки {} корректны",
+            DEFAULT_SETTINGS_PATH
+        )));
+        return;
+    }
+
+    output::line(output::failure(&format!(
+        "Проблемы в настройках {}:",
+        DEFAULT_SETTINGS_PATH
+    )));
+    for issue in &issues {
+        output::line(format!("  - {}", issue));
+    }
+    exit_with(DeployError::Config);
+}
+
 /// Выполняет команду в зависимости от аргументов командной строки
 pub async fn execute_command(cli: &Cli, app_context: &AppContext) {
     let start_time = Instant::now();
@@ -206,24 +1378,149 @@ pub async fn execute_command(cli: &Cli, app_context: &AppContext) {
     debug!("Начало выполнения команды: {:?}", cli.command);
 
     match &cli.command {
-        crate::cli::Command::Run { deployment, event } => {
-            handle_run_command(app_context, deployment, event).await;
+        crate::cli::Command::Run {
+            deployment,
+            deployment_file,
+            interactive,
+            event,
+            since_last_success,
+            on_missing_var,
+            tags,
+            yes,
+            report_file,
+            timings: timings_file,
+            dry_run,
+            check_commands,
+            print_env,
+            buffer_output,
+            prompt_on_fail,
+            force,
+            inputs,
+            vars,
+            env,
+            strict_ignored,
+            ignore_config_change,
+            simulate,
+            repeat,
+            repeat_delay,
+            ..
+        } => {
+            let options = RunOptions {
+                inputs: inputs.iter().cloned().collect(),
+                // Значения --var поддерживают подстановку переменных окружения ${VAR}
+                variables: vars
+                    .iter()
+                    .map(|(key, value)| (key.clone(), util::expand_env_vars(value)))
+                    .collect(),
+                env: env.clone(),
+                since_last_success: *since_last_success,
+                on_missing_var: *on_missing_var,
+                dry_run: *dry_run || *print_env,
+                check_commands: *check_commands,
+                print_env: *print_env,
+                buffer_output: *buffer_output,
+                prompt_on_fail: *prompt_on_fail,
+                force: *force,
+                strict_ignored: *strict_ignored,
+                config_fingerprint: if *ignore_config_change {
+                    None
+                } else {
+                    config::ConfigFingerprint::new(
+                        &app_context.config_path,
+                        cli.config_format,
+                        app_context.config_hash,
+                    )
+                },
+                simulate: *simulate,
+                repeat: *repeat,
+                repeat_delay: repeat_delay.unwrap_or_default(),
+            };
+            if let Some(exit_code) = simulate {
+                warn!(
+                    "Режим имитации: команды не запускаются и завершаются с кодом {}",
+                    exit_code
+                );
+            }
+            if let Some(path) = report_file {
+                report::enable(path);
+            }
+            if let Some(path) = timings_file {
+                timings::enable(path);
+            }
+            if let Some(endpoint) = &app_context.settings.otel_endpoint {
+                telemetry::enable(endpoint);
+            }
+            if *interactive {
+                handle_interactive_run(app_context, event, *yes, &options).await;
+            } else if let Some(path) = deployment_file {
+                handle_deployment_file_run(app_context, path, event, tags, *yes, &options).await;
+            } else if let Some(deployment) = deployment {
+                handle_run_command(app_context, deployment, event, tags, *yes, &options).await;
+            }
+            telemetry::finish().await;
+
+            let ignored = executor::runner::ignored_failure_count();
+            if options.strict_ignored && ignored > 0 {
+                error!(
+                    "Проигнорировано ошибок команд (ignore_errors): {}, завершение с ошибкой из-за --strict-ignored",
+                    ignored
+                );
+                exit_with(DeployError::IgnoredFailures);
+            }
         }
-        crate::cli::Command::List => {
-            handle_list_command(app_context);
+        crate::cli::Command::List {
+            with_status,
+            filter,
+        } => {
+            handle_list_command(app_context, *with_status, filter.as_deref());
         }
         crate::cli::Command::Create { deployment } => {
-            handle_create_command(deployment, &cli.config);
+            handle_create_command(deployment, &app_context.config_path);
         }
         crate::cli::Command::Verify { deployment } => {
             handle_verify_command(app_context, deployment);
         }
-        crate::cli::Command::History { deployment, limit } => {
-            handle_history_command(app_context, deployment, *limit);
+        crate::cli::Command::History {
+            deployment,
+            event,
+            limit,
+        } => {
+            handle_history_command(app_context, deployment, event.as_deref(), *limit);
+        }
+        crate::cli::Command::HistoryDiff { a, b, limit } => {
+            handle_history_diff_command(app_context, a, b, *limit);
         }
         crate::cli::Command::ClearHistory { deployment } => {
             handle_clear_history_command(app_context, deployment);
         }
+        crate::cli::Command::Completions { shell } => {
+            print_completions(*shell, &app_context.config_path);
+        }
+        crate::cli::Command::Probe { deployment, event } => {
+            handle_probe_command(app_context, deployment, event.as_deref()).await;
+        }
+        crate::cli::Command::Schema { output } => {
+            handle_schema_command(output.as_deref());
+        }
+        crate::cli::Command::ValidateSettings => {
+            handle_validate_settings_command();
+        }
+        crate::cli::Command::PruneHistory {
+            older_than,
+            deployment,
+        } => {
+            handle_prune_history_command(app_context, *older_than, deployment);
+        }
+        crate::cli::Command::Config {
+            action: crate::cli::ConfigAction::Diff { deployment },
+        } => {
+            handle_config_diff_command(app_context, deployment);
+        }
+        crate::cli::Command::Config {
+            action: crate::cli::ConfigAction::Fmt { in_place },
+        } => {
+            handle_config_fmt_command(app_context, cli.config_format, *in_place);
+        }
     }
 
     let duration = start_time.elapsed();
@@ -232,3 +1529,36 @@ pub async fn execute_command(cli: &Cli, app_context: &AppContext) {
         duration.as_secs_f64()
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_selection_accepts_numbers_in_range() {
+        assert_eq!(parse_selection("1", 1, 3), Some(1));
+        assert_eq!(parse_selection(" 3\n", 1, 3), Some(3));
+        assert_eq!(parse_selection("0\r\n", 0, 2), Some(0));
+    }
+
+    #[test]
+    fn parse_selection_rejects_invalid_input() {
+        assert_eq!(parse_selection("0", 1, 3), None);
+        assert_eq!(parse_selection("4", 1, 3), None);
+        assert_eq!(parse_selection("-1", 0, 3), None);
+        assert_eq!(parse_selection("two", 1, 3), None);
+        assert_eq!(parse_selection("", 1, 3), None);
+    }
+
+    #[test]
+    fn read_selection_repeats_prompt_until_valid_input() {
+        let mut input = io::Cursor::new("abc\n7\n2\n");
+        assert_eq!(read_selection(&mut input, "Номер", 1, 3), Some(2));
+    }
+
+    #[test]
+    fn read_selection_stops_at_end_of_input() {
+        let mut input = io::Cursor::new("9\n");
+        assert_eq!(read_selection(&mut input, "Номер", 1, 3), None);
+    }
+}
@@ -1,4 +1,9 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::config::{ConfigFormat, MissingVarPolicy};
+use crate::output::ColorMode;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[clap(
@@ -7,13 +12,19 @@ use clap::{Parser, Subcommand};
     about = "Утилита для выполнения команд при деплое"
 )]
 pub struct Cli {
-    /// Путь к файлу конфигурации
-    #[clap(short, long, default_value = "deploy-config.yml")]
-    pub config: String,
-
-    /// Подробный вывод информации
+    /// Путь к файлу конфигурации (по умолчанию значение DC_CONFIG или deploy-config.yml);
+    /// "-" - чтение конфигурации из стандартного ввода
     #[clap(short, long)]
-    pub verbose: bool,
+    pub config: Option<String>,
+
+    /// Формат конфигурации: yaml или json (по умолчанию по расширению файла, для "-" - yaml)
+    #[clap(long, value_enum)]
+    pub config_format: Option<ConfigFormat>,
+
+    /// Уровень подробности вывода: без флага - информационные сообщения,
+    /// -v - отладочные сообщения, -vv (и больше) - трассировка
+    #[clap(short, long, action = ArgAction::Count)]
+    pub verbose: u8,
 
     /// Путь к файлу журнала
     #[clap(long, default_value = "deploy-commander.log")]
@@ -23,26 +34,169 @@ pub struct Cli {
     #[clap(short, long, help = "Включает параллельное выполнение деплоев")]
     pub parallel: Option<bool>,
 
+    /// Цветной вывод: auto (по умолчанию), always или never
+    #[clap(long, value_enum, default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+
+    /// Считать неизвестные поля конфигурации ошибкой, а не предупреждением
+    #[clap(long)]
+    pub strict_config: bool,
+
+    /// Не проверять наличие внешних команд (git, docker, ssh, rsync), например для
+    /// проверки конфигурации в окружении без этих инструментов
+    #[clap(long)]
+    pub offline: bool,
+
     /// Команда для выполнения
     #[clap(subcommand)]
     pub command: Command,
 }
 
+// Команда разбирается один раз при запуске, размер варианта Run не важен
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand, Debug)]
 pub enum Command {
     /// Запустить команды для указанного деплоя и события
     Run {
-        /// Название деплоя или специальное значение "all" для запуска всех деплоев
+        /// Название деплоя, шаблон имени с `*` и `?` (например, 'api-*'), "all" для запуска
+        /// всех деплоев или "failed" для повторного запуска деплоев, завершившихся с ошибкой
+        /// при последнем полном запуске
+        #[clap(
+            short,
+            long,
+            required_unless_present_any = ["deployment_file", "interactive"]
+        )]
+        deployment: Option<String>,
+
+        /// Файл со списком деплоев для запуска (по одному имени в строке, строки с `#` -
+        /// комментарии); деплои запускаются в порядке файла
+        #[clap(long, value_name = "PATH", conflicts_with = "deployment")]
+        deployment_file: Option<String>,
+
+        /// Выбрать деплой и событие из нумерованного списка (только в терминале)
+        #[clap(short, long, conflicts_with_all = ["deployment", "deployment_file"])]
+        interactive: bool,
+
+        /// Название события (можно указать несколько раз, события выполняются в указанном порядке;
+        /// если не указано, будут выполнены все события в порядке их определения)
         #[clap(short, long)]
-        deployment: String,
+        event: Vec<String>,
+
+        /// Выполнить только события, не завершившиеся успешно после последнего полного деплоя
+        #[clap(long, conflicts_with = "event")]
+        since_last_success: bool,
+
+        /// Поведение при отсутствии значения переменной {#VAR} (keep, empty, fail)
+        #[clap(long, value_enum)]
+        on_missing_var: Option<MissingVarPolicy>,
 
-        /// Название события (если не указано, будут выполнены все события в порядке их определения)
+        /// Тег деплоя для отбора при --deployment all (можно указать несколько раз,
+        /// запускаются деплои, у которых есть хотя бы один из тегов)
+        #[clap(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Не запрашивать подтверждение для защищенных деплоев
         #[clap(short, long)]
-        event: Option<String>,
+        yes: bool,
+
+        /// Записать результаты выполнения событий в файл в формате JUnit XML
+        #[clap(long)]
+        report_file: Option<String>,
+
+        /// Записать длительности запуска, деплоев, событий и команд в JSON-файл
+        #[clap(long, value_name = "PATH")]
+        timings: Option<String>,
+
+        /// Показать команды, которые будут выполнены, без их запуска
+        #[clap(long)]
+        dry_run: bool,
+
+        /// При --dry-run проверить, что внешние команды доступны в PATH
+        #[clap(long, requires = "dry_run")]
+        check_commands: bool,
+
+        /// Показать переменные окружения, передаваемые командам деплоя (включает --dry-run)
+        #[clap(long)]
+        print_env: bool,
+
+        /// Прервать запуск, если не найдены необходимые команды (git, docker, ssh, rsync)
+        #[clap(long)]
+        require_commands: bool,
+
+        /// При параллельном запуске выводить вывод каждого деплоя целиком по его завершении
+        /// (файлы журнала записываются без задержки)
+        #[clap(long)]
+        buffer_output: bool,
+
+        /// Запустить деплой вне его окна обслуживания (maintenance_window)
+        /// или раньше минимального интервала после успешного запуска (min_interval_secs)
+        #[clap(long)]
+        force: bool,
+
+        /// При ошибке команды спросить, повторить ее, пропустить или прервать событие
+        /// (только в терминале; без него событие прерывается)
+        #[clap(long)]
+        prompt_on_fail: bool,
+
+        /// Ответ на интерактивный запрос в формате key=value (можно указать несколько раз)
+        #[clap(long = "input", value_parser = parse_key_value)]
+        inputs: Vec<(String, String)>,
+
+        /// Завершиться с кодом 5, если ошибки команд с ignore_errors были проигнорированы
+        #[clap(long)]
+        strict_ignored: bool,
+
+        /// Имитировать выполнение: команды не запускаются, а завершаются с указанным кодом
+        /// (по умолчанию 0); подстановка переменных, порядок команд и история работают как обычно
+        #[clap(
+            long,
+            value_name = "EXIT_CODE",
+            num_args = 0..=1,
+            default_missing_value = "0"
+        )]
+        simulate: Option<i32>,
+
+        /// Выполнить событие указанное число раз подряд для нагрузочной проверки
+        /// (только для одного деплоя и одного события); в конце выводится итог запусков
+        #[clap(
+            long,
+            value_name = "N",
+            requires = "event",
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        repeat: Option<u32>,
+
+        /// Пауза между повторами --repeat (например, 30s, 5m)
+        #[clap(long, value_name = "DURATION", requires = "repeat", value_parser = parse_age)]
+        repeat_delay: Option<Duration>,
+
+        /// Не прерывать запуск нескольких деплоев, если файл конфигурации изменился во время
+        /// выполнения
+        #[clap(long)]
+        ignore_config_change: bool,
+
+        /// Переменная {#KEY} в формате KEY=VALUE, переопределяющая переменные конфигурации
+        /// и файлов переменных (можно указать несколько раз)
+        #[clap(long = "var", value_parser = parse_key_value)]
+        vars: Vec<(String, String)>,
+
+        /// Переменная окружения для всех команд в формате KEY=VALUE, переопределяющая
+        /// environment деплоя (можно указать несколько раз)
+        #[clap(long = "env", value_parser = parse_env_var)]
+        env: Vec<(String, String)>,
     },
 
     /// Вывести список доступных деплоев и событий
-    List,
+    List {
+        /// Показать статус и время последнего запуска каждого деплоя из истории
+        #[clap(long)]
+        with_status: bool,
+
+        /// Показать только деплои, события и команды, имя или текст которых содержит
+        /// подстроку (без учета регистра)
+        #[clap(long)]
+        filter: Option<String>,
+    },
 
     /// Создать новый шаблон деплоя
     Create {
@@ -53,26 +207,218 @@ pub enum Command {
 
     /// Проверить конфигурацию деплоя
     Verify {
-        /// Название деплоя для проверки
+        /// Название деплоя или шаблон имени с `*` и `?` для проверки нескольких деплоев
         #[clap(short, long)]
         deployment: String,
     },
 
+    /// Выполнить событие проверки работоспособности деплоя без вывода журнала
+    /// (код завершения 0 - событие выполнено успешно)
+    Probe {
+        /// Название деплоя
+        #[clap(short, long)]
+        deployment: String,
+
+        /// Событие проверки (по умолчанию - probe_event из настроек, "healthcheck")
+        #[clap(short, long)]
+        event: Option<String>,
+    },
+
     /// Показать историю деплоев
     History {
         /// Название деплоя для просмотра истории
         #[clap(short, long)]
         deployment: String,
 
+        /// Показать только записи указанного события
+        #[clap(short, long)]
+        event: Option<String>,
+
         /// Количество последних записей для отображения
         #[clap(short, long, default_value = "10")]
         limit: usize,
     },
 
+    /// Сравнить последние исходы событий в истории двух деплоев
+    HistoryDiff {
+        /// Название первого деплоя
+        #[clap(long)]
+        a: String,
+
+        /// Название второго деплоя
+        #[clap(long)]
+        b: String,
+
+        /// Количество последних записей каждого деплоя для сравнения
+        #[clap(short, long, default_value = "50")]
+        limit: usize,
+    },
+
     /// Очистить историю деплоев
     ClearHistory {
         /// Название деплоя для очистки истории (если не указано, очищается вся история)
         #[clap(short, long)]
         deployment: Option<String>,
     },
+
+    /// Вывести скрипт автодополнения для командной оболочки
+    Completions {
+        /// Командная оболочка (bash, zsh, fish, elvish, powershell)
+        #[clap(value_enum)]
+        shell: Shell,
+    },
+
+    /// Вывести JSON Schema формата конфигурации для проверки YAML в редакторе
+    Schema {
+        /// Файл для сохранения схемы (по умолчанию - стандартный вывод)
+        #[clap(short, long, value_name = "PATH")]
+        output: Option<String>,
+    },
+
+    /// Проверить файл настроек: доступность путей для записи и шаблоны скрытия
+    ValidateSettings,
+
+    /// Удалить записи истории старше указанного возраста
+    PruneHistory {
+        /// Возраст записей для удаления (например, 30d, 12h, 45m, 2w)
+        #[clap(long, value_parser = parse_age)]
+        older_than: Duration,
+
+        /// Название деплоя (если не указано, очищаются записи всех деплоев)
+        #[clap(short, long)]
+        deployment: Option<String>,
+    },
+
+    /// Операции с конфигурацией деплоя
+    Config {
+        #[clap(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Привести файл конфигурации к каноническому виду (порядок полей, стиль YAML)
+    Fmt {
+        /// Перезаписать файл конфигурации вместо вывода в стандартный вывод
+        #[clap(long)]
+        in_place: bool,
+    },
+
+    /// Сравнить текущую конфигурацию деплоя с использованной при последнем запуске
+    Diff {
+        /// Название деплоя
+        #[clap(short, long)]
+        deployment: String,
+    },
+}
+
+/// Разбирает аргумент вида `key=value`
+pub fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("Ожидается формат key=value, получено: '{}'", s)),
+    }
+}
+
+/// Разбирает переменную окружения вида `KEY=VALUE`
+///
+/// Имя переменной должно состоять из латинских букв, цифр и `_` и не начинаться с цифры.
+pub fn parse_env_var(s: &str) -> Result<(String, String), String> {
+    let (key, value) =
+        parse_key_value(s).map_err(|_| format!("Ожидается формат KEY=VALUE, получено: '{}'", s))?;
+
+    let valid_name = key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !key.starts_with(|c: char| c.is_ascii_digit());
+    if !valid_name {
+        return Err(format!("Недопустимое имя переменной окружения: '{}'", key));
+    }
+
+    Ok((key, value))
+}
+
+/// Разбирает возраст вида `30d`, `12h`, `45m`, `2w` или `3600s`
+pub fn parse_age(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("Неверный формат возраста: '{}'", s))?;
+
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "" | "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Неизвестная единица времени '{}', допустимы: s, m, h, d, w",
+                unit
+            ))
+        }
+    };
+
+    value
+        .checked_mul(multiplier)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Слишком большой возраст: '{}'", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbose_flags_are_counted() {
+        let verbosity = |args: &[&str]| {
+            Cli::try_parse_from(["deploy-cmd"].iter().chain(args).chain(["list"].iter()))
+                .unwrap()
+                .verbose
+        };
+
+        assert_eq!(verbosity(&[]), 0);
+        assert_eq!(verbosity(&["--verbose"]), 1);
+        assert_eq!(verbosity(&["-vv"]), 2);
+        assert_eq!(verbosity(&["-v", "--verbose", "-v"]), 3);
+    }
+
+    #[test]
+    fn parses_age_units() {
+        assert_eq!(parse_age("30d"), Ok(Duration::from_secs(30 * 24 * 60 * 60)));
+        assert_eq!(parse_age("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(parse_age("7"), Ok(Duration::from_secs(7 * 24 * 60 * 60)));
+        assert!(parse_age("10y").is_err());
+        assert!(parse_age("d").is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_age() {
+        assert_eq!(
+            parse_age("99999999999999999d"),
+            Err("Слишком большой возраст: '99999999999999999d'".to_string())
+        );
+        assert_eq!(
+            parse_age("18446744073709551615s"),
+            Ok(Duration::from_secs(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn parses_env_overrides() {
+        assert_eq!(
+            parse_env_var("APP_PORT=9090"),
+            Ok(("APP_PORT".to_string(), "9090".to_string()))
+        );
+        assert_eq!(
+            parse_env_var("_URL=http://host/?a=b"),
+            Ok(("_URL".to_string(), "http://host/?a=b".to_string()))
+        );
+        assert!(parse_env_var("APP_PORT").is_err());
+        assert!(parse_env_var("=value").is_err());
+        assert!(parse_env_var("1PORT=9090").is_err());
+        assert!(parse_env_var("APP-PORT=9090").is_err());
+    }
 }
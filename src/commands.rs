@@ -14,10 +14,13 @@
 - `execute_shell_command` - выполняет shell-команду и возвращает результат
 - `validate_command` - проверяет доступность команды без ее выполнения
 - `check_required_commands` - проверяет наличие всех необходимых инструментов
+- `git_commit_sha` - определяет текущий коммит git рабочей директории деплоя
+- `git_uncommitted_changes` - находит незафиксированные изменения git рабочей директории деплоя
 */
 
 use anyhow::Result;
-use log::{error, info};
+use log::{debug, error, info};
+use std::path::Path;
 
 use command_system::command::ShellCommand;
 use command_system::{CommandBuilder, CommandExecution, ExecutionMode};
@@ -100,12 +103,12 @@ pub async fn validate_command(command: &str) -> Result<()> {
 
 /// Проверяет доступность необходимых команд
 ///
-/// Проверяет наличие git, docker, ssh и rsync в системе,
-/// выводя предупреждение, если какие-то из них отсутствуют
+/// Проверяет наличие git, docker, ssh и rsync в системе
+/// и логирует каждую отсутствующую команду
 ///
 /// # Возвращаемое значение
 ///
-/// Всегда возвращает Ok(()), но логирует отсутствующие команды
+/// Ok(()), если все команды доступны, иначе ошибка со списком отсутствующих команд
 pub async fn check_required_commands() -> Result<()> {
     let required = ["git", "docker", "ssh", "rsync"];
     let mut missing_commands = Vec::new();
@@ -121,11 +124,72 @@ pub async fn check_required_commands() -> Result<()> {
     }
 
     if !missing_commands.is_empty() {
-        error!(
-            "Отсутствуют некоторые команды: {}. Некоторые операции могут быть недоступны.",
+        return Err(anyhow::anyhow!(
+            "Отсутствуют команды: {}",
             missing_commands.join(", ")
-        );
+        ));
     }
 
     Ok(())
 }
+
+/// Определяет хеш текущего коммита git в рабочей директории деплоя
+///
+/// # Параметры
+///
+/// * `working_dir` - Рабочая директория деплоя (None - текущая директория)
+///
+/// # Возвращаемое значение
+///
+/// Хеш коммита или None, если директория не является репозиторием git
+pub async fn git_commit_sha(working_dir: Option<&str>) -> Option<String> {
+    let mut builder = CommandBuilder::new("git_rev_parse", "git rev-parse HEAD")
+        .execution_mode(ExecutionMode::Sequential);
+    if let Some(dir) = working_dir.filter(|dir| Path::new(dir).is_dir()) {
+        builder = builder.working_dir(dir);
+    }
+
+    match builder.build().execute().await {
+        Ok(result) if result.success => {
+            let sha = result.output.trim();
+            (!sha.is_empty()).then(|| sha.to_string())
+        }
+        _ => {
+            debug!(
+                "Коммит git не определен: директория '{}' не является репозиторием",
+                working_dir.unwrap_or(".")
+            );
+            None
+        }
+    }
+}
+
+/// Находит незафиксированные изменения git в рабочей директории деплоя
+///
+/// # Параметры
+///
+/// * `working_dir` - Рабочая директория деплоя (None - текущая директория)
+///
+/// # Возвращаемое значение
+///
+/// Строки `git status --porcelain` (пустой список - нет изменений) или None,
+/// если директория не является репозиторием git
+pub async fn git_uncommitted_changes(working_dir: Option<&str>) -> Option<Vec<String>> {
+    let mut builder = CommandBuilder::new("git_status", "git status --porcelain")
+        .execution_mode(ExecutionMode::Sequential);
+    if let Some(dir) = working_dir.filter(|dir| Path::new(dir).is_dir()) {
+        builder = builder.working_dir(dir);
+    }
+
+    match builder.build().execute().await {
+        Ok(result) if result.success => Some(
+            result
+                .output
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(str::to_string)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
@@ -1,14 +1,28 @@
 use anyhow::{Context, Result};
-use log::{error, info};
+use log::{error, info, warn};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
 use std::path::Path;
+use std::time::Duration;
 
+use crate::maintenance::MaintenanceWindow;
 use crate::run::deployments;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Текущая версия схемы конфигурации
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Конфигурация деплоев
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Config {
+    /// Версия схемы конфигурации (отсутствие поля соответствует версии 0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
     pub deployments: Vec<Deployment>,
     pub variables_file: Option<String>,
 }
@@ -16,24 +30,162 @@ pub struct Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: Some(CONFIG_VERSION),
             deployments: Vec::new(),
             variables_file: None,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Deployment {
     pub name: String,
     pub description: Option<String>,
     pub working_dir: Option<String>,
+    /// Переменные окружения команд в формате `KEY=VALUE`
+    #[schemars(inner(regex(pattern = r"^[^=]+=")))]
     pub environment: Option<Vec<String>>,
     /// Опциональный путь к файлу с переменными
     pub variables_file: Option<String>,
+    /// Переменные `{#VAR}`, заданные прямо в конфигурации (файлы переменных имеют приоритет)
+    pub variables: Option<HashMap<String, String>>,
+    /// Заключать подставляемые значения `{#VAR}` в одинарные кавычки
+    pub quote_variables: Option<bool>,
+    /// Поведение при отсутствии значения переменной `{#VAR}`
+    pub on_missing_var: Option<MissingVarPolicy>,
+    /// Не запускать деплой при `run --deployment all` (запуск по имени остается доступен)
+    pub exclude_from_all: Option<bool>,
+    /// Теги для отбора деплоев при `run --deployment all --tag <тег>`
+    pub tags: Option<Vec<String>>,
+    /// Политика повторов по умолчанию для всех команд деплоя
+    pub retry_policy: Option<RetryPolicy>,
+    /// Окно обслуживания, вне которого запуск запрещен без `--force`
+    /// (`HH:MM-HH:MM` или `Mon-Fri HH:MM-HH:MM`, местное время)
+    pub maintenance_window: Option<String>,
+    /// Минимальный интервал в секундах между успешным запуском и следующим
+    /// запуском деплоя (нарушение допускается только с `--force`)
+    pub min_interval_secs: Option<u64>,
+    /// Файлы в формате `.env`, переменные из которых передаются в окружение
+    /// команд деплоя (записи `environment` имеют приоритет)
+    pub env_files: Option<Vec<String>>,
+    /// Команда, сохраняющая состояние окружения (например, дамп БД) перед
+    /// запуском всех событий деплоя
+    pub snapshot_command: Option<String>,
+    /// Команда, восстанавливающая состояние окружения, если событие деплоя
+    /// завершилось ошибкой
+    pub restore_command: Option<String>,
+    /// Команда, выводящая развернутую версию; выполняется после успешного запуска
+    /// всех событий, ее вывод сохраняется в истории
+    pub version_command: Option<String>,
+    /// Запрещать запуск (без `--force`), если в рабочей директории деплоя есть
+    /// незафиксированные изменения git
+    pub require_clean_git: Option<bool>,
+    /// Порядок выполнения событий при полном запуске (не указанные события выполняются
+    /// последними в порядке файла)
+    pub event_order: Option<Vec<String>>,
     pub events: Vec<Event>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Deployment {
+    /// Возвращает события деплоя в порядке выполнения при полном запуске
+    ///
+    /// События из `event_order` выполняются первыми в указанном порядке,
+    /// остальные - после них в порядке определения в файле.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Ссылки на события в порядке выполнения
+    pub fn ordered_events(&self) -> Vec<&Event> {
+        let order = match &self.event_order {
+            Some(order) => order,
+            None => return self.events.iter().collect(),
+        };
+
+        let mut events: Vec<&Event> = order
+            .iter()
+            .filter_map(|name| self.events.iter().find(|e| &e.name == name))
+            .collect();
+        events.extend(self.events.iter().filter(|e| !order.contains(&e.name)));
+        events
+    }
+
+    /// Разбирает окно обслуживания деплоя
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Окно обслуживания (None, если не задано) или ошибка формата
+    pub fn maintenance_window(&self) -> Result<Option<MaintenanceWindow>> {
+        self.maintenance_window
+            .as_deref()
+            .map(MaintenanceWindow::parse)
+            .transpose()
+            .with_context(|| format!("Ошибка в maintenance_window деплоя '{}'", self.name))
+    }
+
+    /// Проверяет, что `event_order` ссылается только на события деплоя
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Ошибка со списком неизвестных событий
+    pub fn check_event_order(&self) -> Result<()> {
+        let unknown: Vec<&str> = self
+            .event_order
+            .iter()
+            .flatten()
+            .filter(|name| !self.events.iter().any(|e| &e.name == *name))
+            .map(String::as_str)
+            .collect();
+
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        Err(anyhow::anyhow!(
+            "В event_order деплоя '{}' указаны несуществующие события: {}",
+            self.name,
+            unknown.join(", ")
+        ))
+    }
+}
+
+/// Поведение при отсутствии значения переменной из файла
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingVarPolicy {
+    /// Оставить шаблон в команде без изменений
+    #[default]
+    Keep,
+    /// Заменить шаблон пустой строкой
+    Empty,
+    /// Прервать выполнение до запуска команд
+    Fail,
+}
+
+/// Команда отката: одна команда или несколько шагов
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(untagged)]
+pub enum RollbackCommand {
+    /// Одна команда отката (`rollback_command: "git reset --hard HEAD~1"`)
+    Single(String),
+    /// Шаги отката в порядке выполнения
+    Steps(Vec<String>),
+}
+
+impl RollbackCommand {
+    /// Возвращает шаги отката в порядке выполнения
+    pub fn steps(&self) -> Vec<&str> {
+        match self {
+            RollbackCommand::Single(command) => vec![command.as_str()],
+            RollbackCommand::Steps(steps) => steps.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Event {
     pub name: String,
     pub description: Option<String>,
@@ -41,12 +193,16 @@ pub struct Event {
     pub fail_fast: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Command {
+    /// Имя команды, используемое в журнале вместо сгенерированного `cmd_<номер>`
+    pub name: Option<String>,
     pub command: String,
     pub description: Option<String>,
     pub ignore_errors: Option<bool>,
-    pub rollback_command: Option<String>,
+    /// Команда отката: одна строка или список шагов, выполняемых по порядку
+    pub rollback_command: Option<RollbackCommand>,
     /// Флаг интерактивного режима
     pub interactive: Option<bool>,
     /// Предопределенные ответы на запросы в интерактивном режиме
@@ -54,11 +210,395 @@ pub struct Command {
     pub inputs: Option<HashMap<String, String>>,
     /// Опциональный путь к файлу с переменными для этой команды
     pub variables_file: Option<String>,
+    /// Переменные команды, переопределяющие переменные деплоя с теми же именами
+    pub variables: Option<HashMap<String, String>>,
+    /// Данные, передаваемые на стандартный ввод команды (с подстановкой переменных `{#VAR}`)
+    pub stdin: Option<String>,
+    /// Файл, содержимое которого передается на стандартный ввод команды
+    pub stdin_file: Option<String>,
+    /// Рабочая директория команды; относительный путь отсчитывается от директории деплоя
+    pub working_dir: Option<String>,
+    /// Политика повторов команды, переопределяющая политику деплоя
+    /// (повторы выполняются только для событий с fail_fast)
+    pub retry_policy: Option<RetryPolicy>,
+    /// Выполнять команду с `set -x`, выводя трассировку шагов в журнал
+    /// (поток ошибок команды объединяется с ее выводом)
+    pub trace: Option<bool>,
+    /// Не выводить вывод команды в консоль (он по-прежнему записывается в журнал команд)
+    pub quiet: Option<bool>,
+    /// Группа отката: при ошибке команды группы откатываются только запущенные команды этой группы
+    pub group: Option<String>,
+    /// Коды завершения, при которых команда считается успешной (например, 1 у `grep`)
+    pub allow_exit_codes: Option<Vec<i32>>,
+    /// Подстрока, которая должна присутствовать в выводе команды
+    pub assert_output_contains: Option<String>,
+    /// Регулярное выражение, которому должен соответствовать вывод команды
+    pub assert_output_matches: Option<String>,
+}
+
+/// Политика повторного запуска команд при ошибке
+///
+/// Не заданные поля наследуются от политики уровнем выше (команда - деплой).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// Количество повторных запусков
+    pub retries: Option<u32>,
+    /// Задержка перед первым повтором в секундах
+    #[schemars(range(min = 0))]
+    pub delay: Option<f64>,
+    /// Рост задержки между повторами (по умолчанию `exponential`, если задан
+    /// `backoff_factor`, иначе `fixed`)
+    pub backoff: Option<Backoff>,
+    /// Множитель задержки для каждого следующего повтора (по умолчанию 2)
+    #[schemars(range(min = 0))]
+    pub backoff_factor: Option<f64>,
+    /// Доля случайного уменьшения задержки от 0 до 1, чтобы повторы параллельных
+    /// деплоев не совпадали по времени
+    #[schemars(range(min = 0, max = 1))]
+    pub jitter: Option<f64>,
+    /// Коды завершения, при которых команда повторяется (по умолчанию - любой код ошибки)
+    pub retry_on_exit_codes: Option<Vec<i32>>,
+}
+
+/// Рост задержки между повторами команды
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Backoff {
+    /// Одинаковая задержка перед каждым повтором
+    Fixed,
+    /// Задержка умножается на `backoff_factor` перед каждым следующим повтором
+    Exponential,
+}
+
+impl RetryPolicy {
+    /// Дополняет политику значениями другой политики для не заданных полей
+    ///
+    /// # Параметры
+    ///
+    /// * `fallback` - Политика, значения которой используются по умолчанию
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Объединенная политика
+    pub fn or(self, fallback: &RetryPolicy) -> RetryPolicy {
+        RetryPolicy {
+            retries: self.retries.or(fallback.retries),
+            delay: self.delay.or(fallback.delay),
+            backoff: self.backoff.or(fallback.backoff),
+            backoff_factor: self.backoff_factor.or(fallback.backoff_factor),
+            jitter: self.jitter.or(fallback.jitter),
+            retry_on_exit_codes: self
+                .retry_on_exit_codes
+                .or_else(|| fallback.retry_on_exit_codes.clone()),
+        }
+    }
+
+    /// Проверяет, нужно ли повторить команду, завершившуюся с ошибкой
+    ///
+    /// # Параметры
+    ///
+    /// * `exit_code` - Код завершения команды
+    /// * `attempt` - Количество уже выполненных повторов
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// `true`, если лимит повторов не исчерпан и код завершения допускает повтор
+    pub fn should_retry(&self, exit_code: Option<i32>, attempt: u32) -> bool {
+        if attempt >= self.retries.unwrap_or(0) {
+            return false;
+        }
+
+        match (&self.retry_on_exit_codes, exit_code) {
+            (Some(codes), Some(code)) => codes.contains(&code),
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+}
+
+/// Вычисляет задержку перед повтором команды
+///
+/// # Параметры
+///
+/// * `attempt` - Номер повтора (начиная с 1)
+/// * `policy` - Политика повторов команды
+///
+/// # Возвращаемое значение
+///
+/// Задержка перед повтором с учетом роста и случайного разброса
+pub fn next_delay(attempt: u32, policy: &RetryPolicy) -> Duration {
+    let base = base_delay(attempt, policy);
+    match policy.jitter {
+        Some(jitter) => apply_jitter(base, jitter, rand::random()),
+        None => base,
+    }
+}
+
+/// Вычисляет задержку перед повтором без случайного разброса
+///
+/// # Параметры
+///
+/// * `attempt` - Номер повтора (начиная с 1)
+/// * `policy` - Политика повторов команды
+///
+/// # Возвращаемое значение
+///
+/// `delay` для `fixed` и `delay * backoff_factor^(attempt - 1)` для `exponential`
+pub fn base_delay(attempt: u32, policy: &RetryPolicy) -> Duration {
+    let delay = policy.delay.unwrap_or(0.0).max(0.0);
+    let backoff = policy
+        .backoff
+        .unwrap_or(if policy.backoff_factor.is_some() {
+            Backoff::Exponential
+        } else {
+            Backoff::Fixed
+        });
+
+    let seconds = match backoff {
+        Backoff::Fixed => delay,
+        Backoff::Exponential => {
+            let factor = policy.backoff_factor.unwrap_or(2.0).max(0.0);
+            let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+            delay * factor.powi(exponent)
+        }
+    };
+
+    Duration::try_from_secs_f64(seconds).unwrap_or(Duration::MAX)
+}
+
+/// Уменьшает задержку на случайную долю не больше `jitter`
+///
+/// # Параметры
+///
+/// * `delay` - Задержка без разброса
+/// * `jitter` - Максимальная доля уменьшения (ограничивается диапазоном от 0 до 1)
+/// * `random` - Случайное число из диапазона [0, 1)
+///
+/// # Возвращаемое значение
+///
+/// Задержка из диапазона `[delay * (1 - jitter), delay]`
+pub fn apply_jitter(delay: Duration, jitter: f64, random: f64) -> Duration {
+    let jitter = jitter.clamp(0.0, 1.0);
+    delay.mul_f64(1.0 - jitter * random.clamp(0.0, 1.0))
+}
+
+impl Command {
+    /// Проверяет, считается ли код завершения команды успешным (`allow_exit_codes`)
+    ///
+    /// # Параметры
+    ///
+    /// * `exit_code` - Код завершения команды
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// true, если код входит в `allow_exit_codes` команды
+    pub fn allows_exit_code(&self, exit_code: Option<i32>) -> bool {
+        match (&self.allow_exit_codes, exit_code) {
+            (Some(codes), Some(code)) => codes.contains(&code),
+            _ => false,
+        }
+    }
+
+    /// Проверяет, заданы ли у команды проверки вывода
+    pub fn has_output_assertions(&self) -> bool {
+        self.assert_output_contains.is_some() || self.assert_output_matches.is_some()
+    }
+
+    /// Проверяет вывод команды по `assert_output_contains` и `assert_output_matches`
+    ///
+    /// # Параметры
+    ///
+    /// * `output` - Вывод команды
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Описание невыполненной проверки или None, если вывод соответствует проверкам
+    pub fn output_assertion_failure(&self, output: &str) -> Option<String> {
+        if let Some(expected) = &self.assert_output_contains {
+            if !output.contains(expected.as_str()) {
+                return Some(format!("Вывод команды не содержит '{}'", expected));
+            }
+        }
+
+        if let Some(pattern) = &self.assert_output_matches {
+            match regex::Regex::new(pattern) {
+                Ok(re) if re.is_match(output) => {}
+                Ok(_) => {
+                    return Some(format!(
+                        "Вывод команды не соответствует выражению '{}'",
+                        pattern
+                    ))
+                }
+                Err(e) => {
+                    return Some(format!(
+                        "Некорректное регулярное выражение '{}': {}",
+                        pattern, e
+                    ))
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Определяет действующую политику повторов команды
+    ///
+    /// Не заданные в `retry_policy` команды поля наследуются от `retry_policy` деплоя.
+    ///
+    /// # Параметры
+    ///
+    /// * `deployment_policy` - Политика повторов деплоя, к которому относится команда
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Политика повторов команды
+    pub fn retry_policy(&self, deployment_policy: Option<&RetryPolicy>) -> RetryPolicy {
+        let own = self.retry_policy.clone().unwrap_or_default();
+
+        match deployment_policy {
+            Some(policy) => own.or(policy),
+            None => own,
+        }
+    }
+
+    /// Объединяет переменные, заданные в конфигурации деплоя и команды
+    ///
+    /// # Параметры
+    ///
+    /// * `deployment_variables` - Переменные деплоя, к которому относится команда
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Переменные деплоя, переопределенные переменными команды
+    pub fn inline_variables(
+        &self,
+        deployment_variables: Option<&HashMap<String, String>>,
+    ) -> HashMap<String, String> {
+        let mut variables = deployment_variables.cloned().unwrap_or_default();
+        if let Some(command_variables) = &self.variables {
+            variables.extend(command_variables.clone());
+        }
+        variables
+    }
+}
+
+/// Путь к конфигурации, означающий чтение из стандартного ввода
+pub const STDIN_CONFIG_PATH: &str = "-";
+
+/// Формат файла конфигурации
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ConfigFormat {
+    /// YAML (по умолчанию)
+    #[default]
+    Yaml,
+    /// JSON
+    Json,
+}
+
+impl ConfigFormat {
+    /// Определяет формат по расширению файла (`.json` - JSON, иначе YAML)
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+}
+
+/// Вычисляет хеш конфигурации
+///
+/// Хеш считается по нормализованному представлению конфигурации, поэтому не зависит
+/// от форматирования, комментариев и порядка ключей в файле.
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоя
+///
+/// # Возвращаемое значение
+///
+/// Хеш конфигурации
+pub fn hash(config: &Config) -> u64 {
+    let canonical = serde_json::to_value(config)
+        .map(|value| value.to_string())
+        .unwrap_or_default();
+    let mut hasher = DefaultHasher::new();
+    canonical.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Отпечаток загруженной конфигурации для обнаружения ее изменения во время запуска
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigFingerprint {
+    /// Путь к файлу конфигурации
+    pub path: String,
+    /// Формат файла конфигурации
+    pub format: Option<ConfigFormat>,
+    /// Хеш конфигурации на момент загрузки
+    pub hash: u64,
+}
+
+impl ConfigFingerprint {
+    /// Создает отпечаток конфигурации
+    ///
+    /// Конфигурацию из стандартного ввода нельзя перечитать, поэтому для нее отпечаток не создается
+    pub fn new(path: &str, format: Option<ConfigFormat>, hash: u64) -> Option<Self> {
+        if path == STDIN_CONFIG_PATH {
+            return None;
+        }
+        Some(Self {
+            path: path.to_string(),
+            format,
+            hash,
+        })
+    }
+
+    /// Перечитывает файл конфигурации и проверяет, изменился ли он с момента загрузки
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// `true`, если хеш конфигурации отличается от исходного, или ошибка чтения файла
+    pub fn has_changed(&self) -> Result<bool> {
+        let config = Config::load_with_options(&self.path, false, self.format)?;
+        Ok(hash(&config) != self.hash)
+    }
 }
 
 impl Config {
     /// Загружает конфигурацию из файла
     pub fn load(path: &str) -> Result<Self> {
+        Self::load_with_options(path, false, None)
+    }
+
+    /// Загружает конфигурацию из файла или стандартного ввода с проверкой неизвестных полей
+    ///
+    /// # Параметры
+    ///
+    /// * `path` - Путь к файлу конфигурации или `-` для чтения из стандартного ввода
+    /// * `strict` - Считать неизвестные поля ошибкой, а не предупреждением
+    /// * `format` - Формат конфигурации (по умолчанию определяется по расширению файла,
+    ///   для стандартного ввода - YAML)
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Конфигурация или ошибка загрузки
+    pub fn load_with_options(
+        path: &str,
+        strict: bool,
+        format: Option<ConfigFormat>,
+    ) -> Result<Self> {
+        if path == STDIN_CONFIG_PATH {
+            let mut content = String::new();
+            io::stdin()
+                .read_to_string(&mut content)
+                .context("Не удалось прочитать конфигурацию из стандартного ввода")?;
+            info!(
+                "Конфигурация прочитана из стандартного ввода, размер: {} байт",
+                content.len()
+            );
+            return Self::parse(&content, "<stdin>", strict, format.unwrap_or_default());
+        }
+
         let config_path = Path::new(path);
 
         // Расширенное логирование для отладки проблем с путями
@@ -97,10 +637,7 @@ impl Config {
             }
 
             // Если файл не существует, создаем пустую конфигурацию
-            let config = Config {
-                deployments: vec![],
-                variables_file: None,
-            };
+            let config = Config::default();
             config.save(path)?;
             return Ok(config);
         }
@@ -148,7 +685,86 @@ impl Config {
             }
         };
 
-        let config: Self = match serde_yaml::from_str(&content) {
+        Self::parse(
+            &content,
+            path,
+            strict,
+            format.unwrap_or_else(|| ConfigFormat::from_path(path)),
+        )
+    }
+
+    /// Разбирает содержимое конфигурации, мигрирует его и проверяет неизвестные поля
+    ///
+    /// # Параметры
+    ///
+    /// * `content` - Содержимое конфигурации
+    /// * `path` - Источник конфигурации для сообщений об ошибках
+    /// * `strict` - Считать неизвестные поля ошибкой, а не предупреждением
+    /// * `format` - Формат содержимого
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Конфигурация или ошибка разбора
+    fn parse(content: &str, path: &str, strict: bool, format: ConfigFormat) -> Result<Self> {
+        let parsed: std::result::Result<serde_yaml::Value, String> = match format {
+            ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+        };
+        let value = match parsed {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Ошибка разбора конфигурации ({:?}): {}", format, e);
+                error!("Содержимое, вызвавшее ошибку: '{}'", content);
+                return Err(anyhow::anyhow!(
+                    "Неверный формат файла конфигурации: {} ({})",
+                    path,
+                    e
+                ));
+            }
+        };
+
+        // Проверяем версию схемы и при необходимости мигрируем конфигурацию
+        let from_version = value
+            .get("version")
+            .and_then(serde_yaml::Value::as_u64)
+            .unwrap_or(0) as u32;
+        let mut value = if from_version > CONFIG_VERSION {
+            warn!(
+                "Версия конфигурации {} новее поддерживаемой ({}), некоторые поля могут быть проигнорированы",
+                from_version, CONFIG_VERSION
+            );
+            value
+        } else {
+            migrate(value, from_version)
+        };
+
+        move_deprecated_retry_fields(&mut value);
+
+        // Serde молча игнорирует неизвестные поля, поэтому собираем их при десериализации
+        let mut ignored_fields = Vec::new();
+        let parsed: std::result::Result<Self, _> =
+            serde_ignored::deserialize(&value, |path| ignored_fields.push(path.to_string()));
+        let unknown_fields: Vec<String> = ignored_fields
+            .iter()
+            .map(|path| readable_field_path(&value, path))
+            .collect();
+        if !unknown_fields.is_empty() {
+            if strict {
+                return Err(anyhow::anyhow!(
+                    "Неизвестные поля в файле конфигурации {}: {}",
+                    path,
+                    unknown_fields.join(", ")
+                ));
+            }
+            for field in &unknown_fields {
+                warn!(
+                    "Неизвестное поле в конфигурации будет проигнорировано: {}",
+                    field
+                );
+            }
+        }
+
+        let config: Self = match parsed {
             Ok(c) => {
                 info!("YAML успешно десериализован");
                 c
@@ -164,30 +780,252 @@ impl Config {
             }
         };
 
+        // Порядок событий должен ссылаться только на события деплоя,
+        // окно обслуживания - разбираться без ошибок
+        for deployment in &config.deployments {
+            deployment
+                .check_event_order()
+                .and_then(|_| deployment.maintenance_window().map(|_| ()))
+                .with_context(|| format!("Ошибка в файле конфигурации {}", path))?;
+        }
+
         info!("Конфигурация содержит {} деплоев", config.deployments.len());
 
         Ok(config)
     }
 
     /// Сохраняет конфигурацию в файл
+    ///
+    /// Формат определяется по расширению файла (`.json` - JSON, иначе YAML).
     pub fn save(&self, path: &str) -> Result<()> {
-        let yaml =
-            serde_yaml::to_string(self).context("Не удалось сериализовать конфигурацию в YAML")?;
+        let content = self.render(ConfigFormat::from_path(path))?;
 
-        fs::write(path, yaml)
+        fs::write(path, content)
             .with_context(|| format!("Не удалось записать конфигурацию в файл: {}", path))?;
 
         Ok(())
     }
 
+    /// Сериализует конфигурацию в каноническом виде
+    ///
+    /// Поля выводятся в порядке их объявления в структурах, незаданные
+    /// необязательные поля опускаются.
+    ///
+    /// # Параметры
+    ///
+    /// * `format` - Формат вывода
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Текст конфигурации
+    pub fn render(&self, format: ConfigFormat) -> Result<String> {
+        // Отображение YAML сохраняет порядок полей, в отличие от serde_json::Value
+        let mut value =
+            serde_yaml::to_value(self).context("Не удалось сериализовать конфигурацию")?;
+        remove_null_fields(&mut value);
+
+        match format {
+            ConfigFormat::Yaml => serde_yaml::to_string(&value)
+                .context("Не удалось сериализовать конфигурацию в YAML"),
+            ConfigFormat::Json => serde_json::to_string_pretty(&value)
+                .map(|json| json + "\n")
+                .context("Не удалось сериализовать конфигурацию в JSON"),
+        }
+    }
+
     /// Находит деплоймент по имени
     pub fn find_deployment(&self, name: &str) -> Option<&Deployment> {
         self.deployments.iter().find(|d| d.name == name)
     }
+
+    /// Формирует сообщение об отсутствии деплоя с подсказкой ближайшего имени
+    ///
+    /// # Параметры
+    ///
+    /// * `name` - Имя деплоя, которое не удалось найти
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Сообщение об ошибке, например `Деплой 'wbe' не найден. Возможно, вы имели в виду 'web'?`
+    pub fn deployment_not_found_message(&self, name: &str) -> String {
+        let names = self.deployments.iter().map(|d| d.name.as_str());
+        match crate::util::closest_match(name, names) {
+            Some(suggestion) => format!(
+                "Деплой '{}' не найден. Возможно, вы имели в виду '{}'?",
+                name, suggestion
+            ),
+            None => format!("Деплой '{}' не найден", name),
+        }
+    }
+
+    /// Находит деплои, имена которых соответствуют шаблону с `*` и `?`
+    pub fn matching_deployments(&self, pattern: &str) -> Vec<&Deployment> {
+        self.deployments
+            .iter()
+            .filter(|d| crate::settings::glob_matches(pattern, &d.name))
+            .collect()
+    }
+}
+
+/// Проверяет, является ли имя деплоя шаблоном (содержит `*` или `?`)
+pub fn is_deployment_pattern(name: &str) -> bool {
+    name.contains('*') || name.contains('?')
+}
+
+/// Приводит путь к неизвестному полю к читаемому виду
+///
+/// Индексы элементов списков заменяются их именами, служебные сегменты
+/// необязательных полей (`?`) опускаются.
+///
+/// # Параметры
+///
+/// * `value` - Конфигурация в виде YAML-значения
+/// * `path` - Путь к полю, например `deployments.0.working_directory`
+///
+/// # Возвращаемое значение
+///
+/// Путь к полю, например `deployments.myproject.working_directory`
+fn readable_field_path(value: &serde_yaml::Value, path: &str) -> String {
+    let mut node = Some(value);
+    path.split('.')
+        .filter(|segment| *segment != "?")
+        .map(|segment| {
+            let items = node.and_then(serde_yaml::Value::as_sequence);
+            match (items, segment.parse::<usize>()) {
+                (Some(items), Ok(idx)) => {
+                    node = items.get(idx);
+                    node.map(|item| item_label(item, idx))
+                        .unwrap_or_else(|| segment.to_string())
+                }
+                _ => {
+                    node = node.and_then(|n| n.get(segment));
+                    segment.to_string()
+                }
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Возвращает метку элемента списка для пути к полю: имя элемента или его индекс
+fn item_label(value: &serde_yaml::Value, idx: usize) -> String {
+    value
+        .get("name")
+        .and_then(serde_yaml::Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| idx.to_string())
+}
+
+/// Мигрирует конфигурацию старой версии схемы к текущей
+///
+/// Миграции применяются к YAML-значению до типизированной десериализации.
+/// Версия 1 совпадает с версией 0 по составу полей, поэтому переход на нее
+/// только проставляет номер версии.
+///
+/// # Параметры
+///
+/// * `value` - Конфигурация в виде YAML-значения
+/// * `from_version` - Версия схемы исходной конфигурации
+///
+/// # Возвращаемое значение
+///
+/// Конфигурация, приведенная к версии `CONFIG_VERSION`
+pub fn migrate(mut value: serde_yaml::Value, from_version: u32) -> serde_yaml::Value {
+    if from_version >= CONFIG_VERSION {
+        return value;
+    }
+
+    info!(
+        "Миграция конфигурации с версии {} на версию {}",
+        from_version, CONFIG_VERSION
+    );
+
+    if let Some(mapping) = value.as_mapping_mut() {
+        mapping.insert("version".into(), CONFIG_VERSION.into());
+    }
+
+    value
+}
+
+/// Устаревшие поля команды, перенесенные в `retry_policy`
+const DEPRECATED_RETRY_FIELDS: [&str; 2] = ["retries", "retry_on_exit_codes"];
+
+/// Переносит устаревшие поля команды `retries` и `retry_on_exit_codes` в `retry_policy`
+///
+/// Политика повторов команды задается только полем `retry_policy`. Если поле уже
+/// задано в `retry_policy`, устаревшее значение отбрасывается.
+///
+/// # Параметры
+///
+/// * `value` - Конфигурация в виде YAML-значения
+fn move_deprecated_retry_fields(value: &mut serde_yaml::Value) {
+    let commands = value
+        .get_mut("deployments")
+        .and_then(serde_yaml::Value::as_sequence_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(|d| d.get_mut("events"))
+        .filter_map(serde_yaml::Value::as_sequence_mut)
+        .flatten()
+        .filter_map(|e| e.get_mut("commands"))
+        .filter_map(serde_yaml::Value::as_sequence_mut)
+        .flatten()
+        .filter_map(serde_yaml::Value::as_mapping_mut);
+
+    for command in commands {
+        for field in DEPRECATED_RETRY_FIELDS {
+            let Some(deprecated) = command.remove(field) else {
+                continue;
+            };
+            let policy = command
+                .entry("retry_policy".into())
+                .or_insert_with(|| serde_yaml::Mapping::new().into());
+            let Some(policy) = policy.as_mapping_mut() else {
+                continue;
+            };
+
+            if policy.contains_key(field) {
+                warn!(
+                    "Поле команды '{}' устарело и игнорируется: значение задано в retry_policy.{}",
+                    field, field
+                );
+            } else {
+                warn!(
+                    "Поле команды '{}' устарело, используйте retry_policy.{}",
+                    field, field
+                );
+                policy.insert(field.into(), deprecated);
+            }
+        }
+    }
+}
+
+/// Удаляет из сериализованной конфигурации поля со значением null
+///
+/// # Параметры
+///
+/// * `value` - Сериализованная конфигурация
+fn remove_null_fields(value: &mut serde_yaml::Value) {
+    match value {
+        serde_yaml::Value::Mapping(fields) => {
+            fields.retain(|_, field| !field.is_null());
+            for (_, field) in fields.iter_mut() {
+                remove_null_fields(field);
+            }
+        }
+        serde_yaml::Value::Sequence(items) => items.iter_mut().for_each(remove_null_fields),
+        _ => {}
+    }
 }
 
 /// Создает шаблон деплоя с указанным именем
 pub fn create_template_deployment(name: &str, config_path: &str) -> Result<()> {
+    if config_path == STDIN_CONFIG_PATH {
+        return Err(anyhow::anyhow!(
+            "Шаблон деплоя нельзя сохранить в конфигурацию из стандартного ввода"
+        ));
+    }
+
     let mut config = Config::load(config_path)?;
 
     // Проверяем, существует ли уже деплой с таким именем
@@ -213,11 +1051,488 @@ pub fn verify_deployment(config: &Config, deployment_name: &str) -> Result<bool>
     let deployment = match config.find_deployment(deployment_name) {
         Some(d) => d,
         None => {
-            error!("Деплой с именем '{}' не найден", deployment_name);
+            error!("{}", config.deployment_not_found_message(deployment_name));
             return Ok(false);
         }
     };
 
+    // Проверяем команды на небезопасную подстановку переменных
+    for warning in deployments::check_unquoted_variables(deployment) {
+        warn!("{}", warning);
+    }
+
+    // Проверяем переменные окружения деплоя
+    for warning in deployments::check_duplicate_environment_keys(deployment) {
+        warn!("{}", warning);
+    }
+    let mut malformed = deployments::check_environment_entries(deployment);
+    malformed.extend(deployments::check_env_files(deployment));
+    for message in &malformed {
+        error!("{}", message);
+    }
+
     // Проверяем события деплоя
-    deployments::validate_deployment_events(deployment)
+    Ok(deployments::validate_deployment_events(deployment)? && malformed.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_v0_config_to_current_version() {
+        let value: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "make deploy"
+            rollback: "make rollback"
+"#,
+        )
+        .unwrap();
+
+        let migrated = migrate(value.clone(), 0);
+        assert_eq!(
+            migrated.get("version").and_then(serde_yaml::Value::as_u64),
+            Some(CONFIG_VERSION as u64)
+        );
+
+        // Кроме номера версии конфигурация не меняется: неизвестные поля не превращаются в команды
+        let mut stamped = value;
+        stamped
+            .as_mapping_mut()
+            .unwrap()
+            .insert("version".into(), CONFIG_VERSION.into());
+        assert_eq!(migrated, stamped);
+
+        let config: Config = serde_yaml::from_value(migrated).unwrap();
+        assert!(config.deployments[0].events[0].commands[0]
+            .rollback_command
+            .is_none());
+    }
+
+    #[test]
+    fn current_version_config_is_not_changed() {
+        let value: serde_yaml::Value =
+            serde_yaml::from_str("version: 1\ndeployments: []\n").unwrap();
+
+        assert_eq!(migrate(value.clone(), CONFIG_VERSION), value);
+    }
+
+    #[test]
+    fn deprecated_retry_fields_move_into_retry_policy() {
+        let config = Config::parse(
+            r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "make deploy"
+            retries: 2
+            retry_on_exit_codes: [75]
+          - command: "make migrate"
+            retries: 3
+            retry_policy:
+              retries: 0
+              delay: 1
+"#,
+            "test.yml",
+            true,
+            ConfigFormat::Yaml,
+        )
+        .unwrap();
+        let commands = &config.deployments[0].events[0].commands;
+
+        let policy = commands[0].retry_policy(None);
+        assert_eq!(policy.retries, Some(2));
+        assert_eq!(policy.retry_on_exit_codes, Some(vec![75]));
+
+        // Значение из retry_policy важнее устаревшего поля команды
+        let policy = commands[1].retry_policy(None);
+        assert_eq!(policy.retries, Some(0));
+        assert_eq!(policy.delay, Some(1.0));
+    }
+
+    const MISSPELLED_CONFIG: &str = r#"
+deployments:
+  - name: myproject
+    working_directory: /srv/app
+    events:
+      - name: deploy
+        commands:
+          - command: "make deploy"
+            retry_policy:
+              retries: 2
+              delay_sec: 5
+"#;
+
+    #[test]
+    fn strict_mode_reports_misspelled_fields() {
+        let error = Config::parse(MISSPELLED_CONFIG, "test.yml", true, ConfigFormat::Yaml)
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("deployments.myproject.working_directory"));
+        assert!(
+            error.contains("deployments.myproject.events.deploy.commands.0.retry_policy.delay_sec")
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_ignored_without_strict_mode() {
+        let config =
+            Config::parse(MISSPELLED_CONFIG, "test.yml", false, ConfigFormat::Yaml).unwrap();
+
+        assert_eq!(config.deployments[0].name, "myproject");
+        assert_eq!(config.deployments[0].working_dir, None);
+    }
+
+    #[test]
+    fn example_configs_have_no_unknown_fields() {
+        for entry in fs::read_dir("examples").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("yml") {
+                continue;
+            }
+            let content = fs::read_to_string(&path).unwrap();
+            if !content.contains("deployments:") {
+                continue;
+            }
+            Config::parse(
+                &content,
+                &path.display().to_string(),
+                true,
+                ConfigFormat::Yaml,
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn command_inline_variables_override_deployment_variables() {
+        let command: Command = serde_yaml::from_str(
+            r#"
+command: "echo {#BRANCH}"
+variables:
+  BRANCH: "feature"
+"#,
+        )
+        .unwrap();
+        let deployment_variables = HashMap::from([
+            ("BRANCH".to_string(), "main".to_string()),
+            ("APP_DIR".to_string(), "/srv/app".to_string()),
+        ]);
+
+        let variables = command.inline_variables(Some(&deployment_variables));
+
+        assert_eq!(variables["BRANCH"], "feature");
+        assert_eq!(variables["APP_DIR"], "/srv/app");
+        assert_eq!(command.inline_variables(None).len(), 1);
+    }
+
+    #[test]
+    fn event_order_puts_listed_events_first() {
+        let deployment: Deployment = serde_yaml::from_str(
+            r#"
+name: api
+event_order: [migrate, build]
+events:
+  - name: deploy
+    commands: []
+  - name: build
+    commands: []
+  - name: notify
+    commands: []
+  - name: migrate
+    commands: []
+"#,
+        )
+        .unwrap();
+
+        let names: Vec<&str> = deployment
+            .ordered_events()
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["migrate", "build", "deploy", "notify"]);
+        assert!(deployment.check_event_order().is_ok());
+    }
+
+    #[test]
+    fn event_order_with_unknown_event_is_rejected() {
+        let error = Config::parse(
+            r#"
+deployments:
+  - name: api
+    event_order: [deploy, rollout]
+    events:
+      - name: deploy
+        commands:
+          - command: "echo deploy"
+"#,
+            "test.yml",
+            false,
+            ConfigFormat::Yaml,
+        )
+        .unwrap_err();
+
+        assert!(format!("{:#}", error).contains("rollout"));
+    }
+
+    #[test]
+    fn rollback_command_accepts_string_and_list() {
+        let single: Command = serde_yaml::from_str(
+            r#"
+command: "git pull"
+rollback_command: "git reset --hard HEAD~1"
+"#,
+        )
+        .unwrap();
+        let steps: Command = serde_yaml::from_str(
+            r#"
+command: "./migrate.sh up"
+rollback_command:
+  - "./migrate.sh down"
+  - "./restart.sh"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            single.rollback_command.unwrap().steps(),
+            vec!["git reset --hard HEAD~1"]
+        );
+        assert_eq!(
+            steps.rollback_command.unwrap().steps(),
+            vec!["./migrate.sh down", "./restart.sh"]
+        );
+    }
+
+    #[test]
+    fn retries_only_listed_exit_codes() {
+        let policy = RetryPolicy {
+            retries: Some(2),
+            retry_on_exit_codes: Some(vec![75]),
+            ..RetryPolicy::default()
+        };
+
+        assert!(policy.should_retry(Some(75), 0));
+        assert!(policy.should_retry(Some(75), 1));
+        assert!(!policy.should_retry(Some(75), 2));
+        assert!(!policy.should_retry(Some(1), 0));
+        assert!(!policy.should_retry(None, 0));
+    }
+
+    #[test]
+    fn allows_only_listed_exit_codes() {
+        let command: Command =
+            serde_yaml::from_str("command: \"grep -q ERROR app.log\"\nallow_exit_codes: [1]\n")
+                .unwrap();
+
+        assert!(command.allows_exit_code(Some(1)));
+        assert!(!command.allows_exit_code(Some(2)));
+        assert!(!command.allows_exit_code(None));
+
+        let command: Command = serde_yaml::from_str("command: \"true\"\n").unwrap();
+        assert!(!command.allows_exit_code(Some(1)));
+    }
+
+    #[test]
+    fn output_assertions_check_contains_and_regex() {
+        let command: Command = serde_yaml::from_str(
+            r#"
+command: "./app --version"
+assert_output_contains: "app"
+assert_output_matches: 'v2\.\d+'
+"#,
+        )
+        .unwrap();
+
+        assert!(command.has_output_assertions());
+        assert_eq!(command.output_assertion_failure("app v2.15"), None);
+        assert_eq!(
+            command.output_assertion_failure("service v2.15").as_deref(),
+            Some("Вывод команды не содержит 'app'")
+        );
+        assert_eq!(
+            command.output_assertion_failure("app v3.0").as_deref(),
+            Some("Вывод команды не соответствует выражению 'v2\\.\\d+'")
+        );
+
+        let command: Command =
+            serde_yaml::from_str("command: \"true\"\nassert_output_matches: \"(\"\n").unwrap();
+        assert!(command
+            .output_assertion_failure("")
+            .unwrap()
+            .starts_with("Некорректное регулярное выражение '('"));
+
+        let command: Command = serde_yaml::from_str("command: \"true\"\n").unwrap();
+        assert!(!command.has_output_assertions());
+        assert_eq!(command.output_assertion_failure("anything"), None);
+    }
+
+    #[test]
+    fn exponential_backoff_grows_delay() {
+        let policy = RetryPolicy {
+            delay: Some(1.0),
+            backoff_factor: Some(2.0),
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(next_delay(1, &policy), Duration::from_secs(1));
+        assert_eq!(next_delay(2, &policy), Duration::from_secs(2));
+        assert_eq!(next_delay(3, &policy), Duration::from_secs(4));
+        assert_eq!(next_delay(4, &policy), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn fixed_backoff_keeps_delay() {
+        let policy = RetryPolicy {
+            delay: Some(1.5),
+            backoff: Some(Backoff::Fixed),
+            backoff_factor: Some(3.0),
+            ..RetryPolicy::default()
+        };
+
+        assert_eq!(next_delay(1, &policy), Duration::from_millis(1500));
+        assert_eq!(next_delay(5, &policy), Duration::from_millis(1500));
+        assert_eq!(next_delay(1, &RetryPolicy::default()), Duration::ZERO);
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let delay = Duration::from_secs(10);
+        assert_eq!(apply_jitter(delay, 0.5, 0.0), delay);
+        assert_eq!(apply_jitter(delay, 0.5, 1.0), Duration::from_secs(5));
+        assert_eq!(apply_jitter(delay, 2.0, 1.0), Duration::ZERO);
+        assert_eq!(apply_jitter(delay, -1.0, 0.7), delay);
+
+        let policy = RetryPolicy {
+            delay: Some(1.0),
+            backoff_factor: Some(2.0),
+            jitter: Some(0.25),
+            ..RetryPolicy::default()
+        };
+        for attempt in 1..=5 {
+            let base = base_delay(attempt, &policy);
+            for _ in 0..100 {
+                let delay = next_delay(attempt, &policy);
+                assert!(delay <= base);
+                assert!(delay >= base.mul_f64(0.75));
+            }
+        }
+    }
+
+    #[test]
+    fn retries_any_exit_code_without_list() {
+        let policy = RetryPolicy {
+            retries: Some(1),
+            ..RetryPolicy::default()
+        };
+
+        assert!(policy.should_retry(Some(1), 0));
+        assert!(policy.should_retry(None, 0));
+        assert!(!policy.should_retry(Some(1), 1));
+    }
+
+    #[test]
+    fn render_is_idempotent_and_omits_unset_fields() {
+        let config = Config::parse(
+            r#"
+deployments:
+  - events:
+      - commands:
+          - retry_policy: {retries: 2}
+            command: "make deploy"
+        name: deploy
+    name: app
+    variables: {VERSION: "1.0"}
+"#,
+            "test.yml",
+            false,
+            ConfigFormat::Yaml,
+        )
+        .unwrap();
+
+        for format in [ConfigFormat::Yaml, ConfigFormat::Json] {
+            let formatted = config.render(format).unwrap();
+            assert!(!formatted.contains("null"), "{}", formatted);
+
+            let reparsed = Config::parse(&formatted, "test.yml", false, format).unwrap();
+            assert_eq!(reparsed.render(format).unwrap(), formatted);
+        }
+
+        let formatted = config.render(ConfigFormat::Yaml).unwrap();
+        assert!(formatted.find("name: app").unwrap() < formatted.find("events:").unwrap());
+    }
+
+    #[test]
+    fn hash_ignores_formatting_but_not_content() {
+        let parse =
+            |content: &str| Config::parse(content, "test.yml", false, ConfigFormat::Yaml).unwrap();
+        let original = parse(
+            "deployments:\n  - name: app\n    events:\n      - name: deploy\n        commands:\n          - command: \"make\"\n",
+        );
+        let reformatted = parse(
+            "# комментарий\ndeployments: [{events: [{commands: [{command: make}], name: deploy}], name: app}]\n",
+        );
+        let changed = parse(
+            "deployments: [{name: app, events: [{name: deploy, commands: [{command: make install}]}]}]\n",
+        );
+
+        assert_eq!(hash(&original), hash(&reformatted));
+        assert_ne!(hash(&original), hash(&changed));
+    }
+
+    #[test]
+    fn fingerprint_detects_changed_config_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("deploy-config.yml");
+        let path_str = path.to_str().unwrap();
+        fs::write(&path, "deployments: [{name: app, events: []}]\n").unwrap();
+
+        let config = Config::load(path_str).unwrap();
+        let fingerprint = ConfigFingerprint::new(path_str, None, hash(&config)).unwrap();
+        assert!(!fingerprint.has_changed().unwrap());
+
+        fs::write(&path, "deployments: [{name: web, events: []}]\n").unwrap();
+        assert!(fingerprint.has_changed().unwrap());
+
+        assert_eq!(ConfigFingerprint::new(STDIN_CONFIG_PATH, None, 0), None);
+    }
+
+    #[test]
+    fn matching_deployments_uses_glob_pattern() {
+        let config = Config::parse(
+            r#"
+deployments:
+  - name: api-users
+    events: []
+  - name: api-orders
+    events: []
+  - name: web
+    events: []
+"#,
+            "test.yml",
+            false,
+            ConfigFormat::Yaml,
+        )
+        .unwrap();
+
+        let names: Vec<&str> = config
+            .matching_deployments("api-*")
+            .into_iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["api-users", "api-orders"]);
+        assert!(config.matching_deployments("db-?").is_empty());
+
+        assert!(is_deployment_pattern("api-*"));
+        assert!(is_deployment_pattern("web-?"));
+        assert!(!is_deployment_pattern("web"));
+    }
 }
@@ -11,12 +11,17 @@
 
 - `EventType` - перечисление типов событий деплоя
 - `EventEmitter` - компонент для отправки событий и логирования
+
+Отправка событий в канал фильтруется по исходам деплоя, указанным
+в настройке `notify_on`.
 */
 
-use log::{error, info};
+use log::{debug, error, info};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 
+use crate::settings;
+
 /// Типы событий, которые могут происходить во время деплоя
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EventType {
@@ -54,10 +59,30 @@ pub enum EventType {
     },
 }
 
+impl EventType {
+    /// Возвращает категорию исхода деплоя для фильтрации уведомлений
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// "success" или "failure" для событий завершения, None для остальных событий
+    pub fn outcome(&self) -> Option<&'static str> {
+        match self {
+            EventType::DeploymentStarted { .. } => None,
+            EventType::DeploymentSucceeded { .. } => Some(settings::NOTIFY_ON_SUCCESS),
+            EventType::DeploymentFailed { .. } | EventType::CommandFailed { .. } => {
+                Some(settings::NOTIFY_ON_FAILURE)
+            }
+        }
+    }
+}
+
 /// Эмиттер событий для отправки уведомлений о процессе деплоя
 pub struct EventEmitter {
     /// Канал для отправки событий (опционально)
     sender: Option<mpsc::Sender<EventType>>,
+
+    /// Исходы деплоя, о которых отправляются уведомления
+    notify_on: Vec<String>,
 }
 
 impl EventEmitter {
@@ -69,7 +94,43 @@ impl EventEmitter {
     pub fn new() -> Self {
         // В реальном приложении здесь можно было бы настроить отправку событий
         // в систему мониторинга, очередь сообщений и т.д.
-        Self { sender: None }
+        Self {
+            sender: None,
+            notify_on: settings::default_notify_on(),
+        }
+    }
+
+    /// Задает исходы деплоя, о которых отправляются уведомления
+    ///
+    /// # Параметры
+    ///
+    /// * `notify_on` - Список исходов ("success", "failure")
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Эмиттер с настроенным фильтром уведомлений
+    pub fn with_notify_on(mut self, notify_on: Vec<String>) -> Self {
+        self.notify_on = notify_on;
+        self
+    }
+
+    /// Проверяет, нужно ли отправлять уведомление о событии
+    ///
+    /// # Параметры
+    ///
+    /// * `event` - Событие для проверки
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// `true`, если исход события указан в `notify_on` или событие не является исходом
+    fn should_notify(&self, event: &EventType) -> bool {
+        match event.outcome() {
+            Some(outcome) => self
+                .notify_on
+                .iter()
+                .any(|o| o.eq_ignore_ascii_case(outcome)),
+            None => true,
+        }
     }
 
     /// Отправляет событие
@@ -81,7 +142,7 @@ impl EventEmitter {
     /// # Примечания
     ///
     /// Метод логирует информацию о событии, а также отправляет его
-    /// в канал, если он был настроен
+    /// в канал, если он был настроен и исход события указан в `notify_on`
     pub fn emit(&self, event: EventType) {
         match &event {
             EventType::DeploymentStarted { deployment, event } => {
@@ -115,6 +176,11 @@ impl EventEmitter {
             }
         }
 
+        if !self.should_notify(&event) {
+            debug!("Уведомление о событии отключено настройкой notify_on");
+            return;
+        }
+
         // Если есть канал, отправляем событие
         if let Some(sender) = &self.sender {
             let sender = sender.clone();
@@ -128,3 +194,51 @@ impl EventEmitter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn event_pair(deployment: &str) -> (EventType, EventType) {
+        (
+            EventType::DeploymentSucceeded {
+                deployment: deployment.to_string(),
+                event: "deploy".to_string(),
+            },
+            EventType::DeploymentFailed {
+                deployment: deployment.to_string(),
+                event: "deploy".to_string(),
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn failure_filter_skips_success_notifications() {
+        let (sender, mut receiver) = mpsc::channel(8);
+        let emitter = EventEmitter {
+            sender: Some(sender),
+            notify_on: vec![settings::NOTIFY_ON_FAILURE.to_string()],
+        };
+
+        let (succeeded, failed) = event_pair("app");
+        emitter.emit(succeeded);
+        emitter.emit(failed);
+        drop(emitter);
+
+        let received = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+            .await
+            .unwrap();
+        assert!(matches!(received, Some(EventType::DeploymentFailed { .. })));
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[test]
+    fn default_filter_notifies_about_all_outcomes() {
+        let emitter = EventEmitter::new();
+        let (succeeded, failed) = event_pair("app");
+
+        assert!(emitter.should_notify(&succeeded));
+        assert!(emitter.should_notify(&failed));
+    }
+}
@@ -11,14 +11,329 @@
 - Мониторинг и логирование процесса построения цепочек
 */
 
-use crate::config::Config;
+use crate::config::{Config, MissingVarPolicy, RetryPolicy, RollbackCommand};
 use crate::executor::command_executor;
+use crate::executor::remote_variables;
+use crate::executor::runner::RunOptions;
+use crate::util;
 use anyhow::{Context, Result};
 use command_system::{ChainBuilder, ChainExecutionMode, ConsoleLogger, LogLevel};
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::time::Instant;
 
+/// Команды отката по именам исходных команд цепочки
+pub type RollbackCommands = HashMap<String, Vec<command_system::command::ShellCommand>>;
+
+/// Временные файлы стандартного ввода команд цепочки
+///
+/// Файлы удаляются при освобождении, поэтому их нужно хранить до завершения цепочки.
+pub type StdinFiles = Vec<tempfile::TempPath>;
+
+/// Переменная с кодом завершения предыдущей команды события (`{#__PREV_EXIT__}`)
+pub const PREV_EXIT_VARIABLE: &str = "__PREV_EXIT__";
+
+/// Проверяет, указан ли удаленный файл переменных
+///
+/// Command System не загружает файлы по URL, поэтому переменные удаленных файлов
+/// подставляются в команду до ее создания.
+fn uses_remote_variables(
+    cmd_variables_file: Option<&str>,
+    global_variables_file: Option<&str>,
+) -> bool {
+    cmd_variables_file
+        .into_iter()
+        .chain(global_variables_file)
+        .any(remote_variables::is_remote_variables_file)
+}
+
+/// Проверяет, использует ли команда код завершения предыдущей команды
+///
+/// Такая команда начинает новую цепочку: код становится известен только после
+/// выполнения предыдущей команды.
+fn uses_previous_exit_code(cmd: &crate::config::Command) -> bool {
+    cmd.command
+        .contains(&format!("{{#{}}}", PREV_EXIT_VARIABLE))
+}
+
+/// Формирует имена команд события в цепочке
+///
+/// Команда с полем `name` получает имя `<деплой>_<событие>_<name>`, в котором
+/// символы, кроме букв, цифр, `-` и `_`, заменены на `_`. Остальные команды
+/// получают имя `<деплой>_<событие>_cmd_<номер>`. Если имя уже занято другой
+/// командой события, к нему добавляется номер команды.
+///
+/// # Параметры
+///
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+/// * `commands` - Команды события
+///
+/// # Возвращаемое значение
+///
+/// Имена команд в порядке их следования в событии
+fn command_names(
+    deployment_name: &str,
+    event_name: &str,
+    commands: &[crate::config::Command],
+) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(commands.len());
+
+    for (idx, cmd) in commands.iter().enumerate() {
+        let suffix = match cmd.name.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => name
+                .chars()
+                .map(|c| {
+                    if c.is_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect(),
+            _ => format!("cmd_{}", idx + 1),
+        };
+
+        let mut name = format!("{}_{}_{}", deployment_name, event_name, suffix);
+        if names.contains(&name) {
+            name = format!("{}_{}", name, idx + 1);
+        }
+        names.push(name);
+    }
+
+    names
+}
+
+/// Собирает описания команд события по их именам в цепочке
+///
+/// В описаниях подставляются значения переменных окружения `${VAR}`.
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоя
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+///
+/// # Возвращаемое значение
+///
+/// Хэш-карта "имя команды" -> описание (только для команд с описанием)
+pub fn command_descriptions(
+    config: &Config,
+    deployment_name: &str,
+    event_name: &str,
+) -> HashMap<String, String> {
+    let Ok((_, event)) = find_deployment_and_event(config, deployment_name, event_name) else {
+        return HashMap::new();
+    };
+
+    command_names(deployment_name, event_name, &event.commands)
+        .into_iter()
+        .zip(&event.commands)
+        .filter_map(|(name, cmd)| {
+            cmd.description
+                .as_deref()
+                .map(|description| (name, util::expand_env_vars(description)))
+        })
+        .collect()
+}
+
+/// Собирает группы отката команд события по их именам в цепочке
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоя
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+///
+/// # Возвращаемое значение
+///
+/// Хэш-карта "имя команды" -> группа (только для команд с `group`)
+pub fn command_groups(
+    config: &Config,
+    deployment_name: &str,
+    event_name: &str,
+) -> HashMap<String, String> {
+    let Ok((_, event)) = find_deployment_and_event(config, deployment_name, event_name) else {
+        return HashMap::new();
+    };
+
+    command_names(deployment_name, event_name, &event.commands)
+        .into_iter()
+        .zip(&event.commands)
+        .filter_map(|(name, cmd)| cmd.group.clone().map(|group| (name, group)))
+        .collect()
+}
+
+/// Собирает имена команд события, вывод которых не показывается в консоли (`quiet`)
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоя
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+///
+/// # Возвращаемое значение
+///
+/// Множество имен команд в цепочке
+pub fn quiet_commands(config: &Config, deployment_name: &str, event_name: &str) -> HashSet<String> {
+    let Ok((_, event)) = find_deployment_and_event(config, deployment_name, event_name) else {
+        return HashSet::new();
+    };
+
+    command_names(deployment_name, event_name, &event.commands)
+        .into_iter()
+        .zip(&event.commands)
+        .filter(|(_, cmd)| cmd.quiet.unwrap_or(false))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Формирует префикс прогресса выполнения команды
+///
+/// # Параметры
+///
+/// * `position` - Порядковый номер команды в событии (начиная с 1)
+/// * `total` - Количество команд в событии
+///
+/// # Возвращаемое значение
+///
+/// Префикс вида `[3/8]`
+pub fn progress_prefix(position: usize, total: usize) -> String {
+    format!("[{}/{}]", position, total)
+}
+
+/// Собирает строки прогресса команд события по их именам в цепочке
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоя
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+///
+/// # Возвращаемое значение
+///
+/// Хэш-карта "имя команды" -> строка вида `[3/8] npm ci`
+pub fn command_progress(
+    config: &Config,
+    deployment_name: &str,
+    event_name: &str,
+) -> HashMap<String, String> {
+    let Ok((_, event)) = find_deployment_and_event(config, deployment_name, event_name) else {
+        return HashMap::new();
+    };
+
+    let total = event.commands.len();
+    command_names(deployment_name, event_name, &event.commands)
+        .into_iter()
+        .zip(&event.commands)
+        .enumerate()
+        .map(|(idx, (name, cmd))| {
+            (
+                name,
+                format!("{} {}", progress_prefix(idx + 1, total), cmd.command),
+            )
+        })
+        .collect()
+}
+
+/// Находит команду события по ее имени в цепочке
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоя
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+/// * `cmd_name` - Имя команды в цепочке
+///
+/// # Возвращаемое значение
+///
+/// Событие, индекс команды в нем и действующая политика повторов команды
+pub fn find_chain_command<'a>(
+    config: &'a Config,
+    deployment_name: &str,
+    event_name: &str,
+    cmd_name: &str,
+) -> Option<(&'a crate::config::Event, usize, RetryPolicy)> {
+    let (deployment, event) =
+        find_deployment_and_event(config, deployment_name, event_name).ok()?;
+
+    let idx = command_names(deployment_name, event_name, &event.commands)
+        .iter()
+        .position(|name| name == cmd_name)?;
+
+    Some((
+        event,
+        idx,
+        effective_retry_policy(deployment, &event.commands[idx]),
+    ))
+}
+
+/// Проверяет, настроена ли команда цепочки игнорировать ошибки (`ignore_errors`)
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоев
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+/// * `cmd_name` - Имя команды в цепочке
+///
+/// # Возвращаемое значение
+///
+/// true, если ошибка команды не прерывает событие
+pub fn is_ignored_command(
+    config: &Config,
+    deployment_name: &str,
+    event_name: &str,
+    cmd_name: &str,
+) -> bool {
+    find_chain_command(config, deployment_name, event_name, cmd_name)
+        .is_some_and(|(event, idx, _)| event.commands[idx].ignore_errors.unwrap_or(false))
+}
+
+/// Проверяет, разрешен ли код завершения команды цепочки (`allow_exit_codes`)
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоев
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+/// * `cmd_name` - Имя команды в цепочке
+/// * `exit_code` - Код завершения команды
+///
+/// # Возвращаемое значение
+///
+/// true, если команда с этим кодом завершения считается успешной
+pub fn is_allowed_exit_code(
+    config: &Config,
+    deployment_name: &str,
+    event_name: &str,
+    cmd_name: &str,
+    exit_code: Option<i32>,
+) -> bool {
+    find_chain_command(config, deployment_name, event_name, cmd_name)
+        .is_some_and(|(event, idx, _)| event.commands[idx].allows_exit_code(exit_code))
+}
+
+/// Определяет политику повторов команды с учетом политики деплоя
+///
+/// Не заданные у команды параметры повторов наследуются от `retry_policy` деплоя.
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+/// * `cmd` - Конфигурация команды
+///
+/// # Возвращаемое значение
+///
+/// Действующая политика повторов
+pub fn effective_retry_policy(
+    deployment: &crate::config::Deployment,
+    cmd: &crate::config::Command,
+) -> RetryPolicy {
+    cmd.retry_policy(deployment.retry_policy.as_ref())
+}
+
 /// Находит деплойную конфигурацию и событие по имени
 ///
 /// # Параметры
@@ -63,15 +378,143 @@ fn find_deployment_and_event<'a>(
 
 /// Определяет переменные окружения для деплоя
 ///
+/// Переменные из файлов `env_files` переопределяются записями `environment`,
+/// а те - переменными из `--env`.
+///
 /// # Параметры
 ///
 /// * `deployment` - Конфигурация деплоя
 /// * `deployment_name` - Имя деплоя
+/// * `overrides` - Переменные из `--env`, заменяющие одноименные переменные деплоя
 ///
 /// # Возвращаемое значение
 ///
 /// Вектор пар (имя_переменной, значение_переменной)
-fn determine_environment_variables(
+pub fn determine_environment_variables(
+    deployment: &crate::config::Deployment,
+    deployment_name: &str,
+    overrides: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut env_vars = env_file_variables(deployment, deployment_name);
+
+    for (key, value) in deployment_environment_variables(deployment, deployment_name) {
+        set_environment_variable(&mut env_vars, &key, &value);
+    }
+
+    for (key, value) in overrides {
+        if set_environment_variable(&mut env_vars, key, value) {
+            debug!(
+                "Переменная окружения '{}' деплоя '{}' переопределена флагом --env",
+                key, deployment_name
+            );
+        }
+    }
+
+    env_vars
+}
+
+/// Задает значение переменной окружения в списке, заменяя прежнее значение
+///
+/// # Возвращаемое значение
+///
+/// true, если переменная уже была в списке
+fn set_environment_variable(env_vars: &mut Vec<(String, String)>, key: &str, value: &str) -> bool {
+    match env_vars.iter_mut().find(|(name, _)| name == key) {
+        Some(existing) => {
+            existing.1 = value.to_string();
+            true
+        }
+        None => {
+            env_vars.push((key.to_string(), value.to_string()));
+            false
+        }
+    }
+}
+
+/// Загружает переменные окружения из файлов `env_files` деплоя
+///
+/// Файлы читаются по порядку, значения из следующих файлов переопределяют
+/// предыдущие. Нечитаемые файлы пропускаются с предупреждением.
+fn env_file_variables(
+    deployment: &crate::config::Deployment,
+    deployment_name: &str,
+) -> Vec<(String, String)> {
+    let mut env_vars = Vec::new();
+
+    for path in deployment.env_files.iter().flatten() {
+        match load_env_file(path) {
+            Ok(variables) => {
+                debug!(
+                    "Загружено {} переменных окружения деплоя '{}' из файла {}",
+                    variables.len(),
+                    deployment_name,
+                    path
+                );
+                for (key, value) in variables {
+                    set_environment_variable(&mut env_vars, &key, &value);
+                }
+            }
+            Err(e) => warn!(
+                "Файл окружения деплоя '{}' пропущен: {:#}",
+                deployment_name, e
+            ),
+        }
+    }
+
+    env_vars
+}
+
+/// Загружает переменные окружения из файла в формате `.env`
+///
+/// Поддерживаются строки `KEY=VALUE` и `export KEY=VALUE`, комментарии `#` и
+/// значения в одинарных или двойных кавычках (кавычки удаляются).
+///
+/// # Параметры
+///
+/// * `path` - Путь к файлу
+///
+/// # Возвращаемое значение
+///
+/// Переменные в порядке следования в файле или ошибка чтения или формата
+pub fn load_env_file(path: &str) -> Result<Vec<(String, String)>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Не удалось прочитать файл окружения {}", path))?;
+
+    let mut variables = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => (key.trim(), value.trim()),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "{}:{}: строка не соответствует формату KEY=VALUE",
+                    path,
+                    number + 1
+                ))
+            }
+        };
+
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| {
+                value
+                    .strip_prefix(*quote)
+                    .and_then(|rest| rest.strip_suffix(*quote))
+            })
+            .unwrap_or(value);
+        variables.push((key.to_string(), value.to_string()));
+    }
+
+    Ok(variables)
+}
+
+/// Разбирает переменные окружения из поля `environment` деплоя
+fn deployment_environment_variables(
     deployment: &crate::config::Deployment,
     deployment_name: &str,
 ) -> Vec<(String, String)> {
@@ -86,6 +529,10 @@ fn determine_environment_variables(
                     if parts.len() == 2 {
                         Some((parts[0].to_string(), parts[1].to_string()))
                     } else {
+                        warn!(
+                            "Переменная окружения '{}' деплоя '{}' не соответствует формату KEY=VALUE и будет пропущена",
+                            var, deployment_name
+                        );
                         None
                     }
                 })
@@ -166,7 +613,7 @@ fn create_command_chain(
     let chain = ChainBuilder::new(chain_name)
         .execution_mode(chain_mode)
         .logger(logger)
-        .rollback_on_error(true) // Включаем откат при ошибке
+        .rollback_on_error(false) // Откат выполняется отдельно только для запущенных команд
         .build();
 
     info!(
@@ -182,40 +629,115 @@ fn create_command_chain(
     chain
 }
 
+/// Определяет рабочую директорию команды
+///
+/// Абсолютный путь команды используется как есть, относительный
+/// присоединяется к рабочей директории деплоя.
+///
+/// # Параметры
+///
+/// * `deployment_dir` - Рабочая директория деплоя
+/// * `command_dir` - Рабочая директория команды
+///
+/// # Возвращаемое значение
+///
+/// Итоговая рабочая директория или None, если ни одна не задана
+fn command_working_dir(deployment_dir: Option<&str>, command_dir: Option<&str>) -> Option<String> {
+    match (deployment_dir, command_dir) {
+        (Some(base), Some(dir)) => Some(Path::new(base).join(dir).to_string_lossy().into_owned()),
+        (None, Some(dir)) => Some(dir.to_string()),
+        (base, None) => base.map(str::to_string),
+    }
+}
+
+/// Параметры деплоя и события, общие для всех команд цепочки
+#[derive(Clone, Copy)]
+struct ChainContext<'a> {
+    /// Имя деплоя
+    deployment_name: &'a str,
+    /// Имя события
+    event_name: &'a str,
+    /// Конфигурация события
+    event: &'a crate::config::Event,
+    /// Рабочая директория деплоя
+    working_dir: Option<&'a str>,
+    /// Переменные окружения
+    env_vars: &'a [(String, String)],
+    /// Локальный файл переменных деплоя
+    variables_file: Option<&'a str>,
+    /// Переменные, заданные в конфигурации деплоя
+    deployment_variables: Option<&'a HashMap<String, String>>,
+    /// Глобальный файл переменных
+    global_variables_file: Option<&'a str>,
+    /// Имя цепочки команд
+    chain_name: &'a str,
+    /// Заключать подставляемые значения переменных в кавычки
+    quote_variables: bool,
+    /// Поведение при отсутствии значения переменной
+    on_missing_var: MissingVarPolicy,
+    /// Параметры запуска из командной строки
+    options: &'a RunOptions,
+}
+
 /// Добавляет команды в цепочку
 ///
 /// # Параметры
 ///
 /// * `chain` - Цепочка команд
-/// * `deployment_name` - Имя деплоя
-/// * `event_name` - Имя события
-/// * `event` - Конфигурация события
-/// * `working_dir` - Рабочая директория
-/// * `env_vars` - Переменные окружения
-/// * `variables_file` - Локальный файл переменных
-/// * `global_variables_file` - Глобальный файл переменных
-/// * `chain_name` - Имя цепочки команд
+/// * `context` - Параметры деплоя и события
+/// * `first_command` - Индекс команды события, с которой начинается цепочка
 ///
 /// # Возвращаемое значение
 ///
-/// Цепочка команд с добавленными командами и статистика
+/// Цепочка команд с добавленными командами, статистика, команды отката и временные
+/// файлы стандартного ввода или ошибка подстановки переменных
 fn add_commands_to_chain(
     mut chain: command_system::chain::CommandChain,
-    deployment_name: &str,
-    event_name: &str,
-    event: &crate::config::Event,
-    working_dir: Option<&str>,
-    env_vars: &[(String, String)],
-    variables_file: Option<&str>,
-    global_variables_file: Option<&str>,
-    chain_name: &str,
-) -> (command_system::chain::CommandChain, CommandStats) {
+    context: ChainContext,
+    first_command: usize,
+) -> Result<(
+    command_system::chain::CommandChain,
+    CommandStats,
+    RollbackCommands,
+    StdinFiles,
+)> {
+    let ChainContext {
+        deployment_name,
+        event_name,
+        event,
+        working_dir,
+        env_vars,
+        variables_file,
+        deployment_variables,
+        global_variables_file,
+        chain_name,
+        quote_variables,
+        on_missing_var,
+        options,
+    } = context;
+
     // Подсчет команд с различными параметрами для информации
     let mut stats = CommandStats::default();
+    let mut rollbacks = RollbackCommands::new();
+    let mut stdin_files = StdinFiles::new();
 
     // Собираем команды в цепочку с учетом рабочей директорий и переменных окружения
-    for (idx, cmd) in event.commands.iter().enumerate() {
-        let cmd_name = format!("{}_{}_cmd_{}", deployment_name, event_name, idx + 1);
+    let names = command_names(deployment_name, event_name, &event.commands);
+    for (position, (cmd, cmd_name)) in event.commands.iter().zip(names).enumerate() {
+        if position < first_command {
+            continue;
+        }
+        // В последовательном режиме команда с {#__PREV_EXIT__} выполняется следующей цепочкой
+        if position > first_command
+            && event.fail_fast.unwrap_or(true)
+            && uses_previous_exit_code(cmd)
+        {
+            debug!(
+                "Команда '{}' использует код завершения предыдущей команды, цепочка завершается перед ней",
+                cmd_name
+            );
+            break;
+        }
         trace!(
             "Добавление команды '{}' в цепочку: {}",
             cmd_name,
@@ -229,7 +751,7 @@ fn add_commands_to_chain(
 
         // Определяем команду отката
         let rollback_cmd = if !ignore_errors {
-            let rollback = cmd.rollback_command.as_deref();
+            let rollback = cmd.rollback_command.as_ref().map(RollbackCommand::steps);
             if rollback.is_some() {
                 stats.commands_with_rollback += 1;
             }
@@ -247,17 +769,93 @@ fn add_commands_to_chain(
         // Проверяем, есть ли у команды свой файл с переменными
         let cmd_variables_file = cmd.variables_file.as_deref().or(variables_file);
 
+        // Переменные из конфигурации подставляются только нами, а не command_system
+        let inline_variables = cmd.inline_variables(deployment_variables);
+
+        // Определяем рабочую директорию команды относительно директории деплоя
+        let cmd_working_dir = command_working_dir(working_dir, cmd.working_dir.as_deref());
+        if cmd.working_dir.is_some() {
+            debug!(
+                "Рабочая директория команды '{}': {}",
+                cmd_name,
+                cmd_working_dir.as_deref().unwrap_or_default()
+            );
+        }
+
+        // При экранировании, особой политике для отсутствующих переменных или
+        // переменных из конфигурации подставляем переменные самостоятельно
+        let command_str = if has_variables
+            && (quote_variables
+                || on_missing_var != MissingVarPolicy::Keep
+                || !inline_variables.is_empty()
+                || !options.variables.is_empty()
+                || uses_remote_variables(cmd_variables_file, global_variables_file))
+        {
+            substitute_file_variables(
+                &cmd.command,
+                &inline_variables,
+                &options.variables,
+                cmd_variables_file,
+                global_variables_file,
+                quote_variables,
+                on_missing_var,
+            )
+            .with_context(|| format!("Ошибка подстановки переменных в команду '{}'", cmd_name))?
+        } else {
+            cmd.command.clone()
+        };
+
+        // Передаем команде стандартный ввод из строки или файла конфигурации
+        let stdin_file = command_stdin_file(
+            cmd,
+            &cmd_name,
+            &inline_variables,
+            &options.variables,
+            cmd_variables_file,
+            global_variables_file,
+            on_missing_var,
+        )?;
+        let command_str = match stdin_file {
+            Some(StdinSource::File(path)) => {
+                debug!("Стандартный ввод команды '{}': {}", cmd_name, path);
+                command_executor::redirect_stdin(&command_str, &path)
+            }
+            Some(StdinSource::Temp(path)) => {
+                debug!(
+                    "Стандартный ввод команды '{}': {}",
+                    cmd_name,
+                    path.display()
+                );
+                let command_str =
+                    command_executor::redirect_stdin(&command_str, &path.to_string_lossy());
+                stdin_files.push(path);
+                command_str
+            }
+            None => command_str,
+        };
+
+        // Трассировка оборачивает команду целиком, включая перенаправление ввода
+        let command_str = if cmd.trace.unwrap_or(false) {
+            debug!("Трассировка шагов команды '{}' включена", cmd_name);
+            command_executor::enable_trace(&command_str)
+        } else {
+            command_str
+        };
+
+        // В режиме имитации команда заменяется после всех подстановок и преобразований
+        let command_str = options.command_to_run(&command_str);
+
         // Создаем команду с учетом переменных
         let command = if has_variables || cmd.interactive.unwrap_or(false) {
             // Используем переменные, если они указаны
             command_executor::create_command(
                 &cmd_name,
-                &cmd.command,
-                working_dir,
+                &command_str,
+                cmd_working_dir.as_deref(),
                 env_vars,
-                rollback_cmd,
+                None,
                 true,
-                cmd.inputs.clone(),
+                merge_inputs(cmd.inputs.as_ref(), &options.inputs),
                 cmd_variables_file,
                 global_variables_file,
             )
@@ -265,30 +863,191 @@ fn add_commands_to_chain(
             // Для обычных команд используем простое создание
             command_executor::create_simple_command(
                 &cmd_name,
-                &cmd.command,
-                working_dir,
+                &command_str,
+                cmd_working_dir.as_deref(),
                 env_vars,
-                rollback_cmd,
+                None,
             )
         };
 
         // Логируем информацию о команде
         log_command_details(
             cmd,
-            rollback_cmd,
+            rollback_cmd.as_deref(),
             has_variables,
             cmd_variables_file,
             global_variables_file,
         );
 
+        // Шаги отката выполняются отдельно, только если команда была запущена
+        if let Some(steps) = rollback_cmd {
+            let rollback_commands = steps
+                .iter()
+                .enumerate()
+                .map(|(step, rollback)| {
+                    command_executor::create_simple_command(
+                        &format!("{}_rollback_{}", cmd_name, step + 1),
+                        &options.command_to_run(rollback),
+                        cmd_working_dir.as_deref(),
+                        env_vars,
+                        None,
+                    )
+                })
+                .collect();
+            rollbacks.insert(cmd_name.clone(), rollback_commands);
+        }
+
         chain.add_command(command);
         info!(
             "Добавлена команда '{}' в цепочку '{}'",
             cmd_name, chain_name
         );
+
+        // В последовательном режиме вывод команды проверяется до запуска следующих команд
+        if event.fail_fast.unwrap_or(true) && cmd.has_output_assertions() {
+            debug!(
+                "Команда '{}' проверяет вывод, цепочка завершается после нее",
+                cmd_name
+            );
+            break;
+        }
     }
 
-    (chain, stats)
+    Ok((chain, stats, rollbacks, stdin_files))
+}
+
+/// Объединяет ответы на интерактивные запросы из конфигурации и командной строки
+///
+/// # Параметры
+///
+/// * `config_inputs` - Ответы, заданные в конфигурации команды
+/// * `cli_inputs` - Ответы, переданные через `--input` (имеют приоритет)
+///
+/// # Возвращаемое значение
+///
+/// Объединенная карта ответов или None, если ответов нет
+fn merge_inputs(
+    config_inputs: Option<&HashMap<String, String>>,
+    cli_inputs: &HashMap<String, String>,
+) -> Option<HashMap<String, String>> {
+    if config_inputs.is_none() && cli_inputs.is_empty() {
+        return None;
+    }
+
+    let mut inputs = config_inputs.cloned().unwrap_or_default();
+    inputs.extend(cli_inputs.iter().map(|(k, v)| (k.clone(), v.clone())));
+    Some(inputs)
+}
+
+/// Источник стандартного ввода команды
+enum StdinSource {
+    /// Файл из поля `stdin_file`
+    File(String),
+    /// Временный файл с данными поля `stdin`, удаляемый после выполнения цепочки
+    Temp(tempfile::TempPath),
+}
+
+/// Определяет файл, из которого команда получает стандартный ввод
+///
+/// Данные из поля `stdin` записываются во временный файл после подстановки переменных.
+///
+/// # Параметры
+///
+/// * `cmd` - Конфигурация команды
+/// * `cmd_name` - Уникальное имя команды
+/// * `inline_variables` - Переменные, заданные в конфигурации деплоя и команды
+/// * `cli_variables` - Переменные из `--var`
+/// * `cmd_variables_file` - Локальный файл переменных
+/// * `global_variables_file` - Глобальный файл переменных
+/// * `on_missing_var` - Поведение при отсутствии значения переменной
+///
+/// # Возвращаемое значение
+///
+/// Источник стандартного ввода, None если ввод не задан, или ошибка
+fn command_stdin_file(
+    cmd: &crate::config::Command,
+    cmd_name: &str,
+    inline_variables: &HashMap<String, String>,
+    cli_variables: &HashMap<String, String>,
+    cmd_variables_file: Option<&str>,
+    global_variables_file: Option<&str>,
+    on_missing_var: MissingVarPolicy,
+) -> Result<Option<StdinSource>> {
+    match (&cmd.stdin, &cmd.stdin_file) {
+        (Some(_), Some(_)) => Err(anyhow::anyhow!(
+            "Для команды '{}' нельзя одновременно указать stdin и stdin_file",
+            cmd_name
+        )),
+        (Some(content), None) => {
+            let content = substitute_file_variables(
+                content,
+                inline_variables,
+                cli_variables,
+                cmd_variables_file,
+                global_variables_file,
+                false,
+                on_missing_var,
+            )
+            .with_context(|| {
+                format!(
+                    "Ошибка подстановки переменных в стандартный ввод команды '{}'",
+                    cmd_name
+                )
+            })?;
+            command_executor::write_stdin_file(cmd_name, &content)
+                .map(|path| Some(StdinSource::Temp(path)))
+        }
+        (None, Some(path)) => Ok(Some(StdinSource::File(path.clone()))),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Подставляет переменные из конфигурации и файлов в команду
+///
+/// Значения из файлов переменных имеют приоритет над значениями из конфигурации,
+/// а переменные из `--var` - над всеми остальными.
+///
+/// # Параметры
+///
+/// * `command` - Строка с командой
+/// * `inline_variables` - Переменные, заданные в конфигурации деплоя и команды
+/// * `cli_variables` - Переменные из `--var`
+/// * `cmd_variables_file` - Локальный файл переменных
+/// * `global_variables_file` - Глобальный файл переменных
+/// * `quote` - Заключать подставляемые значения в кавычки
+/// * `on_missing_var` - Поведение при отсутствии значения переменной
+///
+/// # Возвращаемое значение
+///
+/// Команда с подставленными значениями или ошибка при политике `Fail`
+fn substitute_file_variables(
+    command: &str,
+    inline_variables: &HashMap<String, String>,
+    cli_variables: &HashMap<String, String>,
+    cmd_variables_file: Option<&str>,
+    global_variables_file: Option<&str>,
+    quote: bool,
+    on_missing_var: MissingVarPolicy,
+) -> Result<String> {
+    let file_variables =
+        match command_executor::load_variables_from_file(cmd_variables_file, global_variables_file)
+        {
+            Ok(variables) => variables,
+            Err(e) if on_missing_var == MissingVarPolicy::Fail => return Err(e),
+            Err(e) => {
+                warn!("Не удалось загрузить переменные для подстановки: {}", e);
+                HashMap::new()
+            }
+        };
+
+    let mut variables = inline_variables.clone();
+    variables.extend(file_variables);
+    variables.extend(cli_variables.clone());
+
+    let substituted =
+        command_executor::replace_file_variables(command, &variables, quote, on_missing_var)?;
+    debug!("Команда после подстановки переменных: {}", substituted);
+    Ok(substituted)
 }
 
 /// Логирует детали о создаваемой команде
@@ -302,7 +1061,7 @@ fn add_commands_to_chain(
 /// * `global_variables_file` - Глобальный файл переменных
 fn log_command_details(
     cmd: &crate::config::Command,
-    rollback_cmd: Option<&str>,
+    rollback_cmd: Option<&[&str]>,
     has_variables: bool,
     cmd_variables_file: Option<&str>,
     global_variables_file: Option<&str>,
@@ -312,7 +1071,8 @@ fn log_command_details(
     } else if let Some(rollback) = rollback_cmd {
         debug!(
             "Команда '{}' настроена с откатом: {}",
-            cmd.command, rollback
+            cmd.command,
+            rollback.join(" && ")
         );
     }
 
@@ -360,16 +1120,30 @@ struct CommandStats {
 /// * `deployment_name` - Имя деплоя
 /// * `event_name` - Имя события
 /// * `global_variables_file` - Опциональный путь к глобальному файлу с переменными
+/// * `options` - Параметры запуска из командной строки
+/// * `first_command` - Индекс команды события, с которой начинается цепочка
+///   (больше 0 при повторном запуске упавшей команды)
+///
+/// Цепочка заканчивается перед следующей командой, использующей `{#__PREV_EXIT__}`,
+/// и после команды с проверками вывода: оставшиеся команды выполняются отдельной
+/// цепочкой после проверки результата.
 ///
 /// # Возвращаемое значение
 ///
-/// Настроенная цепочка команд, готовая к выполнению
+/// Настроенная цепочка команд, готовая к выполнению, команды отката и временные
+/// файлы стандартного ввода, которые нужно хранить до завершения цепочки
 pub fn build_command_chain(
     config: &Config,
     deployment_name: &str,
     event_name: &str,
     global_variables_file: Option<&str>,
-) -> Result<command_system::chain::CommandChain> {
+    options: &RunOptions,
+    first_command: usize,
+) -> Result<(
+    command_system::chain::CommandChain,
+    RollbackCommands,
+    StdinFiles,
+)> {
     // Засекаем время для метрик производительности
     let start_time = Instant::now();
     trace!(
@@ -396,10 +1170,23 @@ pub fn build_command_chain(
     }
 
     // Определяем переменные окружения
-    let env_vars = determine_environment_variables(deployment, deployment_name);
+    let env_vars = determine_environment_variables(deployment, deployment_name, &options.env);
 
     // Определяем файл с переменными, если указан в деплойменте
     let variables_file = deployment.variables_file.as_deref();
+    let quote_variables = deployment.quote_variables.unwrap_or(false);
+    if quote_variables {
+        debug!(
+            "Для деплоя '{}' включено экранирование значений переменных",
+            deployment_name
+        );
+    }
+
+    // Политика для отсутствующих переменных: командная строка имеет приоритет
+    let on_missing_var = options
+        .on_missing_var
+        .or(deployment.on_missing_var)
+        .unwrap_or_default();
 
     // Логируем информацию о переменных
     log_variables_info(variables_file, global_variables_file);
@@ -412,28 +1199,406 @@ pub fn build_command_chain(
     let chain = create_command_chain(&chain_name, chain_mode);
 
     // Добавляем команды в цепочку
-    let (chain, stats) = add_commands_to_chain(
-        chain,
+    let context = ChainContext {
         deployment_name,
         event_name,
         event,
         working_dir,
-        &env_vars,
+        env_vars: &env_vars,
         variables_file,
+        deployment_variables: deployment.variables.as_ref(),
         global_variables_file,
-        &chain_name,
-    );
+        chain_name: &chain_name,
+        quote_variables,
+        on_missing_var,
+        options,
+    };
+    let (chain, stats, rollbacks, stdin_files) =
+        add_commands_to_chain(chain, context, first_command)?;
 
     let duration = start_time.elapsed();
     info!(
         "Цепочка '{}' построена за {:.2} мс: {} команд, {} с откатом, {} с переменными, {} игнорируют ошибки",
         chain_name,
         duration.as_millis(),
-        event.commands.len(),
+        event.commands.len() - first_command.min(event.commands.len()),
         stats.commands_with_rollback,
         stats.commands_with_variables,
         stats.commands_ignoring_errors
     );
 
-    Ok(chain)
+    Ok((chain, rollbacks, stdin_files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn cli_input_overrides_config_input() {
+        let config_inputs = map(&[("version", "1.0.0"), ("name", "yaml")]);
+        let cli_inputs = map(&[("version", "2.0.0")]);
+
+        let inputs = merge_inputs(Some(&config_inputs), &cli_inputs).unwrap();
+
+        assert_eq!(inputs["version"], "2.0.0");
+        assert_eq!(inputs["name"], "yaml");
+    }
+
+    #[test]
+    fn relative_command_dir_joins_deployment_dir() {
+        assert_eq!(
+            command_working_dir(Some("/srv/app"), Some("frontend")),
+            Some(
+                Path::new("/srv/app")
+                    .join("frontend")
+                    .to_string_lossy()
+                    .into_owned()
+            )
+        );
+        assert_eq!(
+            command_working_dir(None, Some("frontend")),
+            Some("frontend".to_string())
+        );
+        assert_eq!(
+            command_working_dir(Some("/srv/app"), None),
+            Some("/srv/app".to_string())
+        );
+        assert_eq!(command_working_dir(None, None), None);
+    }
+
+    #[test]
+    fn absolute_command_dir_overrides_deployment_dir() {
+        assert_eq!(
+            command_working_dir(Some("/srv/app"), Some("/opt/tools")),
+            Some("/opt/tools".to_string())
+        );
+    }
+
+    #[test]
+    fn no_inputs_without_config_and_cli_values() {
+        assert_eq!(merge_inputs(None, &HashMap::new()), None);
+        assert_eq!(
+            merge_inputs(None, &map(&[("name", "cli")])),
+            Some(map(&[("name", "cli")]))
+        );
+    }
+
+    #[test]
+    fn progress_prefix_shows_position_and_total() {
+        assert_eq!(progress_prefix(3, 8), "[3/8]");
+        assert_eq!(progress_prefix(1, 1), "[1/1]");
+    }
+
+    #[test]
+    fn command_progress_is_keyed_by_chain_command_name() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "npm ci"
+          - command: "npm run build"
+            name: build
+"#,
+        )
+        .unwrap();
+
+        let progress = command_progress(&config, "app", "deploy");
+
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress["app_deploy_cmd_1"], "[1/2] npm ci");
+        assert_eq!(progress["app_deploy_build"], "[2/2] npm run build");
+        assert!(command_progress(&config, "app", "missing").is_empty());
+    }
+
+    #[test]
+    fn env_overrides_replace_deployment_environment() {
+        let deployment: crate::config::Deployment = serde_yaml::from_str(
+            r#"
+name: app
+environment:
+  - "APP_ENV=production"
+  - "APP_PORT=8080"
+events: []
+"#,
+        )
+        .unwrap();
+        let overrides = vec![
+            ("APP_PORT".to_string(), "9090".to_string()),
+            ("DEBUG".to_string(), "1".to_string()),
+        ];
+
+        assert_eq!(
+            determine_environment_variables(&deployment, "app", &overrides),
+            vec![
+                ("APP_ENV".to_string(), "production".to_string()),
+                ("APP_PORT".to_string(), "9090".to_string()),
+                ("DEBUG".to_string(), "1".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn chain_runs_in_working_dir_without_changing_process_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: Config = serde_yaml::from_str(&format!(
+            r#"
+deployments:
+  - name: app
+    working_dir: "{}"
+    events:
+      - name: deploy
+        commands:
+          - command: "pwd > cwd.txt"
+"#,
+            dir.path().display()
+        ))
+        .unwrap();
+        let process_cwd = std::env::current_dir().unwrap();
+
+        let (chain, _, _stdin_files) =
+            build_command_chain(&config, "app", "deploy", None, &RunOptions::default(), 0).unwrap();
+        let result = chain.execute().await.unwrap();
+
+        assert!(result.success);
+        assert_eq!(std::env::current_dir().unwrap(), process_cwd);
+        let command_cwd = std::fs::read_to_string(dir.path().join("cwd.txt")).unwrap();
+        assert_eq!(
+            Path::new(command_cwd.trim()).canonicalize().unwrap(),
+            dir.path().canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn command_names_use_sanitized_unique_names() {
+        let commands: Vec<crate::config::Command> = serde_yaml::from_str(
+            r#"
+- command: "./migrate.sh"
+  name: "db migrate"
+- command: "make build"
+- command: "./migrate.sh --again"
+  name: "db migrate"
+- command: "echo"
+  name: "  "
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            command_names("app", "deploy", &commands),
+            vec![
+                "app_deploy_db_migrate",
+                "app_deploy_cmd_2",
+                "app_deploy_db_migrate_3",
+                "app_deploy_cmd_4",
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_commands_using_previous_exit_code() {
+        let commands: Vec<crate::config::Command> = serde_yaml::from_str(
+            r#"
+- command: "./healthcheck.sh"
+- command: "echo {#__PREV_EXIT__}"
+- command: "echo {#PREV_EXIT}"
+"#,
+        )
+        .unwrap();
+
+        let uses: Vec<bool> = commands.iter().map(uses_previous_exit_code).collect();
+        assert_eq!(uses, vec![false, true, false]);
+    }
+
+    #[test]
+    fn cli_variables_override_file_and_inline_variables() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("variables.json");
+        std::fs::write(&file, r#"{ "TARGET": "file", "BRANCH": "file" }"#).unwrap();
+
+        let command = substitute_file_variables(
+            "deploy {#TARGET} {#BRANCH} {#MODE}",
+            &map(&[("TARGET", "inline"), ("MODE", "inline")]),
+            &map(&[("TARGET", "cli")]),
+            file.to_str(),
+            None,
+            false,
+            MissingVarPolicy::Fail,
+        )
+        .unwrap();
+
+        assert_eq!(command, "deploy cli file inline");
+    }
+
+    #[test]
+    fn env_file_supports_export_comments_and_quotes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".env");
+        std::fs::write(
+            &path,
+            "# база данных\nDB_HOST=db.local\nexport DB_USER='deploy'\n\nDB_PASS=\"p@ss word\"\nURL=http://host/?a=b\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            load_env_file(path.to_str().unwrap()).unwrap(),
+            vec![
+                ("DB_HOST".to_string(), "db.local".to_string()),
+                ("DB_USER".to_string(), "deploy".to_string()),
+                ("DB_PASS".to_string(), "p@ss word".to_string()),
+                ("URL".to_string(), "http://host/?a=b".to_string()),
+            ]
+        );
+
+        std::fs::write(&path, "DB_HOST=db.local\nnot a variable\n").unwrap();
+        let error = load_env_file(path.to_str().unwrap()).unwrap_err();
+        assert!(error.to_string().contains(":2:"), "{}", error);
+    }
+
+    #[test]
+    fn env_files_have_lowest_environment_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.env");
+        let second = dir.path().join("second.env");
+        std::fs::write(&first, "FROM_FILE=first\nAPP_ENV=file\nAPP_PORT=1\n").unwrap();
+        std::fs::write(&second, "FROM_FILE=second\n").unwrap();
+
+        let deployment: crate::config::Deployment = serde_yaml::from_str(&format!(
+            r#"
+name: app
+env_files: ["{}", "{}", "missing.env"]
+environment:
+  - "APP_ENV=production"
+  - "APP_PORT=8080"
+events: []
+"#,
+            first.display(),
+            second.display()
+        ))
+        .unwrap();
+        let overrides = vec![("APP_PORT".to_string(), "9090".to_string())];
+
+        assert_eq!(
+            determine_environment_variables(&deployment, "app", &overrides),
+            vec![
+                ("FROM_FILE".to_string(), "second".to_string()),
+                ("APP_ENV".to_string(), "production".to_string()),
+                ("APP_PORT".to_string(), "9090".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn command_groups_maps_grouped_commands() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "./db.sh backup"
+          - command: "./migrate.sh up"
+            group: release
+          - command: "./switch.sh new"
+            name: switch
+            group: release
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            command_groups(&config, "app", "deploy"),
+            map(&[
+                ("app_deploy_cmd_2", "release"),
+                ("app_deploy_switch", "release")
+            ])
+        );
+    }
+
+    #[test]
+    fn quiet_commands_lists_only_quiet_commands() {
+        let config: Config = serde_yaml::from_str(
+            r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "npm ci"
+            quiet: true
+          - command: "npm run build"
+          - command: "npm test"
+            name: test
+            quiet: true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            quiet_commands(&config, "app", "deploy"),
+            HashSet::from([
+                "app_deploy_cmd_1".to_string(),
+                "app_deploy_test".to_string()
+            ])
+        );
+        assert!(quiet_commands(&config, "app", "missing").is_empty());
+    }
+
+    fn retry_deployment() -> crate::config::Deployment {
+        serde_yaml::from_str(
+            r#"
+name: app
+retry_policy:
+  retries: 3
+  delay: 2
+  backoff_factor: 1.5
+events:
+  - name: deploy
+    commands:
+      - command: "make deploy"
+      - command: "make migrate"
+        retry_policy:
+          retries: 1
+      - command: "make notify"
+        retry_policy:
+          retries: 0
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn command_without_policy_uses_deployment_retry_policy() {
+        let deployment = retry_deployment();
+        let policy = effective_retry_policy(&deployment, &deployment.events[0].commands[0]);
+
+        assert_eq!(policy.retries, Some(3));
+        assert_eq!(policy.delay, Some(2.0));
+        assert_eq!(policy.backoff_factor, Some(1.5));
+    }
+
+    #[test]
+    fn command_retry_policy_overrides_deployment_policy() {
+        let deployment = retry_deployment();
+
+        let policy = effective_retry_policy(&deployment, &deployment.events[0].commands[1]);
+        assert_eq!(policy.retries, Some(1));
+        assert_eq!(policy.delay, Some(2.0));
+        assert_eq!(policy.backoff_factor, Some(1.5));
+
+        let policy = effective_retry_policy(&deployment, &deployment.events[0].commands[2]);
+        assert_eq!(policy.retries, Some(0));
+        assert!(!policy.should_retry(Some(1), 0));
+    }
 }
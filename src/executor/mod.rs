@@ -10,12 +10,14 @@
 - Поддержка шаблонных переменных в командах
 - Использование переменных из окружения и файлов
 - Поддержка глобальных переменных из settings.json
+- Загрузка переменных из удаленных источников
 
 ## Структура модуля
 
 - `command_executor` - выполнение команд через SystemCommand
 - `chain_builder` - создание и настройка цепочек команд
 - `runner` - запуск цепочек команд с обработкой ошибок и откатом
+- `remote_variables` - загрузка файлов переменных по HTTP/HTTPS
 
 ## Основные функции
 
@@ -25,7 +27,11 @@
 
 mod chain_builder;
 mod command_executor;
+mod remote_variables;
 pub mod runner;
 
 // Реэкспорт публичных функций из подмодулей
-pub use runner::run_commands;
+pub use chain_builder::{determine_environment_variables, load_env_file};
+pub use command_executor::{load_variables_from_file, replace_file_variables};
+pub use remote_variables::is_remote_variables_file;
+pub use runner::{run_commands, run_deployment_command, RunOptions};
@@ -0,0 +1,295 @@
+/*!
+# Подмодуль Remote Variables
+
+Отвечает за загрузку переменных из удаленных источников:
+
+- Загрузка файлов переменных по HTTP/HTTPS
+- Разбор JSON или YAML в зависимости от типа содержимого или расширения
+- Кэширование загруженных переменных в памяти на время запуска
+
+Загруженные значения (часто секреты) не записываются на диск: переменные подставляются
+в команды до их передачи Command System.
+*/
+
+use anyhow::{Context, Result};
+use log::{debug, info};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use crate::config::Config;
+
+/// Таймаут загрузки удаленного файла переменных
+const REMOTE_VARIABLES_TIMEOUT_SECS: u64 = 10;
+
+/// Переменные удаленного файла, загружаемые один раз за запуск
+type RemoteVariables = Arc<OnceCell<HashMap<String, String>>>;
+
+/// Кэш загруженных файлов: URL -> переменные
+static REMOTE_VARIABLES_CACHE: OnceLock<Mutex<HashMap<String, RemoteVariables>>> = OnceLock::new();
+
+/// Проверяет, указывает ли путь к файлу переменных на удаленный ресурс
+pub fn is_remote_variables_file(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Возвращает ячейку кэша для URL, создавая ее при первом обращении
+fn cache_entry(url: &str) -> RemoteVariables {
+    REMOTE_VARIABLES_CACHE
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(url.to_string())
+        .or_default()
+        .clone()
+}
+
+/// Загружает удаленный файл переменных
+///
+/// Повторные обращения к тому же URL в рамках одного запуска используют кэш; при
+/// одновременных обращениях из параллельных деплоев файл загружается один раз.
+///
+/// # Параметры
+///
+/// * `url` - URL файла переменных
+///
+/// # Возвращаемое значение
+///
+/// Хэш-карта с переменными или ошибка
+pub async fn load_remote_variables(url: &str) -> Result<HashMap<String, String>> {
+    let entry = cache_entry(url);
+    if entry.initialized() {
+        debug!("Используются кэшированные переменные для {}", url);
+    }
+
+    let variables = entry
+        .get_or_try_init(|| async {
+            let variables = fetch_remote_variables(url).await?;
+            info!(
+                "Загружено {} переменных из удаленного источника: {}",
+                variables.len(),
+                url
+            );
+            Ok::<_, anyhow::Error>(variables)
+        })
+        .await?;
+
+    Ok(variables.clone())
+}
+
+/// Возвращает переменные удаленного файла, загруженного ранее в этом запуске
+///
+/// # Параметры
+///
+/// * `url` - URL файла переменных
+///
+/// # Возвращаемое значение
+///
+/// Переменные или None, если файл еще не загружен
+pub fn cached_remote_variables(url: &str) -> Option<HashMap<String, String>> {
+    REMOTE_VARIABLES_CACHE
+        .get()?
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(url)?
+        .get()
+        .cloned()
+}
+
+/// Загружает и разбирает удаленный файл переменных
+///
+/// # Параметры
+///
+/// * `url` - URL файла переменных
+///
+/// # Возвращаемое значение
+///
+/// Хэш-карта с переменными или ошибка
+async fn fetch_remote_variables(url: &str) -> Result<HashMap<String, String>> {
+    info!("Загрузка переменных из удаленного источника: {}", url);
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(REMOTE_VARIABLES_TIMEOUT_SECS))
+        .build()
+        .context("Не удалось создать HTTP-клиент")?;
+
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Не удалось загрузить файл переменных: {}", url))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow::anyhow!(
+            "Сервер вернул статус {} при загрузке файла переменных: {}",
+            status,
+            url
+        ));
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let body = response
+        .text()
+        .await
+        .with_context(|| format!("Не удалось прочитать ответ сервера: {}", url))?;
+
+    let is_yaml = content_type.contains("yaml") || url.ends_with(".yml") || url.ends_with(".yaml");
+
+    let json: Value = if is_yaml {
+        serde_yaml::from_str(&body)
+            .with_context(|| format!("Неверный формат YAML в файле переменных: {}", url))?
+    } else {
+        serde_json::from_str(&body)
+            .with_context(|| format!("Неверный формат JSON в файле переменных: {}", url))?
+    };
+
+    let mut vars = HashMap::new();
+    if let Value::Object(map) = json {
+        for (key, value) in map {
+            if let Some(string_value) = value.as_str() {
+                vars.insert(key, string_value.to_string());
+            } else {
+                vars.insert(key, value.to_string());
+            }
+        }
+    }
+
+    Ok(vars)
+}
+
+/// Загружает удаленные файлы переменных деплоя и глобальный файл до построения цепочки
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоя
+/// * `deployment_name` - Имя деплоя
+/// * `global_variables_file` - Глобальный файл переменных
+///
+/// # Возвращаемое значение
+///
+/// Результат загрузки или ошибка первого недоступного файла
+pub async fn prefetch_remote_variables(
+    config: &Config,
+    deployment_name: &str,
+    global_variables_file: Option<&str>,
+) -> Result<()> {
+    let mut urls: Vec<&str> = global_variables_file.into_iter().collect();
+
+    if let Some(deployment) = config.find_deployment(deployment_name) {
+        urls.extend(deployment.variables_file.as_deref());
+        urls.extend(
+            deployment
+                .events
+                .iter()
+                .flat_map(|event| &event.commands)
+                .filter_map(|cmd| cmd.variables_file.as_deref()),
+        );
+    }
+
+    for url in urls
+        .into_iter()
+        .filter(|path| is_remote_variables_file(path))
+    {
+        load_remote_variables(url).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Запускает локальный HTTP-сервер, отвечающий одним и тем же ответом
+    ///
+    /// Возвращает адрес сервера и счетчик обработанных запросов.
+    async fn mock_server(
+        status: &'static str,
+        content_type: &'static str,
+        body: &'static str,
+    ) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut buffer = [0u8; 4096];
+                let _ = socket.read(&mut buffer).await;
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    content_type,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        (address, requests)
+    }
+
+    #[tokio::test]
+    async fn loads_json_variables_and_caches_them() {
+        let (address, requests) = mock_server(
+            "200 OK",
+            "application/json",
+            r#"{"HOST": "db.local", "PORT": 5432}"#,
+        )
+        .await;
+        let url = format!("{}/vars.json", address);
+
+        let variables = load_remote_variables(&url).await.unwrap();
+        assert_eq!(variables["HOST"], "db.local");
+        assert_eq!(variables["PORT"], "5432");
+
+        load_remote_variables(&url).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert_eq!(cached_remote_variables(&url), Some(variables));
+    }
+
+    #[tokio::test]
+    async fn concurrent_loads_fetch_once() {
+        let (address, requests) = mock_server("200 OK", "application/json", r#"{"A": "1"}"#).await;
+        let url = format!("{}/shared.json", address);
+
+        let (first, second) =
+            tokio::join!(load_remote_variables(&url), load_remote_variables(&url));
+        assert_eq!(first.unwrap(), second.unwrap());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn parses_yaml_by_content_type() {
+        let (address, _) = mock_server("200 OK", "application/yaml", "BRANCH: main\n").await;
+        let url = format!("{}/vars", address);
+
+        let variables = load_remote_variables(&url).await.unwrap();
+        assert_eq!(variables["BRANCH"], "main");
+    }
+
+    #[tokio::test]
+    async fn reports_non_success_status() {
+        let (address, _) = mock_server("404 Not Found", "text/plain", "missing").await;
+        let url = format!("{}/missing.json", address);
+
+        let error = load_remote_variables(&url).await.unwrap_err().to_string();
+        assert!(error.contains("404"), "{}", error);
+        assert_eq!(cached_remote_variables(&url), None);
+    }
+}
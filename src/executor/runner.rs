@@ -10,19 +10,122 @@
 - Расширенное логирование процесса выполнения команд
 */
 
-use crate::config::Config;
+use crate::config::{Config, ConfigFingerprint, Deployment, MissingVarPolicy};
 use crate::events::{EventEmitter, EventType};
-use crate::executor::chain_builder;
-use crate::settings;
+use crate::executor::chain_builder::{self, RollbackCommands};
+use crate::executor::command_executor;
+use crate::executor::remote_variables;
+use crate::output;
+use crate::settings::{self, CommandLogMode};
 use crate::storage;
+use crate::telemetry;
+use crate::timings;
+use crate::util;
 use anyhow::{Context, Result};
 use chrono;
-use command_system::CommandResult;
-use log::{error, info, trace, warn};
+use command_system::chain::command_chain::ChainResult;
+use command_system::command::ShellCommand;
+use command_system::{CommandExecution, CommandResult};
+use log::{debug, error, info, trace, warn};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::io::Write;
-use std::path::Path;
-use std::time::Instant;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Параметры запуска, переданные из командной строки
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Ответы на интерактивные запросы, имеющие приоритет над `inputs` из конфигурации
+    pub inputs: HashMap<String, String>,
+
+    /// Переменные `{#VAR}` из `--var`, имеющие приоритет над конфигурацией и файлами переменных
+    pub variables: HashMap<String, String>,
+
+    /// Переменные окружения из `--env`, имеющие приоритет над `environment` деплоя
+    pub env: Vec<(String, String)>,
+
+    /// Выполнять только события, не завершившиеся успешно после последнего полного деплоя
+    pub since_last_success: bool,
+
+    /// Поведение при отсутствии значения переменной (имеет приоритет над конфигурацией деплоя)
+    pub on_missing_var: Option<MissingVarPolicy>,
+
+    /// Показать план выполнения без запуска команд
+    pub dry_run: bool,
+
+    /// Проверить наличие внешних команд при выводе плана
+    pub check_commands: bool,
+
+    /// Вывести переменные окружения команд в плане выполнения
+    pub print_env: bool,
+
+    /// Буферизовать консольный вывод каждого деплоя при параллельном запуске
+    pub buffer_output: bool,
+
+    /// При ошибке команды спрашивать, повторить ее, пропустить или прервать событие
+    pub prompt_on_fail: bool,
+
+    /// Запускать деплои вне окна обслуживания и раньше минимального интервала
+    pub force: bool,
+
+    /// Завершать программу с ошибкой, если были проигнорированы ошибки команд
+    pub strict_ignored: bool,
+
+    /// Отпечаток конфигурации для проверки ее изменения между деплоями (None - без проверки)
+    pub config_fingerprint: Option<ConfigFingerprint>,
+
+    /// Код завершения имитации команд (`--simulate`); None - команды выполняются
+    pub simulate: Option<i32>,
+
+    /// Количество повторов события (`--repeat`); None - событие выполняется один раз
+    pub repeat: Option<u32>,
+
+    /// Пауза между повторами события (`--repeat-delay`)
+    pub repeat_delay: Duration,
+}
+
+impl RunOptions {
+    /// Возвращает команду для запуска с учетом режима имитации
+    ///
+    /// # Параметры
+    ///
+    /// * `command` - Строка с командой
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Исходная команда или ее имитация при `--simulate`
+    pub fn command_to_run(&self, command: &str) -> String {
+        match self.simulate {
+            Some(exit_code) => command_executor::simulate(command, exit_code),
+            None => command.to_string(),
+        }
+    }
+}
+
+/// Количество ошибок команд с `ignore_errors` за время работы программы
+static IGNORED_FAILURES: AtomicUsize = AtomicUsize::new(0);
+
+/// Возвращает количество ошибок команд, проигнорированных через `ignore_errors`
+///
+/// # Возвращаемое значение
+///
+/// Количество упавших команд в успешно завершенных событиях
+pub fn ignored_failure_count() -> usize {
+    IGNORED_FAILURES.load(Ordering::Relaxed)
+}
+
+/// Действие пользователя после ошибки команды
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailAction {
+    /// Повторить упавшую команду
+    Retry,
+    /// Пропустить упавшую команду и продолжить со следующей
+    Skip,
+    /// Прервать выполнение события
+    Abort,
+}
 
 /// Проверяет существование и создает рабочую директорию при необходимости
 ///
@@ -56,7 +159,7 @@ pub fn setup_working_directory(dir: &str) -> Result<()> {
 fn determine_global_variables_file(explicit_path: Option<&str>) -> Option<&str> {
     // Если файл указан явно, используем его
     if let Some(path) = explicit_path {
-        if Path::new(path).exists() {
+        if remote_variables::is_remote_variables_file(path) || Path::new(path).exists() {
             trace!(
                 "Используется явно указанный глобальный файл переменных: {}",
                 path
@@ -144,30 +247,71 @@ fn get_history_path() -> String {
     }
 }
 
-/// Выполняет цепочку команд и обрабатывает результат
+/// Получает из настроек исходы деплоя, о которых отправляются уведомления
+///
+/// # Возвращаемое значение
+///
+/// Список исходов для уведомлений
+fn get_notify_on() -> Vec<String> {
+    match settings::get_settings(settings::DEFAULT_SETTINGS_PATH) {
+        Ok(settings) => settings.notify_on,
+        Err(_) => {
+            warn!("Ошибка загрузки настроек, уведомления отправляются для всех исходов");
+            settings::default_notify_on()
+        }
+    }
+}
+
+/// Сведения о выполненном событии, нужные для обработки результата цепочки
+struct ChainResultContext<'a> {
+    /// Имя деплоя
+    deployment_name: &'a str,
+    /// Имя события
+    event_name: &'a str,
+    /// Путь к файлу истории деплоев
+    history_path: &'a str,
+    /// Время начала выполнения
+    start_time: Instant,
+    /// Эмиттер событий
+    emitter: EventEmitter,
+    /// Описания команд по их именам в цепочке
+    descriptions: &'a HashMap<String, String>,
+    /// Строки прогресса команд по их именам в цепочке
+    progress: &'a HashMap<String, String>,
+    /// Имена команд, вывод которых не показывается в консоли
+    quiet: &'a HashSet<String>,
+    /// Команды отката по именам команд цепочки
+    rollbacks: &'a RollbackCommands,
+    /// Группы отката по именам команд цепочки
+    groups: &'a HashMap<String, String>,
+}
+
+/// Обрабатывает результат выполнения цепочки команд
 ///
 /// # Параметры
 ///
-/// * `chain` - Цепочка команд для выполнения
-/// * `deployment_name` - Имя деплоя
-/// * `event_name` - Имя события
-/// * `history_path` - Путь к файлу истории деплоев
-/// * `start_time` - Время начала выполнения
-/// * `emitter` - Эмиттер событий
+/// * `result` - Результат выполнения цепочки команд
+/// * `context` - Сведения о выполненном событии
 ///
 /// # Возвращаемое значение
 ///
 /// Результат выполнения цепочки команд
-async fn execute_chain_and_handle_result(
-    chain: command_system::chain::CommandChain,
-    deployment_name: &str,
-    event_name: &str,
-    history_path: &str,
-    start_time: Instant,
-    emitter: EventEmitter,
+async fn handle_chain_result(
+    result: std::result::Result<ChainResult, String>,
+    context: ChainResultContext<'_>,
 ) -> Result<()> {
-    // Выполняем цепочку команд
-    let result = chain.execute().await;
+    let ChainResultContext {
+        deployment_name,
+        event_name,
+        history_path,
+        start_time,
+        emitter,
+        descriptions,
+        progress,
+        quiet,
+        rollbacks,
+        groups,
+    } = context;
 
     // Проверяем результат выполнения
     match result {
@@ -179,10 +323,49 @@ async fn execute_chain_and_handle_result(
                     deployment_name,
                     event_name,
                     &cmd_result.command_name,
+                    descriptions
+                        .get(&cmd_result.command_name)
+                        .map(String::as_str),
+                    quiet.contains(&cmd_result.command_name),
                     cmd_result,
-                );
+                )
+                .await;
+
+                // Индикатор прогресса выводится только в терминал, строки лога сохраняются
+                if output::is_terminal() {
+                    if let Some(progress) = progress.get(&cmd_result.command_name) {
+                        show_command_progress(progress, cmd_result);
+                    }
+                }
             }
 
+            // Записываем общую длительность события в тот же файл лога
+            save_event_duration_to_log(
+                deployment_name,
+                event_name,
+                chain_result.success,
+                start_time.elapsed(),
+            )
+            .await;
+            timings::record_event(
+                deployment_name,
+                event_name,
+                start_time.elapsed(),
+                &chain_result.results,
+            );
+            telemetry::record_event(
+                deployment_name,
+                event_name,
+                start_time.elapsed(),
+                &chain_result.results,
+                (!chain_result.success).then(|| {
+                    chain_result
+                        .error
+                        .as_deref()
+                        .unwrap_or("Неизвестная ошибка")
+                }),
+            );
+
             // Записываем результат в историю
             if let Err(e) = storage::record_chain_result(
                 history_path,
@@ -194,6 +377,18 @@ async fn execute_chain_and_handle_result(
             }
 
             if chain_result.success {
+                // Упавшие команды успешной цепочки настроены игнорировать ошибки
+                let ignored = storage::failed_commands(&chain_result.results);
+                if !ignored.is_empty() {
+                    warn!(
+                        "Деплой '{}', событие '{}' выполнено частично: проигнорированы ошибки команд {}",
+                        deployment_name,
+                        event_name,
+                        ignored.join(", ")
+                    );
+                    IGNORED_FAILURES.fetch_add(ignored.len(), Ordering::Relaxed);
+                }
+
                 // Все команды выполнены успешно
                 let duration = start_time.elapsed();
                 info!(
@@ -210,10 +405,17 @@ async fn execute_chain_and_handle_result(
 
                 Ok(())
             } else {
-                // Произошла ошибка в одной из команд
-                let error_msg = chain_result
-                    .error
-                    .unwrap_or_else(|| "Неизвестная ошибка".to_string());
+                // Произошла ошибка в одной из команд; текст ошибки содержит поток
+                // ошибок команды, поэтому скрываем в нем данные по redact_patterns
+                let error_msg = settings::redact(
+                    chain_result
+                        .error
+                        .as_deref()
+                        .unwrap_or("Неизвестная ошибка"),
+                );
+
+                // Откатываем только команды, которые успели запуститься
+                run_rollbacks(&chain_result.results, rollbacks, groups).await;
 
                 let duration = start_time.elapsed();
                 error!(
@@ -238,6 +440,9 @@ async fn execute_chain_and_handle_result(
         Err(e) => {
             // Критическая ошибка выполнения цепочки
             let duration = start_time.elapsed();
+            save_event_duration_to_log(deployment_name, event_name, false, duration).await;
+            timings::record_event(deployment_name, event_name, duration, &[]);
+            telemetry::record_event(deployment_name, event_name, duration, &[], Some(&e));
             error!(
                 "Критическая ошибка выполнения деплоя '{}', событие '{}' за {:.2} секунд: {}",
                 deployment_name,
@@ -255,7 +460,8 @@ async fn execute_chain_and_handle_result(
             if let Err(log_err) = storage::record_deployment(
                 history_path,
                 deployment_name,
-                &format!("error-{}", event_name),
+                storage::RecordKind::Error,
+                event_name,
                 false,
                 Some(e.to_string()),
             ) {
@@ -275,6 +481,7 @@ async fn execute_chain_and_handle_result(
 /// * `deployment_name` - Имя деплоя
 /// * `event_name` - Имя события в деплое
 /// * `global_variables_file` - Опциональный путь к глобальному файлу переменных
+/// * `options` - Параметры запуска из командной строки
 ///
 /// # Возвращаемое значение
 ///
@@ -284,6 +491,7 @@ pub async fn run_commands(
     deployment_name: &str,
     event_name: &str,
     global_variables_file: Option<&str>,
+    options: &RunOptions,
 ) -> Result<()> {
     // Засекаем время начала выполнения для оценки производительности
     let start_time = Instant::now();
@@ -294,7 +502,7 @@ pub async fn run_commands(
     );
 
     // Создаем эмиттер событий
-    let emitter = EventEmitter::new();
+    let emitter = EventEmitter::new().with_notify_on(get_notify_on());
 
     // Отправляем событие о начале выполнения
     emitter.emit(EventType::DeploymentStarted {
@@ -302,9 +510,22 @@ pub async fn run_commands(
         event: event_name.to_string(),
     });
 
+    // Получаем путь к истории деплоев
+    let history_path = get_history_path();
+
+    // Сохраняем снимок конфигурации деплоя для последующего сравнения
+    if let Some(deployment) = config.find_deployment(deployment_name) {
+        if let Err(e) = storage::save_config_snapshot(&history_path, deployment) {
+            warn!("Ошибка сохранения снимка конфигурации: {}", e);
+        }
+    }
+
     // Определяем глобальный файл переменных
     let global_vars_file = determine_global_variables_file(global_variables_file);
 
+    // Загружаем удаленные файлы переменных в память до построения цепочки
+    remote_variables::prefetch_remote_variables(config, deployment_name, global_vars_file).await?;
+
     // Настраиваем рабочую директорию
     if let Err(e) = setup_deployment_directory(config, deployment_name) {
         return Err(anyhow::anyhow!(
@@ -319,161 +540,1280 @@ pub async fn run_commands(
         deployment_name,
         event_name
     );
-    let chain =
-        chain_builder::build_command_chain(config, deployment_name, event_name, global_vars_file)?;
+    let (chain, mut rollbacks, _stdin_files) = chain_builder::build_command_chain(
+        config,
+        deployment_name,
+        event_name,
+        global_vars_file,
+        options,
+        0,
+    )?;
+
+    // Описания команд для вывода в лог
+    let descriptions = chain_builder::command_descriptions(config, deployment_name, event_name);
+    let progress = chain_builder::command_progress(config, deployment_name, event_name);
+    let quiet = chain_builder::quiet_commands(config, deployment_name, event_name);
+    let groups = chain_builder::command_groups(config, deployment_name, event_name);
 
     // Выполняем цепочку команд и обрабатываем результат
     info!(
         "Выполнение цепочки команд для деплоя '{}', событие '{}'",
         deployment_name, event_name
     );
+    if let Some(description) = config
+        .find_deployment(deployment_name)
+        .and_then(|d| d.events.iter().find(|e| e.name == event_name))
+        .and_then(|e| e.description.as_deref())
+    {
+        info!(
+            "Описание события '{}': {}",
+            event_name,
+            util::expand_env_vars(description)
+        );
+    }
 
-    // Получаем путь к истории деплоев
-    let history_path = get_history_path();
-
-    // Выполняем цепочку и обрабатываем результат
-    execute_chain_and_handle_result(
+    // Выполняем цепочку с повторами упавших команд и обрабатываем результат
+    let result = execute_chain_with_retries(
         chain,
+        config,
         deployment_name,
         event_name,
-        &history_path,
-        start_time,
-        emitter,
+        global_vars_file,
+        options,
+        &mut rollbacks,
+    )
+    .await;
+
+    handle_chain_result(
+        result,
+        ChainResultContext {
+            deployment_name,
+            event_name,
+            history_path: &history_path,
+            start_time,
+            emitter,
+            descriptions: &descriptions,
+            progress: &progress,
+            quiet: &quiet,
+            rollbacks: &rollbacks,
+            groups: &groups,
+        },
     )
     .await
 }
 
-/// Сохраняет детальный вывод команды в файл лога и выводит результат в консоль
+/// Выполняет служебную команду деплоя вне событий (например, снимок окружения)
+///
+/// Команда выполняется в рабочей директории деплоя с его переменными окружения,
+/// ее вывод сохраняется в журнал команд деплоя.
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+/// * `stage` - Название этапа для журнала (`snapshot`, `restore`, `version`)
+/// * `command` - Строка с командой
+/// * `options` - Параметры запуска из командной строки
+///
+/// # Возвращаемое значение
+///
+/// Вывод команды без начальных и конечных пробелов или ошибка
+pub async fn run_deployment_command(
+    deployment: &Deployment,
+    stage: &str,
+    command: &str,
+    options: &RunOptions,
+) -> Result<String> {
+    let cmd_name = format!("{}_{}", deployment.name, stage);
+    let env_vars =
+        chain_builder::determine_environment_variables(deployment, &deployment.name, &options.env);
+    let shell_command = command_executor::create_simple_command(
+        &cmd_name,
+        &options.command_to_run(command),
+        deployment.working_dir.as_deref(),
+        &env_vars,
+        None,
+    );
+
+    let result = shell_command
+        .execute()
+        .await
+        .map_err(|e| anyhow::anyhow!("Ошибка запуска команды '{}': {}", cmd_name, e))?;
+    save_command_output_to_log(&deployment.name, stage, &cmd_name, None, false, &result).await;
+
+    if result.success {
+        Ok(result.output.trim().to_string())
+    } else {
+        Err(anyhow::anyhow!(
+            "Команда '{}' завершилась с ошибкой: {}",
+            cmd_name,
+            result
+                .error
+                .unwrap_or_else(|| "<неизвестная ошибка>".to_string())
+        ))
+    }
+}
+
+/// Выполняет цепочку команд, повторяя упавшую команду согласно ее политике повторов
+///
+/// Повтор возможен только для событий с fail_fast: цепочка перестраивается начиная
+/// с упавшей команды, результаты всех попыток объединяются. Если повторы исчерпаны
+/// и задан `--prompt-on-fail`, пользователь выбирает повтор, пропуск команды или
+/// прерывание события.
+///
+/// Цепочка, завершившаяся перед командой с `{#__PREV_EXIT__}`, продолжается новой
+/// цепочкой, в которую передается код завершения предыдущей команды.
 ///
 /// # Параметры
 ///
+/// * `chain` - Цепочка команд для выполнения
+/// * `config` - Конфигурация деплоев
 /// * `deployment_name` - Имя деплоя
 /// * `event_name` - Имя события
-/// * `command_name` - Имя команды
-/// * `result` - Результат выполнения команды
-fn save_command_output_to_log(
+/// * `global_variables_file` - Глобальный файл переменных
+/// * `options` - Параметры запуска из командной строки
+/// * `rollbacks` - Команды отката, дополняемые командами перестроенных цепочек
+///
+/// # Возвращаемое значение
+///
+/// Объединенный результат выполнения или ошибка выполнения цепочки
+async fn execute_chain_with_retries(
+    chain: command_system::chain::CommandChain,
+    config: &Config,
     deployment_name: &str,
     event_name: &str,
-    command_name: &str,
-    result: &CommandResult,
-) {
-    // Выводим результат выполнения команды в лог
-    if result.success {
-        let output = result.output.trim();
+    global_variables_file: Option<&str>,
+    options: &RunOptions,
+    rollbacks: &mut RollbackCommands,
+) -> std::result::Result<ChainResult, String> {
+    let mut chain_result = execute_chain(chain, config, deployment_name, event_name).await?;
+    let mut attempts: HashMap<String, u32> = HashMap::new();
 
-        // Для больших выводов делаем вывод в несколько строк
-        if output.len() > 80 || output.contains('\n') {
-            info!("Результат выполнения команды '{}':", command_name);
-            for line in output.lines() {
-                if !line.is_empty() {
-                    info!("│ {}", line);
-                }
-            }
-            if output.lines().count() == 0 {
-                info!("│ <пустой вывод>");
+    while let Some(failed) = chain_result.results.last() {
+        let (event, idx, policy) = match chain_builder::find_chain_command(
+            config,
+            deployment_name,
+            event_name,
+            &failed.command_name,
+        ) {
+            Some(found) => found,
+            None => break,
+        };
+
+        if !event.fail_fast.unwrap_or(true) {
+            break;
+        }
+
+        // Последняя команда цепочки выполнилась, но событие еще не закончено:
+        // цепочка завершилась перед командой с {#__PREV_EXIT__}
+        if failed.success {
+            if idx + 1 >= event.commands.len() {
+                break;
             }
-            info!("└─ Конец вывода");
-        } else {
+            let (next_chain, next_rollbacks, _next_stdin_files) =
+                chain_builder::build_command_chain(
+                    config,
+                    deployment_name,
+                    event_name,
+                    global_variables_file,
+                    &chain_options(options, failed.exit_code),
+                    idx + 1,
+                )
+                .map_err(|e| format!("{:#}", e))?;
+            rollbacks.extend(next_rollbacks);
+            let next_result =
+                execute_chain(next_chain, config, deployment_name, event_name).await?;
+
+            chain_result.results.extend(next_result.results);
+            chain_result.success = next_result.success;
+            chain_result.error = next_result.error;
+            continue;
+        }
+
+        let allowed = event.commands[idx].allows_exit_code(failed.exit_code);
+        let attempt = attempts.entry(failed.command_name.clone()).or_insert(0);
+        let first_command = if allowed {
             info!(
-                "Результат выполнения команды '{}': {}",
-                command_name, output
+                "Команда '{}' завершилась с разрешенным кодом {} (allow_exit_codes), выполнение продолжается",
+                failed.command_name,
+                failed.exit_code.unwrap_or_default()
             );
+            idx + 1
+        } else if policy.should_retry(failed.exit_code, *attempt) {
+            *attempt += 1;
+            retry_command(&failed.command_name, failed.exit_code, *attempt, &policy).await;
+            idx
+        } else if event.commands[idx].ignore_errors.unwrap_or(false) {
+            warn!(
+                "Ошибка команды '{}' проигнорирована (ignore_errors), выполнение продолжается",
+                failed.command_name
+            );
+            idx + 1
+        } else if options.prompt_on_fail {
+            match ask_fail_action(&failed.command_name) {
+                FailAction::Retry => idx,
+                FailAction::Skip => idx + 1,
+                FailAction::Abort => break,
+            }
+        } else {
+            break;
+        };
+
+        if allowed {
+            if let Some(last) = chain_result.results.last_mut() {
+                accept_exit_code(last);
+            }
         }
-    } else {
-        let error_msg = result
-            .error
-            .clone()
-            .unwrap_or_else(|| "<неизвестная ошибка>".to_string());
 
-        error!("Ошибка выполнения команды '{}':", command_name);
-        error!("├─ Сообщение ошибки: {}", error_msg);
+        // Пропуск последней команды события завершает цепочку успешно
+        if first_command >= event.commands.len() {
+            chain_result.success = true;
+            chain_result.error = None;
+            break;
+        }
 
-        // Выводим вывод команды, если он есть
-        let output = result.output.trim();
-        if !output.is_empty() {
-            error!("├─ Стандартный вывод команды:");
-            for line in output.lines() {
-                if !line.is_empty() {
-                    error!("│ {}", line);
-                }
-            }
-        } else {
-            error!("├─ Стандартный вывод команды: <пустой>");
+        let previous_exit = previous_exit_code(
+            config,
+            deployment_name,
+            event_name,
+            &chain_result.results,
+            first_command,
+        );
+        let (retry_chain, retry_rollbacks, _retry_stdin_files) =
+            chain_builder::build_command_chain(
+                config,
+                deployment_name,
+                event_name,
+                global_variables_file,
+                &chain_options(options, previous_exit),
+                first_command,
+            )
+            .map_err(|e| format!("{:#}", e))?;
+        rollbacks.extend(retry_rollbacks);
+        let retry_result = execute_chain(retry_chain, config, deployment_name, event_name).await?;
+
+        chain_result.results.extend(retry_result.results);
+        chain_result.success = retry_result.success;
+        chain_result.error = retry_result.error;
+    }
+
+    // В событиях без fail_fast цепочка не прерывается, разрешенные коды учитываются здесь
+    let mut accepted = 0;
+    for result in chain_result.results.iter_mut().filter(|r| !r.success) {
+        if chain_builder::is_allowed_exit_code(
+            config,
+            deployment_name,
+            event_name,
+            &result.command_name,
+            result.exit_code,
+        ) {
+            accept_exit_code(result);
+            accepted += 1;
         }
-        error!("└─ Конец вывода");
     }
 
-    // Получаем директорию логов из настроек или используем значение по умолчанию
-    let logs_dir = match settings::get_settings(settings::DEFAULT_SETTINGS_PATH) {
-        Ok(settings) => settings.logs_dir,
-        Err(e) => {
-            warn!("Ошибка загрузки настроек для директории логов: {}", e);
-            settings::DEFAULT_LOGS_DIR.to_string()
+    // Цепочка, в которой упали только команды с ignore_errors, выполнена частично
+    if !chain_result.success {
+        let failed = storage::failed_commands(&chain_result.results);
+        let only_ignored = !failed.is_empty()
+            && failed.iter().all(|name| {
+                chain_builder::is_ignored_command(config, deployment_name, event_name, name)
+            });
+        if only_ignored || (failed.is_empty() && accepted > 0) {
+            chain_result.success = true;
+            chain_result.error = None;
         }
-    };
+    }
 
-    // Создаем директорию логов, если ее нет
-    if !std::path::Path::new(&logs_dir).exists() {
-        if let Err(e) = std::fs::create_dir_all(&logs_dir) {
-            warn!("Не удалось создать директорию логов {}: {}", logs_dir, e);
-            return;
+    Ok(chain_result)
+}
+
+/// Выполняет цепочку команд и проверяет вывод команд с `assert_output_*`
+///
+/// Команда, вывод которой не прошел проверку, считается упавшей даже при коде
+/// завершения 0; ошибка проверки становится ошибкой цепочки.
+///
+/// # Параметры
+///
+/// * `chain` - Цепочка команд для выполнения
+/// * `config` - Конфигурация деплоев
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+///
+/// # Возвращаемое значение
+///
+/// Результат выполнения цепочки или ошибка выполнения
+async fn execute_chain(
+    chain: command_system::chain::CommandChain,
+    config: &Config,
+    deployment_name: &str,
+    event_name: &str,
+) -> std::result::Result<ChainResult, String> {
+    let mut chain_result = chain.execute().await.map_err(|e| e.to_string())?;
+
+    for result in chain_result.results.iter_mut().filter(|r| r.success) {
+        let Some((event, idx, _)) = chain_builder::find_chain_command(
+            config,
+            deployment_name,
+            event_name,
+            &result.command_name,
+        ) else {
+            continue;
+        };
+        if let Some(failure) = event.commands[idx].output_assertion_failure(&result.output) {
+            warn!(
+                "Команда '{}' не прошла проверку вывода: {}",
+                result.command_name, failure
+            );
+            result.success = false;
+            result.error = Some(failure.clone());
+            chain_result.success = false;
+            chain_result.error.get_or_insert(failure);
         }
     }
 
-    // Создаем имя файла лога только с датой (один файл на день)
-    let current_date = chrono::Local::now().format("%Y%m%d");
-    let timestamp = chrono::Local::now().format("%H:%M:%S");
-    let filename = format!("{}/{}_commands.log", logs_dir, current_date);
+    Ok(chain_result)
+}
 
-    // Формируем содержимое лога с отметкой времени и информацией о команде
-    let log_content = if result.success {
-        format!(
-            "\n[{}] Деплой: '{}', Событие: '{}', Команда: '{}'\nСтатус: Успех\nВывод:\n{}\n{}\n",
-            timestamp, deployment_name, event_name, command_name, 
-            result.output.trim(),
-            "-".repeat(80)
-        )
-    } else {
-        let error_msg = result
-            .error
-            .clone()
-            .unwrap_or_else(|| "<неизвестная ошибка>".to_string());
-        format!(
-            "\n[{}] Деплой: '{}', Событие: '{}', Команда: '{}'\nСтатус: Ошибка\nСообщение ошибки:\n{}\nСтандартный вывод:\n{}\n{}\n",
-            timestamp, deployment_name, event_name, command_name, 
-            error_msg, result.output.trim(),
-            "-".repeat(80)
-        )
-    };
+/// Возвращает параметры запуска с кодом завершения предыдущей команды (`{#__PREV_EXIT__}`)
+///
+/// # Параметры
+///
+/// * `options` - Параметры запуска из командной строки
+/// * `exit_code` - Код завершения предыдущей команды события
+fn chain_options(options: &RunOptions, exit_code: Option<i32>) -> RunOptions {
+    let mut options = options.clone();
+    if let Some(code) = exit_code {
+        options.variables.insert(
+            chain_builder::PREV_EXIT_VARIABLE.to_string(),
+            code.to_string(),
+        );
+    }
+    options
+}
 
-    // Записываем или дописываем лог в файл
-    let file_exists = std::path::Path::new(&filename).exists();
-    let file_operation_result;
-    
-    // Если файл существует, дописываем в него
-    if file_exists {
-        file_operation_result = std::fs::OpenOptions::new()
-            .append(true)
-            .open(&filename)
-            .and_then(|mut file| file.write_all(log_content.as_bytes()));
-    } else {
-        // Если файла нет, создаем новый
-        file_operation_result = std::fs::write(&filename, log_content);
+/// Находит код завершения команды, предшествующей первой команде новой цепочки
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация деплоев
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+/// * `results` - Результаты уже выполненных команд события
+/// * `first_command` - Индекс первой команды новой цепочки
+///
+/// # Возвращаемое значение
+///
+/// Код завершения последнего запуска предыдущей команды или None
+fn previous_exit_code(
+    config: &Config,
+    deployment_name: &str,
+    event_name: &str,
+    results: &[CommandResult],
+    first_command: usize,
+) -> Option<i32> {
+    results
+        .iter()
+        .rev()
+        .find(|result| {
+            chain_builder::find_chain_command(
+                config,
+                deployment_name,
+                event_name,
+                &result.command_name,
+            )
+            .is_some_and(|(_, idx, _)| idx + 1 == first_command)
+        })
+        .and_then(|result| result.exit_code)
+}
+
+/// Отмечает результат команды с разрешенным кодом завершения как успешный
+///
+/// # Параметры
+///
+/// * `result` - Результат команды, код завершения которой входит в `allow_exit_codes`
+fn accept_exit_code(result: &mut CommandResult) {
+    result.success = true;
+    result.error = None;
+}
+
+/// Сообщает о повторе упавшей команды и выдерживает задержку политики повторов
+///
+/// # Параметры
+///
+/// * `command_name` - Имя команды в цепочке
+/// * `exit_code` - Код завершения команды
+/// * `attempt` - Номер повтора (начиная с 1)
+/// * `policy` - Политика повторов команды
+async fn retry_command(
+    command_name: &str,
+    exit_code: Option<i32>,
+    attempt: u32,
+    policy: &crate::config::RetryPolicy,
+) {
+    warn!(
+        "Команда '{}' завершилась с кодом {}, повтор {} из {}",
+        command_name,
+        exit_code
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| "<нет>".to_string()),
+        attempt,
+        policy.retries.unwrap_or(0)
+    );
+
+    let delay = crate::config::next_delay(attempt, policy);
+    if !delay.is_zero() {
+        info!(
+            "Ожидание {:.1} с перед повтором команды '{}'",
+            delay.as_secs_f64(),
+            command_name
+        );
+        tokio::time::sleep(delay).await;
     }
+}
 
-    if let Err(e) = file_operation_result {
-        warn!("Не удалось записать лог в файл {}: {}", filename, e);
-    } else {
-        if file_exists {
-            info!(
-                "Вывод команды '{}' добавлен в лог: {}",
-                command_name, filename
-            );
-        } else {
-            info!(
-                "Создан новый лог-файл для команд: {}",
-                filename
-            );
-        }
+/// Спрашивает пользователя, что делать с упавшей командой
+///
+/// Без терминала на стандартном вводе выполнение прерывается.
+///
+/// # Параметры
+///
+/// * `command_name` - Имя команды в цепочке
+///
+/// # Возвращаемое значение
+///
+/// Выбранное действие
+fn ask_fail_action(command_name: &str) -> FailAction {
+    if !std::io::stdin().is_terminal() {
+        warn!(
+            "Стандартный ввод не является терминалом, выполнение после ошибки команды '{}' прерывается",
+            command_name
+        );
+        return FailAction::Abort;
+    }
+
+    let action = read_fail_action(&mut std::io::stdin().lock(), command_name);
+    info!(
+        "Действие после ошибки команды '{}': {:?}",
+        command_name, action
+    );
+    action
+}
+
+/// Читает действие после ошибки команды, повторяя запрос при нераспознанном ответе
+///
+/// # Параметры
+///
+/// * `input` - Источник ввода
+/// * `command_name` - Имя команды в цепочке
+///
+/// # Возвращаемое значение
+///
+/// Выбранное действие; при окончании ввода - прерывание
+fn read_fail_action(input: &mut impl BufRead, command_name: &str) -> FailAction {
+    loop {
+        print!(
+            "Команда '{}' завершилась с ошибкой. Повторить (r), пропустить (s) или прервать (a)? ",
+            command_name
+        );
+        let _ = std::io::stdout().flush();
+
+        let mut answer = String::new();
+        match input.read_line(&mut answer) {
+            Ok(0) | Err(_) => return FailAction::Abort,
+            Ok(_) => {}
+        }
+
+        match parse_fail_action(&answer) {
+            Some(action) => return action,
+            None => output::line("Введите r, s или a"),
+        }
+    }
+}
+
+/// Разбирает ответ пользователя на запрос после ошибки команды
+///
+/// # Параметры
+///
+/// * `input` - Введенная строка
+///
+/// # Возвращаемое значение
+///
+/// Действие или None, если ответ не распознан
+pub fn parse_fail_action(input: &str) -> Option<FailAction> {
+    match input.trim().to_lowercase().as_str() {
+        "r" | "retry" => Some(FailAction::Retry),
+        "s" | "skip" => Some(FailAction::Skip),
+        "a" | "abort" => Some(FailAction::Abort),
+        _ => None,
+    }
+}
+
+/// Выводит строку прогресса выполненной команды
+///
+/// # Параметры
+///
+/// * `progress` - Строка прогресса вида `[3/8] npm ci`
+/// * `result` - Результат выполнения команды
+fn show_command_progress(progress: &str, result: &CommandResult) {
+    let status = if result.success {
+        output::success("успешно")
+    } else {
+        output::failure("ошибка")
+    };
+    output::line(format!(
+        "{} - {} {}",
+        progress,
+        status,
+        output::muted(&format!("({} мс)", result.duration_ms))
+    ));
+}
+
+/// Выполняет откат запущенных команд в обратном порядке
+///
+/// Результаты цепочки содержат только команды, которые начали выполняться, поэтому
+/// команды, до которых выполнение не дошло, не откатываются. Если упавшая команда
+/// входит в группу (`group`), откатываются только команды этой группы.
+///
+/// # Параметры
+///
+/// * `results` - Результаты выполненных команд цепочки
+/// * `rollbacks` - Команды отката по именам команд цепочки
+/// * `groups` - Группы отката по именам команд цепочки
+async fn run_rollbacks(
+    results: &[CommandResult],
+    rollbacks: &RollbackCommands,
+    groups: &HashMap<String, String>,
+) {
+    // Повторенная команда встречается в результатах несколько раз, откатываем ее однажды
+    let mut rolled_back = HashSet::new();
+
+    let failed_group = results
+        .iter()
+        .rev()
+        .find(|r| !r.success)
+        .and_then(|r| groups.get(&r.command_name));
+    if let Some(group) = failed_group {
+        info!("Откат команд группы '{}'", group);
+    }
+
+    for result in results.iter().rev() {
+        if failed_group.is_some_and(|group| groups.get(&result.command_name) != Some(group)) {
+            continue;
+        }
+
+        let rollback = match rollbacks.get(&result.command_name) {
+            Some(rollback) if rolled_back.insert(result.command_name.as_str()) => rollback,
+            _ => continue,
+        };
+
+        info!("Откат команды '{}'", result.command_name);
+        if run_rollback_steps(&result.command_name, rollback).await {
+            info!("Откат команды '{}' выполнен", result.command_name);
+        }
+    }
+}
+
+/// Выполняет шаги отката команды по порядку
+///
+/// При ошибке шага оставшиеся шаги не выполняются, так как они могут зависеть от него.
+///
+/// # Параметры
+///
+/// * `command_name` - Имя откатываемой команды
+/// * `steps` - Команды шагов отката в порядке выполнения
+///
+/// # Возвращаемое значение
+///
+/// true, если все шаги выполнены успешно
+async fn run_rollback_steps(command_name: &str, steps: &[ShellCommand]) -> bool {
+    for (step, rollback) in steps.iter().enumerate() {
+        let step_label = if steps.len() > 1 {
+            format!(" (шаг {}/{})", step + 1, steps.len())
+        } else {
+            String::new()
+        };
+
+        match rollback.execute().await {
+            Ok(rollback_result) if rollback_result.success => {
+                debug!(
+                    "Шаг отката команды '{}'{} выполнен",
+                    command_name, step_label
+                );
+            }
+            Ok(rollback_result) => {
+                warn!(
+                    "Откат команды '{}'{} завершился с ошибкой: {}",
+                    command_name,
+                    step_label,
+                    rollback_result
+                        .error
+                        .unwrap_or_else(|| "<неизвестная ошибка>".to_string())
+                );
+                return false;
+            }
+            Err(e) => {
+                warn!(
+                    "Ошибка выполнения отката команды '{}'{}: {}",
+                    command_name, step_label, e
+                );
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// Сохраняет детальный вывод команды в файл лога и выводит результат в консоль
+///
+/// # Параметры
+///
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+/// * `command_name` - Имя команды
+/// * `description` - Описание команды из конфигурации
+/// * `quiet` - Не выводить вывод команды в консоль, только в файл лога
+/// * `result` - Результат выполнения команды
+async fn save_command_output_to_log(
+    deployment_name: &str,
+    event_name: &str,
+    command_name: &str,
+    description: Option<&str>,
+    quiet: bool,
+    result: &CommandResult,
+) {
+    // Имя команды с описанием для более понятного вывода
+    let command_label = match description {
+        Some(description) => format!("'{}' ({})", command_name, description),
+        None => format!("'{}'", command_name),
+    };
+
+    // Command System декодирует вывод с заменой недопустимых байтов,
+    // поэтому сообщаем о потере данных явно
+    let lossy_output = has_lossy_output(&result.output);
+    if lossy_output {
+        warn!(
+            "Вывод команды {} содержит недопустимые UTF-8 данные, они заменены символом '{}'",
+            command_label,
+            char::REPLACEMENT_CHARACTER
+        );
+    }
+    let lossy_note = if lossy_output {
+        " (содержит недопустимые UTF-8 данные)"
+    } else {
+        ""
+    };
+
+    // Скрываем значения, совпадающие с шаблонами redact_patterns из настроек
+    let output = settings::redact(result.output.trim());
+    let output = output.as_str();
+    let error_msg = settings::redact(result.error.as_deref().unwrap_or("<неизвестная ошибка>"));
+
+    // Выводим результат выполнения команды в лог
+    if quiet {
+        // Вывод команды с quiet попадает только в файл лога команд
+        if result.success {
+            info!(
+                "Команда {} выполнена ({} мс), вывод записан в журнал команд",
+                command_label, result.duration_ms
+            );
+        } else {
+            error!(
+                "Ошибка выполнения команды {} ({} мс): {}",
+                command_label, result.duration_ms, error_msg
+            );
+            error!("└─ Стандартный вывод команды записан в журнал команд");
+        }
+    } else if result.success {
+        // Для больших выводов делаем вывод в несколько строк
+        if should_wrap_output(output, get_console_wrap_width()) {
+            info!(
+                "Результат выполнения команды {} ({} мс):",
+                command_label, result.duration_ms
+            );
+            for line in output.lines() {
+                if !line.is_empty() {
+                    info!("│ {}", line);
+                }
+            }
+            if output.lines().count() == 0 {
+                info!("│ <пустой вывод>");
+            }
+            info!("└─ Конец вывода");
+        } else {
+            info!(
+                "Результат выполнения команды {} ({} мс): {}",
+                command_label, result.duration_ms, output
+            );
+        }
+    } else {
+        error!(
+            "Ошибка выполнения команды {} ({} мс):",
+            command_label, result.duration_ms
+        );
+        error!("├─ Сообщение ошибки: {}", error_msg);
+
+        // Выводим вывод команды, если он есть
+        if !output.is_empty() {
+            error!("├─ Стандартный вывод команды:");
+            for line in output.lines() {
+                if !line.is_empty() {
+                    error!("│ {}", line);
+                }
+            }
+        } else {
+            error!("├─ Стандартный вывод команды: <пустой>");
+        }
+        error!("└─ Конец вывода");
+    }
+
+    // Формируем содержимое лога с отметкой времени и информацией о команде
+    let header = command_log_header(
+        deployment_name,
+        event_name,
+        &command_label,
+        result.duration_ms,
+    );
+    let log_content = if result.success {
+        format!(
+            "\n{}\nСтатус: Успех\nВывод{}:\n{}\n{}\n",
+            header,
+            lossy_note,
+            output,
+            "-".repeat(80)
+        )
+    } else {
+        format!(
+            "\n{}\nСтатус: Ошибка\nСообщение ошибки:\n{}\nСтандартный вывод{}:\n{}\n{}\n",
+            header,
+            error_msg,
+            lossy_note,
+            output,
+            "-".repeat(80)
+        )
+    };
+
+    for filename in append_to_command_log(deployment_name, log_content).await {
+        info!(
+            "Вывод команды {} добавлен в лог: {}",
+            command_label, filename
+        );
+    }
+}
+
+/// Формирует строку заголовка записи о команде в логе команд
+///
+/// # Параметры
+///
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+/// * `command_label` - Имя команды с описанием
+/// * `duration_ms` - Длительность выполнения команды в миллисекундах
+///
+/// # Возвращаемое значение
+///
+/// Строка с отметкой времени, деплоем, событием, командой и длительностью
+fn command_log_header(
+    deployment_name: &str,
+    event_name: &str,
+    command_label: &str,
+    duration_ms: u64,
+) -> String {
+    format!(
+        "[{}] Деплой: '{}', Событие: '{}', Команда: {}, Длительность: {} мс",
+        chrono::Local::now().format("%H:%M:%S"),
+        deployment_name,
+        event_name,
+        command_label,
+        duration_ms
+    )
+}
+
+/// Получает из настроек ширину вывода в консоль
+///
+/// # Возвращаемое значение
+///
+/// Длина вывода, после которой он выводится построчно
+fn get_console_wrap_width() -> usize {
+    match settings::get_settings(settings::DEFAULT_SETTINGS_PATH) {
+        Ok(settings) => settings.console_wrap_width,
+        Err(_) => settings::DEFAULT_CONSOLE_WRAP_WIDTH,
+    }
+}
+
+/// Проверяет, нужно ли выводить результат команды в консоль построчно
+///
+/// # Параметры
+///
+/// * `output` - Вывод команды
+/// * `wrap_width` - Максимальная длина однострочного вывода в символах
+///
+/// # Возвращаемое значение
+///
+/// `true`, если вывод длиннее `wrap_width` или содержит переводы строк
+fn should_wrap_output(output: &str, wrap_width: usize) -> bool {
+    output.chars().count() > wrap_width || output.contains('\n')
+}
+
+/// Проверяет, был ли вывод команды получен с заменой недопустимых UTF-8 байтов
+///
+/// # Параметры
+///
+/// * `output` - Вывод команды
+///
+/// # Возвращаемое значение
+///
+/// `true`, если вывод содержит символ замены U+FFFD
+fn has_lossy_output(output: &str) -> bool {
+    output.contains(char::REPLACEMENT_CHARACTER)
+}
+
+/// Записывает итоговую длительность события в файл лога команд
+///
+/// # Параметры
+///
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+/// * `success` - Успешно ли выполнено событие
+/// * `duration` - Общая длительность выполнения события
+async fn save_event_duration_to_log(
+    deployment_name: &str,
+    event_name: &str,
+    success: bool,
+    duration: Duration,
+) {
+    let log_content = event_duration_log_entry(deployment_name, event_name, success, duration);
+    append_to_command_log(deployment_name, log_content).await;
+}
+
+/// Формирует запись об итоговой длительности события для лога команд
+///
+/// # Параметры
+///
+/// * `deployment_name` - Имя деплоя
+/// * `event_name` - Имя события
+/// * `success` - Успешно ли выполнено событие
+/// * `duration` - Общая длительность выполнения события
+///
+/// # Возвращаемое значение
+///
+/// Текст записи для лога команд
+fn event_duration_log_entry(
+    deployment_name: &str,
+    event_name: &str,
+    success: bool,
+    duration: Duration,
+) -> String {
+    let timestamp = chrono::Local::now().format("%H:%M:%S");
+    let status = if success {
+        "Успех"
+    } else {
+        "Ошибка"
+    };
+    format!(
+        "\n[{}] Деплой: '{}', Событие: '{}' завершено\nСтатус: {}\nОбщая длительность: {} мс ({:.2} секунд)\n{}\n",
+        timestamp,
+        deployment_name,
+        event_name,
+        status,
+        duration.as_millis(),
+        duration.as_secs_f64(),
+        "=".repeat(80)
+    )
+}
+
+/// Дописывает запись в дневные файлы лога команд
+///
+/// В зависимости от настройки `command_log_mode` запись попадает в общий файл
+/// `logs_dir/YYYYMMDD_commands.log`, в файл деплоя `logs_dir/<деплой>/YYYYMMDD.log`
+/// или в оба. Если в директорию логов записать не удалось, запись сохраняется
+/// во временную директорию системы.
+///
+/// Запись выполняется в блокирующем потоке tokio, чтобы дисковый ввод-вывод
+/// при большом выводе команд не останавливал асинхронные задачи.
+///
+/// # Параметры
+///
+/// * `deployment_name` - Имя деплоя
+/// * `content` - Текст записи
+///
+/// # Возвращаемое значение
+///
+/// Пути к файлам лога, в которые удалось записать запись
+async fn append_to_command_log(deployment_name: &str, content: String) -> Vec<String> {
+    let deployment_name = deployment_name.to_string();
+    run_log_write(move || write_to_command_logs(&deployment_name, &content)).await
+}
+
+/// Выполняет запись в лог команд в блокирующем потоке tokio
+///
+/// # Параметры
+///
+/// * `write` - Функция записи, возвращающая пути к файлам лога
+///
+/// # Возвращаемое значение
+///
+/// Пути к файлам лога или пустой список, если задача записи завершилась аварийно
+async fn run_log_write(write: impl FnOnce() -> Vec<String> + Send + 'static) -> Vec<String> {
+    tokio::task::spawn_blocking(write)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Ошибка задачи записи лога команд: {}", e);
+            Vec::new()
+        })
+}
+
+/// Синхронно дописывает запись в дневные файлы лога команд
+///
+/// # Параметры
+///
+/// * `deployment_name` - Имя деплоя
+/// * `content` - Текст записи
+///
+/// # Возвращаемое значение
+///
+/// Пути к файлам лога, в которые удалось записать запись
+fn write_to_command_logs(deployment_name: &str, content: &str) -> Vec<String> {
+    // Получаем директорию логов, режим записи и ограничение размера из настроек
+    // или используем значения по умолчанию
+    let (logs_dir, mode, max_daily_log_mb) =
+        match settings::get_settings(settings::DEFAULT_SETTINGS_PATH) {
+            Ok(settings) => (
+                settings.logs_dir,
+                settings.command_log_mode,
+                settings.max_daily_log_mb,
+            ),
+            Err(e) => {
+                warn!("Ошибка загрузки настроек для директории логов: {}", e);
+                (
+                    settings::DEFAULT_LOGS_DIR.to_string(),
+                    CommandLogMode::default(),
+                    0,
+                )
+            }
+        };
+
+    // Создаем имена файлов лога только с датой (один файл на день)
+    let current_date = chrono::Local::now().format("%Y%m%d");
+    let mut log_files = Vec::new();
+    if mode != CommandLogMode::PerDeployment {
+        log_files.push(PathBuf::from(format!("{}_commands.log", current_date)));
+    }
+    if mode != CommandLogMode::Shared {
+        match deployment_log_dir_name(deployment_name) {
+            Some(dir_name) => {
+                log_files.push(Path::new(&dir_name).join(format!("{}.log", current_date)))
+            }
+            None => warn!(
+                "Имя деплоя '{}' не может быть именем директории лога, отдельный лог деплоя не записан",
+                deployment_name
+            ),
+        }
+    }
+
+    let logs_dir = Path::new(&logs_dir);
+    log_files
+        .iter()
+        .map(|log_file| rolled_log_file(logs_dir, log_file, max_daily_log_mb * 1024 * 1024))
+        .filter_map(|log_file| append_with_fallback(logs_dir, &log_file, content))
+        .collect()
+}
+
+/// Формирует имя директории отдельного лога деплоя
+///
+/// Разделители пути в имени деплоя заменяются на `_`, чтобы лог не оказался
+/// во вложенной или внешней директории.
+///
+/// # Параметры
+///
+/// * `deployment_name` - Имя деплоя
+///
+/// # Возвращаемое значение
+///
+/// Имя директории или None, если имя деплоя пустое или содержит `..`
+fn deployment_log_dir_name(deployment_name: &str) -> Option<String> {
+    if deployment_name.trim().is_empty() || deployment_name.contains("..") {
+        return None;
+    }
+
+    Some(deployment_name.replace(['/', '\\'], "_"))
+}
+
+/// Выбирает файл дневного лога с учетом ограничения размера
+///
+/// Если файл достиг ограничения, запись продолжается в файле с номером:
+/// `20240115_commands.log` -> `20240115_commands.1.log` -> `20240115_commands.2.log`.
+///
+/// # Параметры
+///
+/// * `logs_dir` - Директория логов из настроек
+/// * `log_file` - Путь к дневному файлу лога относительно директории логов
+/// * `max_bytes` - Максимальный размер файла в байтах (0 - без ограничения)
+///
+/// # Возвращаемое значение
+///
+/// Путь к файлу лога для записи относительно директории логов
+fn rolled_log_file(logs_dir: &Path, log_file: &Path, max_bytes: u64) -> PathBuf {
+    if max_bytes == 0 {
+        return log_file.to_path_buf();
+    }
+
+    let stem = log_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut candidate = log_file.to_path_buf();
+
+    for number in 1.. {
+        let size = fs::metadata(logs_dir.join(&candidate))
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        if size < max_bytes {
+            break;
+        }
+        candidate = log_file.with_file_name(format!("{}.{}.log", stem, number));
+    }
+
+    candidate
+}
+
+/// Дописывает запись в файл лога, при ошибке - в тот же файл во временной директории
+///
+/// # Параметры
+///
+/// * `logs_dir` - Директория логов из настроек
+/// * `log_file` - Путь к файлу лога относительно директории логов
+/// * `content` - Текст записи
+///
+/// # Возвращаемое значение
+///
+/// Путь к файлу лога или None, если запись не удалась
+fn append_with_fallback(logs_dir: &Path, log_file: &Path, content: &str) -> Option<String> {
+    let error = match write_command_log(&logs_dir.join(log_file), content) {
+        Ok(filename) => return Some(filename),
+        Err(e) => e,
+    };
+
+    // Используем временную директорию, чтобы не потерять вывод команды
+    let fallback_file = std::env::temp_dir().join("deploy-commander").join(log_file);
+    match write_command_log(&fallback_file, content) {
+        Ok(filename) => {
+            warn!(
+                "{:#}. Лог записан во временную директорию: {}",
+                error, filename
+            );
+            Some(filename)
+        }
+        Err(e) => {
+            warn!(
+                "{:#}. Запись во временную директорию также не удалась: {:#}",
+                error, e
+            );
+            None
+        }
+    }
+}
+
+/// Дописывает запись в файл лога команд
+///
+/// # Параметры
+///
+/// * `log_file` - Путь к файлу лога (директория создается при необходимости)
+/// * `content` - Текст записи
+///
+/// # Возвращаемое значение
+///
+/// Путь к файлу лога или ошибка создания директории или записи
+fn write_command_log(log_file: &Path, content: &str) -> Result<String> {
+    // Создаем директорию логов, если ее нет
+    if let Some(logs_dir) = log_file.parent().filter(|dir| !dir.exists()) {
+        fs::create_dir_all(logs_dir).with_context(|| {
+            format!("Не удалось создать директорию логов {}", logs_dir.display())
+        })?;
+    }
+
+    let filename = log_file.to_string_lossy().into_owned();
+
+    // Дописываем запись в файл, создавая его при необходимости
+    let file_exists = log_file.exists();
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .with_context(|| format!("Не удалось записать лог в файл {}", filename))?;
+
+    if !file_exists {
+        info!("Создан новый лог-файл для команд: {}", filename);
+    }
+
+    Ok(filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_log_header_includes_duration() {
+        let header = command_log_header("app", "deploy", "'build'", 42);
+
+        assert!(header.contains("Команда: 'build'"));
+        assert!(header.ends_with("Длительность: 42 мс"));
+    }
+
+    #[test]
+    fn event_duration_entry_includes_total_duration() {
+        let entry = event_duration_log_entry("app", "deploy", true, Duration::from_millis(1500));
+
+        assert!(entry.contains("Событие: 'deploy' завершено"));
+        assert!(entry.contains("Общая длительность: 1500 мс (1.50 секунд)"));
+    }
+
+    #[test]
+    fn wraps_output_longer_than_configured_width() {
+        let width = 120;
+
+        assert!(!should_wrap_output(&"x".repeat(width - 1), width));
+        assert!(!should_wrap_output(&"x".repeat(width), width));
+        assert!(should_wrap_output(&"x".repeat(width + 1), width));
+        assert!(should_wrap_output("строка\nстрока", width));
+    }
+
+    #[test]
+    fn unwritable_logs_dir_falls_back_to_temp_dir() {
+        // Директория логов указывает на файл, поэтому создать в ней лог невозможно
+        let not_a_dir = tempfile::NamedTempFile::new().unwrap();
+        let log_file = PathBuf::from(format!(
+            "fallback-test-{}-{}.log",
+            std::process::id(),
+            chrono::Local::now()
+                .timestamp_nanos_opt()
+                .unwrap_or_default()
+        ));
+
+        let written = append_with_fallback(not_a_dir.path(), &log_file, "запись\n").unwrap();
+
+        let expected = std::env::temp_dir()
+            .join("deploy-commander")
+            .join(&log_file);
+        assert_eq!(Path::new(&written), expected);
+        assert_eq!(fs::read_to_string(&expected).unwrap(), "запись\n");
+        fs::remove_file(expected).unwrap();
+    }
+
+    #[test]
+    fn writable_logs_dir_is_used_directly() {
+        let logs_dir = tempfile::tempdir().unwrap();
+        let log_file = Path::new("app").join("20240115.log");
+
+        let written = append_with_fallback(logs_dir.path(), &log_file, "запись\n").unwrap();
+
+        assert_eq!(Path::new(&written), logs_dir.path().join(&log_file));
+    }
+
+    #[test]
+    fn detects_lossy_output() {
+        let output = String::from_utf8_lossy(b"ok \xff\xfe end");
+
+        assert!(has_lossy_output(&output));
+        assert!(!has_lossy_output("обычный вывод"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn log_write_does_not_block_async_tasks() {
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+        let done = AtomicBool::new(false);
+        let ticks = AtomicUsize::new(0);
+
+        let (written, _) = tokio::join!(
+            async {
+                let written = run_log_write(|| {
+                    std::thread::sleep(Duration::from_millis(50));
+                    vec!["app.log".to_string()]
+                })
+                .await;
+                done.store(true, Ordering::SeqCst);
+                written
+            },
+            async {
+                while !done.load(Ordering::SeqCst) {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                }
+            }
+        );
+
+        assert_eq!(written, vec!["app.log"]);
+        // Однопоточный runtime продолжал выполнять другую задачу во время записи
+        assert!(ticks.load(Ordering::SeqCst) > 0);
+    }
+
+    #[tokio::test]
+    async fn failed_log_write_returns_no_paths() {
+        let written = run_log_write(|| panic!("ошибка записи")).await;
+
+        assert!(written.is_empty());
+    }
+
+    #[test]
+    fn deployment_log_dir_name_replaces_path_separators() {
+        assert_eq!(deployment_log_dir_name("api").as_deref(), Some("api"));
+        assert_eq!(
+            deployment_log_dir_name("team/api\\v2").as_deref(),
+            Some("team_api_v2")
+        );
+        assert_eq!(deployment_log_dir_name("/etc").as_deref(), Some("_etc"));
+    }
+
+    #[test]
+    fn full_daily_log_rolls_to_numbered_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let daily = Path::new("20240115_commands.log");
+
+        assert_eq!(rolled_log_file(dir.path(), daily, 10), daily);
+
+        fs::write(dir.path().join(daily), "0123456789").unwrap();
+        assert_eq!(
+            rolled_log_file(dir.path(), daily, 10),
+            Path::new("20240115_commands.1.log")
+        );
+        assert_eq!(rolled_log_file(dir.path(), daily, 0), daily);
+
+        fs::write(dir.path().join("20240115_commands.1.log"), "0123456789").unwrap();
+        assert_eq!(
+            rolled_log_file(dir.path(), daily, 10),
+            Path::new("20240115_commands.2.log")
+        );
+        assert_eq!(rolled_log_file(dir.path(), daily, 11), daily);
+    }
+
+    #[test]
+    fn fail_action_answers_are_parsed() {
+        assert_eq!(parse_fail_action("r\n"), Some(FailAction::Retry));
+        assert_eq!(parse_fail_action(" Skip "), Some(FailAction::Skip));
+        assert_eq!(parse_fail_action("ABORT"), Some(FailAction::Abort));
+        assert_eq!(parse_fail_action("continue"), None);
+        assert_eq!(parse_fail_action(""), None);
+    }
+
+    #[test]
+    fn fail_action_prompt_repeats_until_answer_is_recognized() {
+        let mut input = std::io::Cursor::new("maybe\n\ns\n");
+        assert_eq!(read_fail_action(&mut input, "build"), FailAction::Skip);
+    }
+
+    #[test]
+    fn fail_action_prompt_aborts_at_end_of_input() {
+        let mut input = std::io::Cursor::new("what\n");
+        assert_eq!(read_fail_action(&mut input, "build"), FailAction::Abort);
+    }
+
+    #[test]
+    fn deployment_log_dir_name_rejects_parent_references() {
+        assert_eq!(deployment_log_dir_name(".."), None);
+        assert_eq!(deployment_log_dir_name("../outside"), None);
+        assert_eq!(deployment_log_dir_name(""), None);
     }
 }
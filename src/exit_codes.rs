@@ -0,0 +1,84 @@
+/*!
+# Модуль Exit Codes
+
+Модуль `exit_codes` определяет коды завершения программы, на которые можно
+опираться в скриптах и CI:
+
+- `0` - успешное выполнение
+- `1` - ошибка конфигурации, настроек или истории
+- `2` - деплой не найден
+- `3` - ошибка выполнения команд деплоя
+- `4` - превышено время ожидания
+- `5` - деплой выполнен, но ошибки команд с `ignore_errors` были проигнорированы
+  (только с `run --strict-ignored`)
+- `130` - выполнение прервано пользователем
+
+## Основные функции
+
+- `exit_code_for` - возвращает код завершения для категории ошибки
+- `exit_with` - завершает программу с кодом, соответствующим категории ошибки
+*/
+
+use std::process::exit;
+
+/// Категория ошибки, определяющая код завершения программы
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeployError {
+    /// Ошибка конфигурации, настроек или истории деплоев
+    Config,
+    /// Деплой с указанным именем или шаблоном не найден
+    DeploymentNotFound,
+    /// Команда деплоя завершилась с ошибкой
+    CommandFailure,
+    /// Превышено время ожидания (зарезервировано: таймауты команд пока не поддерживаются)
+    #[allow(dead_code)]
+    Timeout,
+    /// Выполнение прервано пользователем
+    Interrupted,
+    /// Ошибки команд с `ignore_errors` проигнорированы при `--strict-ignored`
+    IgnoredFailures,
+}
+
+/// Возвращает код завершения программы для категории ошибки
+///
+/// # Параметры
+///
+/// * `error` - Категория ошибки
+///
+/// # Возвращаемое значение
+///
+/// Код завершения процесса
+pub fn exit_code_for(error: &DeployError) -> i32 {
+    match error {
+        DeployError::Config => 1,
+        DeployError::DeploymentNotFound => 2,
+        DeployError::CommandFailure => 3,
+        DeployError::Timeout => 4,
+        DeployError::IgnoredFailures => 5,
+        DeployError::Interrupted => 130,
+    }
+}
+
+/// Завершает программу с кодом, соответствующим категории ошибки
+///
+/// # Параметры
+///
+/// * `error` - Категория ошибки
+pub fn exit_with(error: DeployError) -> ! {
+    exit(exit_code_for(&error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_error_category_has_its_exit_code() {
+        assert_eq!(exit_code_for(&DeployError::Config), 1);
+        assert_eq!(exit_code_for(&DeployError::DeploymentNotFound), 2);
+        assert_eq!(exit_code_for(&DeployError::CommandFailure), 3);
+        assert_eq!(exit_code_for(&DeployError::Timeout), 4);
+        assert_eq!(exit_code_for(&DeployError::IgnoredFailures), 5);
+        assert_eq!(exit_code_for(&DeployError::Interrupted), 130);
+    }
+}
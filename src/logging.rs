@@ -1,23 +1,189 @@
 use anyhow::Result;
-use log::LevelFilter;
+use log::{LevelFilter, Record};
 use log4rs::append::console::{ConsoleAppender, Target};
 use log4rs::append::file::FileAppender;
+use log4rs::append::Append;
 use log4rs::config::{Appender, Config, Root};
 use log4rs::encode::pattern::PatternEncoder;
+use log4rs::encode::writer::ansi::AnsiWriter;
+use log4rs::encode::writer::simple::SimpleWriter;
+use log4rs::encode::Encode;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{self, Write};
+use std::sync::{Mutex, OnceLock};
 
-pub fn setup_logger(log_file: &str, verbose: bool) -> Result<()> {
-    let level = if verbose {
-        LevelFilter::Debug
-    } else {
-        LevelFilter::Info
-    };
+tokio::task_local! {
+    /// Имя деплоя, консольный вывод которого буферизуется в текущей задаче
+    static BUFFERED_DEPLOYMENT: String;
+}
+
+/// Буферы консольного вывода по именам деплоев
+static OUTPUT_BUFFERS: OnceLock<Mutex<HashMap<String, Vec<String>>>> = OnceLock::new();
+
+/// Шаблон вывода журнала в консоль
+const CONSOLE_PATTERN: &str = "{d(%Y-%m-%d %H:%M:%S)} [{h({l}):<5}] {m}{n}";
+
+/// Консольный вывод журнала, который внутри `with_output_buffer` попадает в буфер деплоя
+#[derive(Debug)]
+struct BufferedConsoleAppender {
+    console: ConsoleAppender,
+    /// Шаблон консольного вывода для записей, попадающих в буфер
+    encoder: PatternEncoder,
+}
+
+impl BufferedConsoleAppender {
+    /// Форматирует запись журнала так же, как консольный вывод
+    ///
+    /// Цвета уровней сохраняются только при выводе в терминал.
+    ///
+    /// # Параметры
+    ///
+    /// * `record` - Запись журнала
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Строка записи без завершающего перевода строки
+    fn encode(&self, record: &Record) -> anyhow::Result<String> {
+        let mut buffer = Vec::new();
+        if crate::output::is_terminal() {
+            self.encoder.encode(&mut AnsiWriter(&mut buffer), record)?;
+        } else {
+            self.encoder
+                .encode(&mut SimpleWriter(&mut buffer), record)?;
+        }
+
+        let line = String::from_utf8_lossy(&buffer);
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+impl Append for BufferedConsoleAppender {
+    fn append(&self, record: &Record) -> anyhow::Result<()> {
+        if BUFFERED_DEPLOYMENT.try_with(|_| ()).is_err() {
+            return self.console.append(record);
+        }
+
+        buffer_line(&self.encode(record)?);
+        Ok(())
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+    }
+}
+
+/// Выполняет задачу, буферизуя ее консольный вывод под именем деплоя
+///
+/// Буферизуются записи журнала и строки `output::line`, сделанные в этой задаче;
+/// файл журнала и логи команд записываются без задержки.
+///
+/// # Параметры
+///
+/// * `deployment` - Имя деплоя
+/// * `future` - Задача деплоя
+///
+/// # Возвращаемое значение
+///
+/// Результат задачи
+pub async fn with_output_buffer<F: Future>(deployment: String, future: F) -> F::Output {
+    BUFFERED_DEPLOYMENT.scope(deployment, future).await
+}
+
+/// Добавляет строку в буфер деплоя текущей задачи
+///
+/// # Параметры
+///
+/// * `line` - Строка вывода
+///
+/// # Возвращаемое значение
+///
+/// true, если строка буферизована; false, если задача выводит в консоль напрямую
+pub fn buffer_line(line: &str) -> bool {
+    BUFFERED_DEPLOYMENT
+        .try_with(|deployment| {
+            output_buffers()
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry(deployment.clone())
+                .or_default()
+                .push(line.to_string());
+        })
+        .is_ok()
+}
+
+/// Выводит накопленный вывод деплоя одним непрерывным блоком
+///
+/// # Параметры
+///
+/// * `deployment` - Имя деплоя
+pub fn flush_output_buffer(deployment: &str) {
+    let lines = take_output_buffer(deployment);
+
+    let mut stdout = io::stdout().lock();
+    for line in lines {
+        let _ = writeln!(stdout, "{}", line);
+    }
+    let _ = stdout.flush();
+}
+
+/// Извлекает накопленный вывод деплоя, очищая его буфер
+///
+/// # Параметры
+///
+/// * `deployment` - Имя деплоя
+///
+/// # Возвращаемое значение
+///
+/// Строки вывода в порядке их добавления
+fn take_output_buffer(deployment: &str) -> Vec<String> {
+    output_buffers()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(deployment)
+        .unwrap_or_default()
+}
+
+/// Возвращает буферы консольного вывода деплоев
+fn output_buffers() -> &'static Mutex<HashMap<String, Vec<String>>> {
+    OUTPUT_BUFFERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Определяет уровень журналирования по числу флагов `-v`
+///
+/// Без флагов используется Info: информационные сообщения - основной вывод запуска
+/// в консоль и в файл журнала. Более подробных уровней два, поэтому `-vvv` и
+/// больше равнозначны `-vv`.
+///
+/// # Параметры
+///
+/// * `verbosity` - Количество указанных флагов `-v`/`--verbose`
+///
+/// # Возвращаемое значение
+///
+/// Info без флагов, Debug для `-v`, Trace для `-vv` и выше
+pub fn level_for_verbosity(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Info,
+        1 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Настраивает журналирование в консоль и файл
+///
+/// # Параметры
+///
+/// * `log_file` - Путь к файлу журнала
+/// * `verbosity` - Количество указанных флагов `-v`/`--verbose`
+/// * `console` - Выводить журнал в консоль (без него записи попадают только в файл)
+pub fn setup_logger(log_file: &str, verbosity: u8, console: bool) -> Result<()> {
+    let level = level_for_verbosity(verbosity);
 
     // Шаблон вывода для консоли
     let stdout = ConsoleAppender::builder()
         .target(Target::Stdout)
-        .encoder(Box::new(PatternEncoder::new(
-            "{d(%Y-%m-%d %H:%M:%S)} [{h({l}):<5}] {m}{n}",
-        )))
+        .encoder(Box::new(PatternEncoder::new(CONSOLE_PATTERN)))
         .build();
 
     // Шаблон вывода для файла журнала
@@ -28,18 +194,87 @@ pub fn setup_logger(log_file: &str, verbose: bool) -> Result<()> {
         .build(log_file)?;
 
     // Создание конфигурации
+    let mut root = Root::builder().appender("file");
+    if console {
+        root = root.appender("stdout");
+    }
     let config = Config::builder()
-        .appender(Appender::builder().build("stdout", Box::new(stdout)))
+        .appender(Appender::builder().build(
+            "stdout",
+            Box::new(BufferedConsoleAppender {
+                console: stdout,
+                encoder: PatternEncoder::new(CONSOLE_PATTERN),
+            }),
+        ))
         .appender(Appender::builder().build("file", Box::new(file)))
-        .build(
-            Root::builder()
-                .appender("stdout")
-                .appender("file")
-                .build(level),
-        )?;
+        .build(root.build(level))?;
 
     // Применение конфигурации
     log4rs::init_config(config)?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_count_maps_to_level() {
+        assert_eq!(level_for_verbosity(0), LevelFilter::Info);
+        assert_eq!(level_for_verbosity(1), LevelFilter::Debug);
+        assert_eq!(level_for_verbosity(2), LevelFilter::Trace);
+        assert_eq!(level_for_verbosity(3), LevelFilter::Trace);
+        assert_eq!(level_for_verbosity(u8::MAX), LevelFilter::Trace);
+    }
+
+    #[tokio::test]
+    async fn buffered_output_of_each_deployment_is_contiguous() {
+        let deployment = |name: &'static str| {
+            with_output_buffer(format!("contiguous-{}", name), async move {
+                for step in 0..3 {
+                    buffer_line(&format!("{} {}", name, step));
+                    tokio::task::yield_now().await;
+                }
+            })
+        };
+
+        tokio::join!(deployment("first"), deployment("second"));
+
+        assert!(!buffer_line("outside of deployment"));
+        assert_eq!(
+            take_output_buffer("contiguous-first"),
+            vec!["first 0", "first 1", "first 2"]
+        );
+        assert_eq!(
+            take_output_buffer("contiguous-second"),
+            vec!["second 0", "second 1", "second 2"]
+        );
+        assert!(take_output_buffer("contiguous-first").is_empty());
+    }
+
+    #[tokio::test]
+    async fn buffered_records_use_console_pattern() {
+        let appender = BufferedConsoleAppender {
+            console: ConsoleAppender::builder().build(),
+            encoder: PatternEncoder::new("[{l}] {m}{n}"),
+        };
+
+        with_output_buffer("pattern".to_string(), async {
+            appender
+                .append(
+                    &Record::builder()
+                        .level(log::Level::Warn)
+                        .args(format_args!("disk is almost full"))
+                        .build(),
+                )
+                .unwrap();
+        })
+        .await;
+
+        assert_eq!(
+            take_output_buffer("pattern"),
+            vec!["[WARN] disk is almost full"]
+        );
+    }
+}
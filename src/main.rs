@@ -20,14 +20,21 @@ Deploy Commander - это инструмент командной строки 
 - `commands` - Работа с системными командами
 - `events` - Система событий и уведомлений
 - `executor` - Выполнение команд и обработка ошибок
+- `exit_codes` - Коды завершения программы
 - `logging` - Настройка журналирования
+- `maintenance` - Окна обслуживания деплоев
+- `output` - Вывод информации для пользователя с поддержкой цвета
+- `report` - Отчеты о выполнении в формате JUnit XML
 - `run` - Управление процессом деплоя
+- `schema` - JSON Schema формата конфигурации
 - `settings` - Глобальные настройки приложения
 - `storage` - Хранение и управление историей деплоев
+- `telemetry` - Отправка спанов выполнения в OpenTelemetry
+- `timings` - Длительности выполнения в формате JSON
+- `util` - Вспомогательные функции (расстояние редактирования)
 */
 
 use clap::Parser;
-use std::process::exit;
 
 mod app;
 mod cli;
@@ -35,30 +42,66 @@ mod commands;
 mod config;
 mod events;
 mod executor;
+mod exit_codes;
 mod logging;
+mod maintenance;
+mod output;
+mod report;
 mod run;
+mod schema;
 mod settings;
 mod storage;
+mod telemetry;
+mod timings;
+mod util;
 
 use cli::Cli;
+use exit_codes::{exit_with, DeployError};
 
 #[tokio::main]
 async fn main() {
     // Парсинг аргументов командной строки
     let cli = Cli::parse();
 
+    // Настройка цветного вывода
+    output::init(cli.color);
+
+    // Генерация автодополнения не требует инициализации, чтобы не засорять вывод логами
+    if let cli::Command::Completions { shell } = &cli.command {
+        app::print_completions(*shell, &app::resolve_config_path(&cli));
+        return;
+    }
+
+    // Схема конфигурации также не зависит от настроек и файла конфигурации
+    if let cli::Command::Schema { output } = &cli.command {
+        app::handle_schema_command(output.as_deref());
+        return;
+    }
+
+    // Проверка настроек выполняется до настройки логирования по путям из этих настроек
+    if let cli::Command::ValidateSettings = &cli.command {
+        app::handle_validate_settings_command();
+        return;
+    }
+
     // Инициализация приложения, загрузка настроек и конфигурации
     let app_context = match app::initialize(&cli) {
         Ok(context) => context,
         Err(e) => {
             eprintln!("{}", e);
-            exit(1);
+            exit_with(DeployError::Config);
         }
     };
 
     // Проверка наличия необходимых команд
-    app::check_dependencies().await;
+    app::check_dependencies(&cli, &app_context).await;
 
-    // Выполнение команды
-    app::execute_command(&cli, &app_context).await;
+    // Выполнение команды; прерывание по Ctrl+C завершает программу с кодом 130
+    tokio::select! {
+        _ = app::execute_command(&cli, &app_context) => {}
+        _ = tokio::signal::ctrl_c() => {
+            log::error!("Выполнение прервано пользователем");
+            exit_with(DeployError::Interrupted);
+        }
+    }
 }
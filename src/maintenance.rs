@@ -0,0 +1,186 @@
+/*!
+# Модуль Maintenance
+
+Модуль `maintenance` разбирает окна обслуживания деплоев и проверяет, попадает ли
+в них текущее время:
+
+- Формат `HH:MM-HH:MM` (ежедневно) или `Mon-Fri HH:MM-HH:MM` (в указанные дни)
+- Дни задаются списком и диапазонами: `Sat,Sun`, `Mon-Thu,Sat`
+- Окна через полночь (`22:00-06:00`): время после полуночи относится к окну,
+  начавшемуся накануне
+
+## Основные функции
+
+- `MaintenanceWindow::parse` - разбирает строку окна обслуживания
+- `MaintenanceWindow::contains` - проверяет, попадает ли момент времени в окно
+*/
+
+use anyhow::{Context, Result};
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
+
+/// Окно обслуживания, в которое разрешен запуск деплоя
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceWindow {
+    /// Дни начала окна (None - каждый день)
+    days: Option<Vec<Weekday>>,
+    /// Время начала окна
+    start: NaiveTime,
+    /// Время окончания окна (меньше начала для окон через полночь)
+    end: NaiveTime,
+}
+
+impl MaintenanceWindow {
+    /// Разбирает строку окна обслуживания
+    ///
+    /// # Параметры
+    ///
+    /// * `spec` - Окно в формате `[дни] HH:MM-HH:MM`, например `Mon-Fri 22:00-06:00`
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Окно обслуживания или ошибка формата
+    pub fn parse(spec: &str) -> Result<Self> {
+        let parts: Vec<&str> = spec.split_whitespace().collect();
+        let (days, times) = match parts.as_slice() {
+            [times] => (None, *times),
+            [days, times] => (Some(parse_days(days)?), *times),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Неверный формат окна обслуживания '{}', ожидается '[дни] HH:MM-HH:MM'",
+                    spec
+                ))
+            }
+        };
+
+        let (start, end) = times.split_once('-').with_context(|| {
+            format!(
+                "Неверный интервал времени '{}', ожидается HH:MM-HH:MM",
+                times
+            )
+        })?;
+
+        Ok(Self {
+            days,
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        })
+    }
+
+    /// Проверяет, попадает ли момент времени в окно обслуживания
+    ///
+    /// # Параметры
+    ///
+    /// * `moment` - Локальные дата и время
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// true, если запуск в этот момент разрешен
+    pub fn contains(&self, moment: NaiveDateTime) -> bool {
+        let time = moment.time();
+        let day = moment.weekday();
+
+        if self.start <= self.end {
+            return self.allows_day(day) && time >= self.start && time < self.end;
+        }
+
+        // Окно через полночь: вечер дня начала или утро следующего дня
+        (self.allows_day(day) && time >= self.start)
+            || (self.allows_day(day.pred()) && time < self.end)
+    }
+
+    /// Проверяет, может ли окно начинаться в указанный день недели
+    fn allows_day(&self, day: Weekday) -> bool {
+        self.days.as_ref().is_none_or(|days| days.contains(&day))
+    }
+}
+
+/// Разбирает время в формате `HH:MM`
+fn parse_time(time: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .with_context(|| format!("Неверное время '{}', ожидается HH:MM", time))
+}
+
+/// Разбирает список дней недели вида `Mon-Fri,Sun`
+fn parse_days(spec: &str) -> Result<Vec<Weekday>> {
+    let mut days = Vec::new();
+
+    for part in spec.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let last = parse_day(last)?;
+                let mut day = parse_day(first)?;
+                days.push(day);
+                while day != last {
+                    day = day.succ();
+                    days.push(day);
+                }
+            }
+            None => days.push(parse_day(part)?),
+        }
+    }
+
+    Ok(days)
+}
+
+/// Разбирает день недели (`Mon`, `monday`)
+fn parse_day(day: &str) -> Result<Weekday> {
+    day.parse::<Weekday>().map_err(|_| {
+        anyhow::anyhow!(
+            "Неизвестный день недели '{}', ожидается Mon, Tue, Wed, Thu, Fri, Sat или Sun",
+            day
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Момент времени в неделе с 2024-01-01 (понедельник)
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    #[test]
+    fn daily_window_contains_only_its_hours() {
+        let window = MaintenanceWindow::parse("09:00-17:30").unwrap();
+
+        assert!(window.contains(at(1, "09:00")));
+        assert!(window.contains(at(7, "17:29")));
+        assert!(!window.contains(at(1, "08:59")));
+        assert!(!window.contains(at(1, "17:30")));
+    }
+
+    #[test]
+    fn window_with_days_excludes_other_days() {
+        let window = MaintenanceWindow::parse("Mon-Wed,Fri 10:00-12:00").unwrap();
+
+        assert!(window.contains(at(1, "11:00")));
+        assert!(window.contains(at(3, "11:00")));
+        assert!(window.contains(at(5, "11:00")));
+        assert!(!window.contains(at(4, "11:00")));
+        assert!(!window.contains(at(6, "11:00")));
+    }
+
+    #[test]
+    fn overnight_window_belongs_to_the_starting_day() {
+        let window = MaintenanceWindow::parse("Fri 22:00-06:00").unwrap();
+
+        assert!(window.contains(at(5, "23:00")));
+        assert!(window.contains(at(6, "05:59")));
+        assert!(!window.contains(at(6, "06:00")));
+        assert!(!window.contains(at(5, "05:00")));
+        assert!(!window.contains(at(6, "23:00")));
+    }
+
+    #[test]
+    fn invalid_windows_are_rejected() {
+        assert!(MaintenanceWindow::parse("9-17").is_err());
+        assert!(MaintenanceWindow::parse("25:00-26:00").is_err());
+        assert!(MaintenanceWindow::parse("Someday 09:00-17:00").is_err());
+        assert!(MaintenanceWindow::parse("Mon 09:00-17:00 extra").is_err());
+        assert!(MaintenanceWindow::parse("09:00").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+/*!
+# Модуль Output
+
+Модуль `output` централизует вывод информации для пользователя:
+
+- Единая точка вывода строк в стандартный поток
+- Оформление заголовков и статусов цветом
+- Управление цветом через флаг `--color` и переменную окружения `NO_COLOR`
+
+## Основные функции
+
+- `init` - настраивает режим цветного вывода
+- `is_terminal` - проверяет, идет ли вывод в терминал
+- `line` - выводит строку пользователю
+- `heading`, `success`, `failure`, `muted` - оформляют текст цветом
+*/
+
+use colored::Colorize;
+use std::io::IsTerminal;
+
+/// Режим цветного вывода
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ColorMode {
+    /// Цвет включается, если вывод идет в терминал и не задана переменная NO_COLOR
+    #[default]
+    Auto,
+    /// Всегда выводить цвет
+    Always,
+    /// Никогда не выводить цвет
+    Never,
+}
+
+/// Настраивает режим цветного вывода
+///
+/// # Параметры
+///
+/// * `mode` - Режим цветного вывода
+pub fn init(mode: ColorMode) {
+    match mode {
+        ColorMode::Always => colored::control::set_override(true),
+        ColorMode::Never => colored::control::set_override(false),
+        // В автоматическом режиме учитываются NO_COLOR, CLICOLOR и наличие терминала
+        ColorMode::Auto => colored::control::unset_override(),
+    }
+}
+
+/// Проверяет, идет ли стандартный вывод в терминал
+///
+/// Индикаторы прогресса выводятся только в терминал, чтобы не засорять
+/// вывод, перенаправленный в файл или CI.
+pub fn is_terminal() -> bool {
+    std::io::stdout().is_terminal()
+}
+
+/// Выводит строку пользователю
+///
+/// # Параметры
+///
+/// * `text` - Текст для вывода
+pub fn line(text: impl AsRef<str>) {
+    // При буферизации вывода деплоя строка выводится вместе с остальным его выводом
+    if !crate::logging::buffer_line(text.as_ref()) {
+        println!("{}", text.as_ref());
+    }
+}
+
+/// Выводит пустую строку
+pub fn blank() {
+    println!();
+}
+
+/// Оформляет текст как заголовок
+pub fn heading(text: &str) -> String {
+    text.bold().to_string()
+}
+
+/// Оформляет текст как успешный статус
+pub fn success(text: &str) -> String {
+    text.green().to_string()
+}
+
+/// Оформляет текст как статус ошибки
+pub fn failure(text: &str) -> String {
+    text.red().to_string()
+}
+
+/// Оформляет второстепенный текст
+pub fn muted(text: &str) -> String {
+    text.dimmed().to_string()
+}
@@ -0,0 +1,208 @@
+/*!
+# Модуль Report
+
+Модуль `report` формирует отчет о выполнении деплоя в формате JUnit XML для CI:
+
+- Сбор результатов выполнения событий деплоев
+- Сериализация результатов в JUnit XML (каждое событие - отдельный testcase)
+- Запись отчета в файл после каждого события, чтобы он сохранялся даже при аварийном завершении
+
+## Основные функции
+
+- `enable` - включает запись отчета в указанный файл
+- `record_event` - добавляет результат выполнения события в отчет
+- `to_junit_xml` - сериализует результаты в JUnit XML
+*/
+
+use log::{info, warn};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Результат выполнения события для отчета
+#[derive(Debug, Clone)]
+pub struct EventReport {
+    /// Имя деплоя
+    pub deployment: String,
+    /// Имя события
+    pub event: String,
+    /// Длительность выполнения события
+    pub duration: Duration,
+    /// Сообщение об ошибке, если событие завершилось неудачно
+    pub failure: Option<String>,
+}
+
+/// Состояние отчета: путь к файлу и накопленные результаты
+struct Report {
+    path: String,
+    events: Vec<EventReport>,
+}
+
+/// Отчет текущего запуска (включается флагом `--report-file`)
+static REPORT: OnceLock<Mutex<Report>> = OnceLock::new();
+
+/// Включает запись отчета JUnit XML в указанный файл
+///
+/// # Параметры
+///
+/// * `path` - Путь к файлу отчета
+pub fn enable(path: &str) {
+    info!("Отчет о выполнении будет записан в файл: {}", path);
+    let _ = REPORT.set(Mutex::new(Report {
+        path: path.to_string(),
+        events: Vec::new(),
+    }));
+}
+
+/// Добавляет результат выполнения события в отчет и перезаписывает файл отчета
+///
+/// Ничего не делает, если отчет не включен.
+///
+/// # Параметры
+///
+/// * `deployment` - Имя деплоя
+/// * `event` - Имя события
+/// * `duration` - Длительность выполнения события
+/// * `failure` - Сообщение об ошибке или None при успехе
+pub fn record_event(deployment: &str, event: &str, duration: Duration, failure: Option<&str>) {
+    let Some(report) = REPORT.get() else {
+        return;
+    };
+
+    let mut report = report.lock().unwrap_or_else(|e| e.into_inner());
+    report.events.push(EventReport {
+        deployment: deployment.to_string(),
+        event: event.to_string(),
+        duration,
+        failure: failure.map(str::to_string),
+    });
+
+    let xml = to_junit_xml(&report.events);
+    if let Err(e) = fs::write(&report.path, xml) {
+        warn!("Не удалось записать отчет в файл {}: {}", report.path, e);
+    }
+}
+
+/// Сериализует результаты выполнения событий в JUnit XML
+///
+/// События группируются в `testsuite` по деплоям в порядке их выполнения.
+///
+/// # Параметры
+///
+/// * `events` - Результаты выполнения событий
+///
+/// # Возвращаемое значение
+///
+/// Текст отчета в формате JUnit XML
+pub fn to_junit_xml(events: &[EventReport]) -> String {
+    let mut deployments: Vec<&str> = Vec::new();
+    for event in events {
+        if !deployments.contains(&event.deployment.as_str()) {
+            deployments.push(&event.deployment);
+        }
+    }
+
+    let total_failures = events.iter().filter(|e| e.failure.is_some()).count();
+    let total_time: f64 = events.iter().map(|e| e.duration.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"deploy-commander\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        events.len(),
+        total_failures,
+        total_time
+    ));
+
+    for deployment in deployments {
+        let suite: Vec<&EventReport> = events
+            .iter()
+            .filter(|e| e.deployment == deployment)
+            .collect();
+        let failures = suite.iter().filter(|e| e.failure.is_some()).count();
+        let time: f64 = suite.iter().map(|e| e.duration.as_secs_f64()).sum();
+
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+            escape_xml(deployment),
+            suite.len(),
+            failures,
+            time
+        ));
+
+        for event in suite {
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                escape_xml(&event.deployment),
+                escape_xml(&event.event),
+                event.duration.as_secs_f64()
+            ));
+
+            match &event.failure {
+                Some(message) => {
+                    xml.push_str(">\n");
+                    xml.push_str(&format!(
+                        "      <failure message=\"{}\">{}</failure>\n",
+                        escape_xml(message),
+                        escape_xml(message)
+                    ));
+                    xml.push_str("    </testcase>\n");
+                }
+                None => xml.push_str("/>\n"),
+            }
+        }
+
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Экранирует специальные символы XML
+///
+/// # Параметры
+///
+/// * `value` - Исходная строка
+///
+/// # Возвращаемое значение
+///
+/// Строка, безопасная для использования в тексте и атрибутах XML
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(deployment: &str, event: &str, failure: Option<&str>) -> EventReport {
+        EventReport {
+            deployment: deployment.to_string(),
+            event: event.to_string(),
+            duration: Duration::from_millis(1500),
+            failure: failure.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn junit_xml_has_testcase_per_event_and_failure() {
+        let xml = to_junit_xml(&[
+            event("api", "build", None),
+            event("api", "deploy", Some("exit code 1 <stderr>")),
+            event("web", "deploy", None),
+        ]);
+
+        assert_eq!(xml.matches("<testcase ").count(), 3);
+        assert_eq!(xml.matches("<testsuite ").count(), 2);
+        assert_eq!(xml.matches("<failure ").count(), 1);
+        assert!(xml.contains("tests=\"3\" failures=\"1\" time=\"4.500\""));
+        assert!(xml.contains(
+            "<testcase classname=\"api\" name=\"deploy\" time=\"1.500\">\n      <failure message=\"exit code 1 &lt;stderr&gt;\">"
+        ));
+    }
+}
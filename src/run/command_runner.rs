@@ -1,12 +1,132 @@
 use anyhow::Result;
 use log::{error, info, warn};
 use std::path::Path;
+use std::time::Instant;
 
-use crate::config::Config;
+use crate::commands;
+use crate::config::{Config, Deployment};
 use crate::executor;
+use crate::executor::RunOptions;
+use crate::report;
 use crate::settings;
 use crate::storage;
 
+/// Снимает снимок окружения деплоя командой `snapshot_command` перед запуском событий
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+/// * `options` - Параметры запуска из командной строки
+///
+/// # Возвращаемое значение
+///
+/// true, если снимок снят или команда снимка не задана
+pub async fn snapshot_deployment(deployment: &Deployment, options: &RunOptions) -> bool {
+    let Some(command) = deployment.snapshot_command.as_deref() else {
+        return true;
+    };
+
+    info!("Снимок окружения деплоя '{}'", deployment.name);
+    match executor::run_deployment_command(deployment, "snapshot", command, options).await {
+        Ok(_) => true,
+        Err(e) => {
+            error!(
+                "Не удалось снять снимок окружения деплоя '{}', события не запускаются: {}",
+                deployment.name, e
+            );
+            false
+        }
+    }
+}
+
+/// Восстанавливает окружение деплоя командой `restore_command` после ошибки события
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+/// * `options` - Параметры запуска из командной строки
+pub async fn restore_deployment(deployment: &Deployment, options: &RunOptions) {
+    let Some(command) = deployment.restore_command.as_deref() else {
+        return;
+    };
+
+    warn!("Восстановление окружения деплоя '{}'", deployment.name);
+    match executor::run_deployment_command(deployment, "restore", command, options).await {
+        Ok(_) => info!("Окружение деплоя '{}' восстановлено", deployment.name),
+        Err(e) => error!(
+            "Не удалось восстановить окружение деплоя '{}': {}",
+            deployment.name, e
+        ),
+    }
+}
+
+/// Записывает успешное завершение всех событий деплоя с развернутой версией
+///
+/// Версия определяется командой `version_command`; если она не задана,
+/// завершилась ошибкой или запуск выполняется в режиме имитации (`--simulate`),
+/// запись сохраняется без версии.
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории деплоев
+/// * `deployment` - Конфигурация деплоя
+/// * `options` - Параметры запуска из командной строки
+///
+/// # Возвращаемое значение
+///
+/// Результат записи или ошибка
+pub async fn record_deploy_success(
+    history_path: &str,
+    deployment: &Deployment,
+    options: &RunOptions,
+) -> Result<()> {
+    let deployed_version = match deployment.version_command.as_deref() {
+        // Имитация команды версии вывела бы свой текст вместо версии
+        Some(_) if options.simulate.is_some() => {
+            info!(
+                "Режим имитации: версия деплоя '{}' не определяется",
+                deployment.name
+            );
+            None
+        }
+        Some(command) => {
+            match executor::run_deployment_command(deployment, "version", command, options).await {
+                Ok(version) if !version.is_empty() => {
+                    info!(
+                        "Развернута версия деплоя '{}': {}",
+                        deployment.name, version
+                    );
+                    Some(version)
+                }
+                Ok(_) => {
+                    warn!(
+                        "Команда version_command деплоя '{}' не вывела версию",
+                        deployment.name
+                    );
+                    None
+                }
+                Err(e) => {
+                    warn!(
+                        "Не удалось определить версию деплоя '{}': {}",
+                        deployment.name, e
+                    );
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    storage::record_deployment_complete(
+        history_path,
+        &deployment.name,
+        storage::FULL_DEPLOY_EVENT,
+        Some("Все события деплоя успешно завершены".to_string()),
+        deployed_version,
+    )
+    .map_err(|e| anyhow::anyhow!("Ошибка записи успешного события: {}", e))
+}
+
 /// Выполняет команды для указанного деплоя и события
 ///
 /// # Параметры
@@ -15,6 +135,7 @@ use crate::storage;
 /// * `deployment_name` - Имя деплоя
 /// * `event_name` - Имя события
 /// * `history_path` - Путь к файлу истории деплоев
+/// * `options` - Параметры запуска из командной строки
 ///
 /// # Возвращаемое значение
 ///
@@ -24,6 +145,7 @@ pub async fn execute_command(
     deployment_name: &str,
     event_name: &str,
     history_path: &str,
+    options: &RunOptions,
 ) -> Result<()> {
     info!(
         "Выполнение команд для деплоя '{}', событие '{}'",
@@ -32,7 +154,9 @@ pub async fn execute_command(
 
     // Получаем настройки и путь к глобальному файлу переменных
     let settings = settings::get_settings(settings::DEFAULT_SETTINGS_PATH).unwrap_or_default();
-    let global_variables_file = if Path::new(&settings.variables_file).exists() {
+    let global_variables_file = if Path::new(&settings.variables_file).exists()
+        || executor::is_remote_variables_file(&settings.variables_file)
+    {
         Some(settings.variables_file.as_str())
     } else {
         None
@@ -42,19 +166,38 @@ pub async fn execute_command(
         info!("Используется глобальный файл переменных: {}", file);
     }
 
-    // Записываем событие начала деплоя
-    if let Err(e) = crate::storage::record_deployment(
-        history_path,
-        deployment_name,
-        &format!("start-{}", event_name),
-        true,
-        None,
-    ) {
+    // Записываем событие начала деплоя с развертываемым коммитом git
+    let working_dir = config
+        .find_deployment(deployment_name)
+        .and_then(|d| d.working_dir.as_deref());
+    let git_sha = commands::git_commit_sha(working_dir).await;
+    if let Err(e) =
+        storage::record_deployment_start(history_path, deployment_name, event_name, git_sha)
+    {
         info!("Ошибка записи события: {}", e);
     }
 
     // Вызываем выполнение команд из executor
-    match executor::run_commands(config, deployment_name, event_name, global_variables_file).await {
+    let start_time = Instant::now();
+    let result = executor::run_commands(
+        config,
+        deployment_name,
+        event_name,
+        global_variables_file,
+        options,
+    )
+    .await;
+
+    // Добавляем результат события в отчет (если включен --report-file)
+    let failure = result.as_ref().err().map(|e| e.to_string());
+    report::record_event(
+        deployment_name,
+        event_name,
+        start_time.elapsed(),
+        failure.as_deref(),
+    );
+
+    match result {
         Ok(_) => {
             info!(
                 "Деплой '{}', событие '{}' успешно выполнено",
@@ -62,12 +205,7 @@ pub async fn execute_command(
             );
 
             // Записываем успешное завершение
-            if let Err(e) = record_success(
-                history_path,
-                deployment_name,
-                &format!("complete-{}", event_name),
-                None,
-            ) {
+            if let Err(e) = record_success(history_path, deployment_name, event_name, None) {
                 info!("Ошибка записи события: {}", e);
             }
 
@@ -80,12 +218,9 @@ pub async fn execute_command(
             );
 
             // Записываем ошибку
-            if let Err(log_err) = record_failure(
-                history_path,
-                deployment_name,
-                &format!("failed-{}", event_name),
-                e.to_string(),
-            ) {
+            if let Err(log_err) =
+                record_failure(history_path, deployment_name, event_name, e.to_string())
+            {
                 info!("Ошибка записи события: {}", log_err);
             }
 
@@ -105,7 +240,8 @@ pub fn record_success(
     if let Err(e) = storage::record_deployment(
         history_path,
         deployment,
-        &format!("success-{}", event),
+        storage::RecordKind::Complete,
+        event,
         true,
         Some(details),
     ) {
@@ -125,7 +261,8 @@ pub fn record_failure(
     if let Err(e) = storage::record_deployment(
         history_path,
         deployment,
-        &format!("failed-{}", event),
+        storage::RecordKind::Failed,
+        event,
         false,
         Some(error_msg),
     ) {
@@ -0,0 +1,180 @@
+/*!
+# Подмодуль Config Diff
+
+Сравнивает текущую конфигурацию деплоя с конфигурацией,
+использованной при последнем запуске:
+
+- Загрузка снимка конфигурации из файла рядом с историей
+- Построчное сравнение нормализованных представлений
+*/
+
+use log::{error, info};
+
+use crate::config::Config;
+use crate::exit_codes::{exit_with, DeployError};
+use crate::output;
+use crate::run::deployments;
+use crate::storage;
+
+/// Строит построчную разницу двух текстов
+///
+/// Строки, удаленные из `old`, помечаются префиксом `- `,
+/// добавленные в `new` - префиксом `+ `, общие - двумя пробелами.
+///
+/// # Параметры
+///
+/// * `old` - Исходный текст
+/// * `new` - Новый текст
+///
+/// # Возвращаемое значение
+///
+/// Список строк разницы
+pub fn line_diff(old: &str, new: &str) -> Vec<String> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let (n, m) = (old_lines.len(), new_lines.len());
+
+    // Таблица длин наибольшей общей подпоследовательности для суффиксов
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_lines[i] == new_lines[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_lines[i] == new_lines[j] {
+            diff.push(format!("  {}", old_lines[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(format!("- {}", old_lines[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+ {}", new_lines[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old_lines[i..].iter().map(|line| format!("- {}", line)));
+    diff.extend(new_lines[j..].iter().map(|line| format!("+ {}", line)));
+
+    diff
+}
+
+/// Выводит разницу между текущей конфигурацией деплоя и конфигурацией последнего запуска
+///
+/// # Параметры
+///
+/// * `config` - Текущая конфигурация
+/// * `history_path` - Путь к файлу истории
+/// * `deployment_name` - Имя деплоя
+pub fn show_config_diff(config: &Config, history_path: &str, deployment_name: &str) {
+    let deployment = match deployments::get_deployment_config(config, deployment_name) {
+        Ok(deployment) => deployment,
+        Err(e) => {
+            error!("Ошибка получения конфигурации деплоя: {}", e);
+            exit_with(DeployError::Config);
+        }
+    };
+
+    let current = match storage::normalize_deployment_config(deployment) {
+        Ok(current) => current,
+        Err(e) => {
+            error!("Ошибка нормализации конфигурации: {}", e);
+            exit_with(DeployError::Config);
+        }
+    };
+
+    let previous = match storage::load_config_snapshot(history_path, deployment_name) {
+        Ok(Some(previous)) => previous,
+        Ok(None) => {
+            output::line(format!(
+                "Снимок конфигурации для деплоя '{}' не найден: деплой еще не запускался",
+                deployment_name
+            ));
+            return;
+        }
+        Err(e) => {
+            error!("Ошибка загрузки снимка конфигурации: {}", e);
+            exit_with(DeployError::Config);
+        }
+    };
+
+    if previous == current {
+        output::line(output::success(&format!(
+            "Конфигурация деплоя '{}' не изменилась с последнего запуска",
+            deployment_name
+        )));
+        return;
+    }
+
+    info!(
+        "Конфигурация деплоя '{}' изменилась с последнего запуска",
+        deployment_name
+    );
+    output::line(output::heading(&format!(
+        "Изменения конфигурации деплоя '{}' с последнего запуска:",
+        deployment_name
+    )));
+    for line in line_diff(&previous, &current) {
+        if line.starts_with("+ ") {
+            output::line(output::success(&line));
+        } else if line.starts_with("- ") {
+            output::line(output::failure(&line));
+        } else {
+            output::line(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Deployment;
+
+    fn deployment(build_command: &str) -> Deployment {
+        serde_yaml::from_str(&format!(
+            r#"
+name: app
+events:
+  - name: deploy
+    commands:
+      - command: "{}"
+      - command: "make deploy"
+"#,
+            build_command
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn diff_mentions_changed_command() {
+        let old = storage::normalize_deployment_config(&deployment("make build")).unwrap();
+        let new =
+            storage::normalize_deployment_config(&deployment("make build --release")).unwrap();
+
+        let changes: Vec<String> = line_diff(&old, &new)
+            .into_iter()
+            .filter(|line| !line.starts_with("  "))
+            .collect();
+
+        assert_eq!(changes.len(), 2);
+        assert!(changes[0].starts_with("- ") && changes[0].ends_with("command: make build"));
+        assert!(
+            changes[1].starts_with("+ ") && changes[1].ends_with("command: make build --release")
+        );
+    }
+
+    #[test]
+    fn identical_texts_have_only_common_lines() {
+        let diff = line_diff("a\nb\n", "a\nb\n");
+
+        assert_eq!(diff, vec!["  a", "  b"]);
+    }
+}
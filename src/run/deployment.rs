@@ -1,12 +1,21 @@
 use log::{error, info, warn};
-use std::process::exit;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::{Id, JoinSet};
 
 use crate::config::Config;
+use crate::executor::RunOptions;
+use crate::exit_codes::{exit_with, DeployError};
+use crate::logging;
+use crate::output;
 use crate::run::command_runner;
 use crate::run::deployments;
 use crate::run::history;
 use crate::storage;
+use crate::telemetry;
 
 /// Структура для параметров выполнения события
 pub struct EventExecutionParams<'a> {
@@ -16,13 +25,16 @@ pub struct EventExecutionParams<'a> {
     pub history_path: &'a str,
 }
 
+/// Деплои, завершившиеся с ошибкой: имя деплоя и описание ошибки
+pub type FailedDeployments = Arc<Mutex<Vec<(String, String)>>>;
+
 /// Структура для параметров выполнения всех событий деплоя
 pub struct DeploymentExecutionParams<'a> {
     pub config: &'a Config,
     pub deployment_name: &'a str,
     #[allow(dead_code)] // Используется внутри других методов
     pub history_path: &'a str,
-    pub failed_deployments: &'a Arc<Mutex<Vec<String>>>,
+    pub failed_deployments: &'a FailedDeployments,
 }
 
 /// Структура для параметров запуска всех деплоев
@@ -30,7 +42,7 @@ pub struct AllDeploymentsParams<'a> {
     pub event: Option<&'a str>,
     #[allow(dead_code)] // Используется внутри метода run_all_deployments
     pub parallel: bool,
-    pub failed_deployments: Arc<Mutex<Vec<String>>>,
+    pub failed_deployments: FailedDeployments,
 }
 
 /// Структура, отвечающая за работу с деплоем
@@ -38,6 +50,7 @@ pub struct Deployment {
     config: Config,
     history_path: String,
     parallel_mode: bool,
+    options: RunOptions,
 }
 
 impl Deployment {
@@ -53,9 +66,20 @@ impl Deployment {
             config,
             history_path,
             parallel_mode,
+            options: RunOptions::default(),
         }
     }
 
+    /// Задает параметры запуска из командной строки
+    ///
+    /// # Параметры
+    ///
+    /// * `options` - Параметры запуска
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     /// Выполняет указанное событие для деплоя
     ///
     /// # Параметры
@@ -73,17 +97,81 @@ impl Deployment {
             &self.config,
             &self.history_path,
             false,
+            &self.options,
         );
 
         // Выполняем указанное событие деплоя
         if !deployment_manager.execute_event(deployment, event).await {
             error!("Ошибка выполнения команд для деплоя '{}'", deployment);
-            exit(1);
+            telemetry::finish().await;
+            exit_with(DeployError::CommandFailure);
         }
 
         info!("Все команды выполнены успешно");
     }
 
+    /// Выполняет событие деплоя несколько раз подряд (`--repeat`)
+    ///
+    /// Каждый запуск записывается в историю как обычно, ошибка запуска не прерывает
+    /// повторы. В конце выводится число успешных и неудачных запусков; программа
+    /// завершается с ошибкой, если хотя бы один запуск не удался.
+    ///
+    /// # Параметры
+    ///
+    /// * `deployment` - Имя деплоя
+    /// * `event` - Имя события
+    pub async fn repeat_event(&self, deployment: &str, event: &str) {
+        let times = self.options.repeat.unwrap_or(1);
+        let deployment_manager = super::deployment_manager::DeploymentManager::new(
+            &self.config,
+            &self.history_path,
+            false,
+            &self.options,
+        );
+
+        let mut failed = 0;
+        for iteration in 1..=times {
+            if iteration > 1 {
+                super::ensure_config_unchanged(&self.options);
+                if !self.options.repeat_delay.is_zero() {
+                    info!(
+                        "Ожидание {:.1} с перед повтором {} из {}",
+                        self.options.repeat_delay.as_secs_f64(),
+                        iteration,
+                        times
+                    );
+                    tokio::time::sleep(self.options.repeat_delay).await;
+                }
+            }
+
+            info!(
+                "Повтор {} из {}: деплой '{}', событие '{}'",
+                iteration, times, deployment, event
+            );
+            if deployment_manager.execute_event(deployment, event).await {
+                output::line(format!("{} {}/{}", output::success("✅"), iteration, times));
+            } else {
+                failed += 1;
+                output::line(format!("{} {}/{}", output::failure("❌"), iteration, times));
+            }
+        }
+
+        let summary = format!(
+            "Событие '{}' деплоя '{}': запусков {}, успешно {}, с ошибкой {}",
+            event,
+            deployment,
+            times,
+            times - failed,
+            failed
+        );
+        if failed > 0 {
+            output::line(output::failure(&summary));
+            telemetry::finish().await;
+            exit_with(DeployError::CommandFailure);
+        }
+        output::line(output::success(&summary));
+    }
+
     /// Запускает все события для указанного деплоя последовательно
     ///
     /// # Параметры
@@ -97,24 +185,32 @@ impl Deployment {
             &self.config,
             &self.history_path,
             false,
+            &self.options,
         );
 
         // Выполняем все события деплоя
         if !deployment_manager.execute_all_events(deployment).await {
             error!("Ошибка выполнения событий для деплоя '{}'", deployment);
-            exit(1);
+            telemetry::finish().await;
+            exit_with(DeployError::CommandFailure);
         }
     }
 
     /// Выводит список всех доступных деплоев и команд
-    pub fn list_deployments(&self) {
+    ///
+    /// # Параметры
+    ///
+    /// * `with_status` - Показать статус последнего запуска из истории
+    /// * `filter` - Подстрока для отбора деплоев, событий и команд
+    pub fn list_deployments(&self, with_status: bool, filter: Option<&str>) {
         // Создаем менеджер деплоев с любыми параметрами, т.к. они не используются при выводе списка
         let deployment_manager = super::deployment_manager::DeploymentManager::new(
             &self.config,
             &self.history_path,
             false,
+            &self.options,
         );
-        deployment_manager.list_deployments();
+        deployment_manager.list_deployments(with_status, filter);
     }
 
     /// Проверяет конфигурацию деплоя на корректность
@@ -128,31 +224,96 @@ impl Deployment {
             Ok(true) => info!("Конфигурация деплоя '{}' корректна", deployment_name),
             Ok(false) => {
                 error!("Конфигурация деплоя '{}' некорректна", deployment_name);
-                exit(1);
+                exit_with(DeployError::Config);
             }
             Err(e) => {
                 error!("Ошибка проверки конфигурации: {}", e);
-                exit(1);
+                exit_with(DeployError::Config);
             }
         }
     }
 
+    /// Проверяет конфигурацию нескольких деплоев и выводит результат для каждого
+    ///
+    /// Завершает программу с ошибкой, если хотя бы один деплой не прошел проверку.
+    ///
+    /// # Параметры
+    ///
+    /// * `deployment_names` - Имена деплоев для проверки
+    pub fn verify_deployment_configs(&self, deployment_names: &[&str]) {
+        let mut failed = 0;
+
+        for deployment_name in deployment_names {
+            match crate::config::verify_deployment(&self.config, deployment_name) {
+                Ok(true) => output::line(format!("{} {}", output::success("✅"), deployment_name)),
+                Ok(false) => {
+                    failed += 1;
+                    output::line(format!("{} {}", output::failure("❌"), deployment_name));
+                }
+                Err(e) => {
+                    failed += 1;
+                    output::line(format!(
+                        "{} {}: {}",
+                        output::failure("❌"),
+                        deployment_name,
+                        e
+                    ));
+                }
+            }
+        }
+
+        if failed > 0 {
+            error!(
+                "Конфигурация некорректна у {} из {} деплоев",
+                failed,
+                deployment_names.len()
+            );
+            exit_with(DeployError::Config);
+        }
+
+        info!(
+            "Конфигурация всех {} деплоев корректна",
+            deployment_names.len()
+        );
+    }
+
     /// Показывает историю деплоев
     ///
     /// # Параметры
     ///
     /// * `deployment_name` - Имя деплоя
+    /// * `event` - Имя события для отбора записей (None - все записи)
     /// * `limit` - Количество записей истории для отображения
-    pub fn show_deployment_history(&self, deployment_name: &str, limit: usize) {
-        match history::display_deployment_history(&self.history_path, deployment_name, limit) {
+    pub fn show_deployment_history(
+        &self,
+        deployment_name: &str,
+        event: Option<&str>,
+        limit: usize,
+    ) {
+        match history::display_deployment_history(&self.history_path, deployment_name, event, limit)
+        {
             Ok(_) => {}
             Err(e) => {
                 error!("Ошибка отображения истории деплоя: {}", e);
-                exit(1);
+                exit_with(DeployError::Config);
             }
         }
     }
 
+    /// Показывает сравнение истории двух деплоев
+    ///
+    /// # Параметры
+    ///
+    /// * `a` - Имя первого деплоя
+    /// * `b` - Имя второго деплоя
+    /// * `limit` - Количество последних записей каждого деплоя для сравнения
+    pub fn show_history_diff(&self, a: &str, b: &str, limit: usize) {
+        if let Err(e) = history::display_history_diff(&self.history_path, a, b, limit) {
+            error!("Ошибка сравнения истории деплоев: {}", e);
+            exit_with(DeployError::Config);
+        }
+    }
+
     /// Очищает историю деплоев
     ///
     /// # Параметры
@@ -163,17 +324,31 @@ impl Deployment {
             Ok(_) => {}
             Err(e) => {
                 error!("Ошибка очистки истории: {}", e);
-                exit(1);
+                exit_with(DeployError::Config);
             }
         }
     }
 
+    /// Удаляет записи истории старше указанного возраста
+    ///
+    /// # Параметры
+    ///
+    /// * `older_than` - Возраст записей для удаления
+    /// * `deployment_name` - Имя деплоя (если None, обрабатываются все деплои)
+    pub fn prune_deployment_history(&self, older_than: Duration, deployment_name: Option<&str>) {
+        if let Err(e) = history::prune_history(&self.history_path, older_than, deployment_name) {
+            error!("Ошибка удаления устаревших записей истории: {}", e);
+            exit_with(DeployError::Config);
+        }
+    }
+
     /// Записывает событие о начале полного деплоя
     fn record_full_deploy_start(&self) {
         if let Err(e) = storage::record_deployment(
             &self.history_path,
-            "all-deployments",
-            "start-full-deploy-all",
+            storage::ALL_DEPLOYMENTS,
+            storage::RecordKind::Start,
+            storage::FULL_DEPLOY_ALL_EVENT,
             true,
             None,
         ) {
@@ -196,7 +371,7 @@ impl Deployment {
         &self,
         deployment_name: &str,
         event_name: &str,
-        failed_deployments: &Arc<Mutex<Vec<String>>>,
+        failed_deployments: &FailedDeployments,
     ) -> bool {
         let params = EventExecutionParams {
             config: &self.config,
@@ -223,13 +398,14 @@ impl Deployment {
     async fn execute_event_with_params(
         &self,
         params: &EventExecutionParams<'_>,
-        failed_deployments: &Arc<Mutex<Vec<String>>>,
+        failed_deployments: &FailedDeployments,
     ) -> bool {
         match command_runner::execute_command(
             params.config,
             params.deployment_name,
             params.event_name,
             params.history_path,
+            &self.options,
         )
         .await
         {
@@ -245,8 +421,11 @@ impl Deployment {
                     "Ошибка выполнения деплоя '{}', событие '{}': {}",
                     params.deployment_name, params.event_name, e
                 );
-                let mut failed = failed_deployments.lock().unwrap();
-                failed.push(params.deployment_name.to_string());
+                let mut failed = failed_deployments.lock().unwrap_or_else(|e| e.into_inner());
+                failed.push((
+                    params.deployment_name.to_string(),
+                    event_failure(params.event_name, &e.to_string()),
+                ));
                 false
             }
         }
@@ -257,12 +436,12 @@ impl Deployment {
     /// # Параметры
     ///
     /// * `all_success` - Флаг успешности всех деплоев
-    /// * `failed_list` - Список неудачных деплоев
+    /// * `failed_list` - Список неудачных деплоев с описанием ошибок
     /// * `is_parallel` - Режим выполнения (параллельный или последовательный)
-    fn record_deploy_all_result(
+    async fn record_deploy_all_result(
         &self,
         all_success: bool,
-        failed_list: &[String],
+        failed_list: &[(String, String)],
         is_parallel: bool,
     ) {
         let mode_str = if is_parallel {
@@ -270,14 +449,19 @@ impl Deployment {
         } else {
             "последовательный"
         };
+        let failed_names: Vec<&str> = failed_list.iter().map(|(name, _)| name.as_str()).collect();
+        let summary = storage::AllDeploymentsSummary::new(
+            self.config.deployments.len(),
+            failed_names.iter().map(|name| name.to_string()).collect(),
+        );
 
         if all_success {
             info!("Все деплои успешно выполнены ({} режим)", mode_str);
             if let Err(e) = command_runner::record_success(
                 &self.history_path,
-                "all-deployments",
-                "complete-full-deploy-all",
-                Some("Все деплои успешно завершены".to_string()),
+                storage::ALL_DEPLOYMENTS,
+                storage::FULL_DEPLOY_ALL_EVENT,
+                Some(summary.to_details()),
             ) {
                 warn!("Ошибка записи события: {}", e);
             }
@@ -285,17 +469,19 @@ impl Deployment {
             error!(
                 "Некоторые деплои завершились с ошибками ({} режим): {}",
                 mode_str,
-                failed_list.join(", ")
+                failed_names.join(", ")
             );
             if let Err(e) = command_runner::record_failure(
                 &self.history_path,
-                "all-deployments",
-                "failed-full-deploy-all",
-                format!("Деплои с ошибками: {}", failed_list.join(", ")),
+                storage::ALL_DEPLOYMENTS,
+                storage::FULL_DEPLOY_ALL_EVENT,
+                summary.to_details(),
             ) {
                 warn!("Ошибка записи события: {}", e);
             }
-            exit(1);
+            show_failure_report(failed_list);
+            telemetry::finish().await;
+            exit_with(DeployError::CommandFailure);
         }
     }
 
@@ -312,7 +498,8 @@ impl Deployment {
         match storage::record_deployment(
             &self.history_path,
             deployment_name,
-            "start-full-deploy",
+            storage::RecordKind::Start,
+            storage::FULL_DEPLOY_EVENT,
             true,
             None,
         ) {
@@ -331,39 +518,29 @@ impl Deployment {
     /// * `deployment_name` - Имя деплоя
     /// * `event_name` - Имя события
     /// * `error` - Описание ошибки
-    ///
-    /// # Возвращаемое значение
-    ///
-    /// Результат записи ошибки
-    fn handle_event_error(&self, deployment_name: &str, event_name: &str, error: String) -> bool {
+    fn handle_event_error(&self, deployment_name: &str, event_name: &str, error: String) {
         error!("Ошибка выполнения события '{}': {}", event_name, error);
-        if let Err(log_err) = command_runner::record_failure(
-            &self.history_path,
-            deployment_name,
-            &format!("failed-{}", event_name),
-            error,
-        ) {
+        if let Err(log_err) =
+            command_runner::record_failure(&self.history_path, deployment_name, event_name, error)
+        {
             warn!("Ошибка записи события: {}", log_err);
         }
-        false
     }
 
     /// Записывает результат успешного выполнения всех событий деплоя
     ///
     /// # Параметры
     ///
-    /// * `deployment_name` - Имя деплоя
-    fn record_deployment_success(&self, deployment_name: &str) {
+    /// * `deployment` - Конфигурация деплоя
+    async fn record_deployment_success(&self, deployment: &crate::config::Deployment) {
         info!(
             "Все события для деплоя '{}' успешно выполнены",
-            deployment_name
+            deployment.name
         );
-        if let Err(e) = command_runner::record_success(
-            &self.history_path,
-            deployment_name,
-            "complete-full-deploy",
-            Some("Все события деплоя успешно завершены".to_string()),
-        ) {
+        if let Err(e) =
+            command_runner::record_deploy_success(&self.history_path, deployment, &self.options)
+                .await
+        {
             warn!("Ошибка записи события: {}", e);
         }
     }
@@ -373,25 +550,27 @@ impl Deployment {
     /// # Параметры
     ///
     /// * `deployment_name` - Имя деплоя
+    /// * `failure` - Описание ошибки (событие и текст ошибки)
     /// * `failed_deployments` - Список неудачных деплоев
     fn record_deployment_failure(
         &self,
         deployment_name: &str,
-        failed_deployments: &Arc<Mutex<Vec<String>>>,
+        failure: String,
+        failed_deployments: &FailedDeployments,
     ) {
         error!("Деплой '{}' завершился с ошибками", deployment_name);
         if let Err(e) = command_runner::record_failure(
             &self.history_path,
             deployment_name,
-            "failed-full-deploy",
+            storage::FULL_DEPLOY_EVENT,
             "Одно из событий завершилось с ошибкой".to_string(),
         ) {
             warn!("Ошибка записи события: {}", e);
         }
 
-        // Добавляем имя деплоя в список неудачных
-        let mut failed = failed_deployments.lock().unwrap();
-        failed.push(deployment_name.to_string());
+        // Добавляем деплой в список неудачных вместе с описанием ошибки
+        let mut failed = failed_deployments.lock().unwrap_or_else(|e| e.into_inner());
+        failed.push((deployment_name.to_string(), failure));
     }
 
     /// Выполняет события для деплоя
@@ -404,16 +583,15 @@ impl Deployment {
     ///
     /// # Возвращаемое значение
     ///
-    /// Успешность выполнения всех событий
+    /// Ok при успешном выполнении всех событий или описание ошибки первого
+    /// неудачного события
     #[allow(unused_variables)]
     async fn execute_events_sequence(
         &self,
         deployment_name: &str,
-        events: &[crate::config::Event],
-        failed_deployments: &Arc<Mutex<Vec<String>>>,
-    ) -> bool {
-        let mut deployment_success = true;
-
+        events: &[&crate::config::Event],
+        failed_deployments: &FailedDeployments,
+    ) -> std::result::Result<(), String> {
         // Выполняем все события последовательно
         for event in events {
             info!(
@@ -434,6 +612,7 @@ impl Deployment {
                 params.deployment_name,
                 params.event_name,
                 params.history_path,
+                &self.options,
             )
             .await
             {
@@ -441,14 +620,13 @@ impl Deployment {
                     info!("Событие '{}' успешно выполнено", event.name);
                 }
                 Err(e) => {
-                    deployment_success =
-                        self.handle_event_error(deployment_name, &event.name, e.to_string());
-                    break;
+                    self.handle_event_error(deployment_name, &event.name, e.to_string());
+                    return Err(event_failure(&event.name, &e.to_string()));
                 }
             }
         }
 
-        deployment_success
+        Ok(())
     }
 
     /// Выполняет все события для указанного деплоя последовательно
@@ -464,7 +642,7 @@ impl Deployment {
     async fn execute_all_events_for_deployment(
         &self,
         deployment_name: &str,
-        failed_deployments: &Arc<Mutex<Vec<String>>>,
+        failed_deployments: &FailedDeployments,
     ) -> bool {
         let params = DeploymentExecutionParams {
             config: &self.config,
@@ -492,34 +670,59 @@ impl Deployment {
                 // Запись события начала деплоя
                 self.record_deployment_start(params.deployment_name);
 
+                // Снимок окружения снимается до первого события
+                if !command_runner::snapshot_deployment(dep_config, &self.options).await {
+                    self.record_deployment_failure(
+                        params.deployment_name,
+                        "не удалось снять снимок окружения".to_string(),
+                        params.failed_deployments,
+                    );
+                    return false;
+                }
+
                 // Выполняем все события последовательно
-                let deployment_success = self
+                let events = history::select_events(
+                    &self.history_path,
+                    dep_config,
+                    self.options.since_last_success,
+                );
+                let result = self
                     .execute_events_sequence(
                         params.deployment_name,
-                        &dep_config.events,
+                        &events,
                         params.failed_deployments,
                     )
                     .await;
+                if result.is_err() {
+                    command_runner::restore_deployment(dep_config, &self.options).await;
+                }
 
                 // Запись результата деплоя
-                if deployment_success {
-                    self.record_deployment_success(params.deployment_name);
-                } else {
-                    self.record_deployment_failure(
-                        params.deployment_name,
-                        params.failed_deployments,
-                    );
+                match result {
+                    Ok(()) => {
+                        self.record_deployment_success(dep_config).await;
+                        true
+                    }
+                    Err(failure) => {
+                        self.record_deployment_failure(
+                            params.deployment_name,
+                            failure,
+                            params.failed_deployments,
+                        );
+                        false
+                    }
                 }
-
-                deployment_success
             }
             Err(e) => {
                 error!(
                     "Ошибка получения конфигурации деплоя '{}': {}",
                     params.deployment_name, e
                 );
-                let mut failed = params.failed_deployments.lock().unwrap();
-                failed.push(params.deployment_name.to_string());
+                let mut failed = params
+                    .failed_deployments
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                failed.push((params.deployment_name.to_string(), e.to_string()));
                 false
             }
         }
@@ -536,14 +739,19 @@ impl Deployment {
     /// true если все деплои выполнены успешно, false если были ошибки
     async fn run_deployments_sequentially(&self, params: &AllDeploymentsParams<'_>) -> bool {
         let mut all_success = true;
+        let total = self.config.deployments.len();
 
-        for deployment in &self.config.deployments {
+        for (index, deployment) in self.config.deployments.iter().enumerate() {
             let deployment_name = &deployment.name;
             info!(
-                "Запуск деплоя '{}' (последовательный режим)",
-                deployment_name
+                "{} (последовательный режим)",
+                progress_message(index + 1, total, deployment_name)
             );
 
+            if index > 0 {
+                super::ensure_config_unchanged(&self.options);
+            }
+
             // Обрабатываем деплой в зависимости от наличия события
             let success = if let Some(event_name) = params.event {
                 // Запускаем конкретное событие
@@ -578,7 +786,7 @@ impl Deployment {
         &self,
         deployment_name: String,
         event: Option<String>,
-        failed_deployments: Arc<Mutex<Vec<String>>>,
+        failed_deployments: FailedDeployments,
     ) -> (String, bool) {
         info!("Запуск деплоя '{}' (параллельный режим)", deployment_name);
 
@@ -622,44 +830,59 @@ impl Deployment {
     ///
     /// true если все деплои выполнены успешно, false если были ошибки
     async fn run_deployments_in_parallel(&self, params: &AllDeploymentsParams<'_>) -> bool {
-        use tokio::task::JoinSet;
-
         // Создаем набор задач для параллельного выполнения
         info!("Запуск деплоев в параллельном режиме");
         let mut tasks = JoinSet::new();
+        let mut task_names = HashMap::new();
+
+        // Общий счетчик запущенных деплоев для отображения прогресса
+        let total = self.config.deployments.len();
+        let started = Arc::new(AtomicUsize::new(0));
 
         // Добавляем все деплои в JoinSet для параллельного выполнения
         for deployment in &self.config.deployments {
             let deployment_name = deployment.name.clone();
             let event_clone = params.event.map(|e| e.to_string());
             let failed_deployments_clone = Arc::clone(&params.failed_deployments);
+            let started_clone = Arc::clone(&started);
+            let buffer_output = self.options.buffer_output;
 
             // Клонируем self для передачи в задачу
             let deployment_self = self.clone();
 
-            // Запускаем отдельную задачу для каждого деплоя
-            tasks.spawn(async move {
-                deployment_self
-                    .run_deployment_task(deployment_name, event_clone, failed_deployments_clone)
-                    .await
-            });
-        }
+            // Запускаем отдельную задачу для каждого деплоя и запоминаем ее
+            // идентификатор, чтобы приписать панику задачи нужному деплою
+            let name = deployment_name.clone();
+            let handle = tasks.spawn(async move {
+                let current = started_clone.fetch_add(1, Ordering::SeqCst) + 1;
+                info!("{}", progress_message(current, total, &deployment_name));
+
+                let task = deployment_self.run_deployment_task(
+                    deployment_name.clone(),
+                    event_clone,
+                    failed_deployments_clone,
+                );
 
-        // Дожидаемся завершения всех задач
-        let mut all_success = true;
-        while let Some(result) = tasks.join_next().await {
-            if let Ok((name, success)) = result {
-                if !success {
-                    all_success = false;
-                    info!("Деплой '{}' завершился с ошибками", name);
+                // При буферизации вывод деплоя выводится одним блоком после его завершения
+                if buffer_output {
+                    let result = logging::with_output_buffer(deployment_name.clone(), task).await;
+                    logging::flush_output_buffer(&deployment_name);
+                    result
+                } else {
+                    task.await
                 }
-            } else {
-                error!("Ошибка выполнения задачи деплоя");
-                all_success = false;
-            }
+            });
+            task_names.insert(handle.id(), name);
         }
 
-        all_success
+        join_deployment_tasks(
+            tasks,
+            &task_names,
+            &params.failed_deployments,
+            total,
+            self.options.buffer_output,
+        )
+        .await
     }
 
     /// Запускает все доступные деплои в конфигурации
@@ -708,11 +931,205 @@ impl Deployment {
             self.run_deployments_sequentially(&deploy_params).await
         };
 
-        // Получаем список неудачных деплоев
-        let failed_list = failed_deployments.lock().unwrap().clone();
+        // Получаем список неудачных деплоев (мьютекс мог быть отравлен паникой в задаче)
+        let failed_list = failed_deployments
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
 
         // Запись итогового результата
-        self.record_deploy_all_result(all_success, &failed_list, parallel);
+        self.record_deploy_all_result(all_success, &failed_list, parallel)
+            .await;
+    }
+}
+
+/// Формирует строку прогресса запуска деплоев
+///
+/// # Параметры
+///
+/// * `current` - Порядковый номер запускаемого деплоя (начиная с 1)
+/// * `total` - Общее количество деплоев
+/// * `deployment_name` - Имя деплоя
+///
+/// # Возвращаемое значение
+///
+/// Строка вида `[3/12] Запуск деплоя 'api'`
+fn progress_message(current: usize, total: usize, deployment_name: &str) -> String {
+    format!(
+        "[{}/{}] Запуск деплоя '{}'",
+        current, total, deployment_name
+    )
+}
+
+/// Формирует строку о завершении деплоя при параллельном запуске
+///
+/// # Параметры
+///
+/// * `deployment_name` - Имя деплоя
+/// * `success` - Успешно ли завершился деплой
+/// * `done` - Количество завершившихся деплоев
+/// * `total` - Общее количество деплоев
+/// * `failed` - Количество деплоев, завершившихся с ошибкой
+///
+/// # Возвращаемое значение
+///
+/// Строка вида `✅ api (завершено 4/12, с ошибками: 1)`
+fn completion_message(
+    deployment_name: &str,
+    success: bool,
+    done: usize,
+    total: usize,
+    failed: usize,
+) -> String {
+    let status = if success {
+        output::success("✅")
+    } else {
+        output::failure("❌")
+    };
+
+    format!(
+        "{} {} {}",
+        status,
+        deployment_name,
+        output::muted(&format!(
+            "(завершено {}/{}, с ошибками: {})",
+            done, total, failed
+        ))
+    )
+}
+
+/// Формирует описание ошибки события для итогового отчета
+///
+/// # Параметры
+///
+/// * `event_name` - Имя события
+/// * `error` - Текст ошибки
+///
+/// # Возвращаемое значение
+///
+/// Строка вида `событие 'deploy': <первая строка ошибки>`
+fn event_failure(event_name: &str, error: &str) -> String {
+    format!(
+        "событие '{}': {}",
+        event_name,
+        error.lines().next().unwrap_or_default()
+    )
+}
+
+/// Выводит итоговый отчет о деплоях, завершившихся с ошибкой
+///
+/// # Параметры
+///
+/// * `failed_list` - Имена неудачных деплоев с описанием ошибок
+fn show_failure_report(failed_list: &[(String, String)]) {
+    output::blank();
+    output::line(output::failure(&format!(
+        "Деплои, завершившиеся с ошибкой ({}):",
+        failed_list.len()
+    )));
+    for (name, failure) in failed_list {
+        output::line(format!("  - {}: {}", output::heading(name), failure));
+    }
+}
+
+/// Дожидается завершения задач параллельного запуска, выводя результат каждой по мере завершения
+///
+/// Паника задачи приписывается деплою по идентификатору задачи и добавляется
+/// в список неудачных деплоев.
+///
+/// # Параметры
+///
+/// * `tasks` - Набор задач, каждая возвращает имя деплоя и результат выполнения
+/// * `task_names` - Имена деплоев по идентификаторам задач
+/// * `failed_deployments` - Общий список неудачных деплоев
+/// * `total` - Общее число деплоев
+/// * `buffer_output` - Флаг буферизации вывода деплоев
+///
+/// # Возвращаемое значение
+///
+/// true если все деплои выполнены успешно, false если были ошибки
+async fn join_deployment_tasks(
+    mut tasks: JoinSet<(String, bool)>,
+    task_names: &HashMap<Id, String>,
+    failed_deployments: &FailedDeployments,
+    total: usize,
+    buffer_output: bool,
+) -> bool {
+    let mut all_success = true;
+    let mut done = 0;
+    let mut failed_count = 0;
+    while let Some(result) = tasks.join_next_with_id().await {
+        done += 1;
+        let (name, success) = match result {
+            Ok((_, (name, success))) => {
+                if !success {
+                    info!("Деплой '{}' завершился с ошибками", name);
+                }
+                (name, success)
+            }
+            Err(e) => {
+                let name = task_names
+                    .get(&e.id())
+                    .cloned()
+                    .unwrap_or_else(|| "<неизвестный деплой>".to_string());
+                let message = if e.is_panic() {
+                    format!(
+                        "аварийное завершение: {}",
+                        panic_message(e.into_panic().as_ref())
+                    )
+                } else {
+                    format!("задача отменена: {}", e)
+                };
+                error!(
+                    "Задача деплоя '{}' завершилась с ошибкой: {}",
+                    name, message
+                );
+
+                // Буфер вывода паникующей задачи не был выведен внутри нее
+                if buffer_output {
+                    logging::flush_output_buffer(&name);
+                }
+
+                let mut failed = failed_deployments.lock().unwrap_or_else(|e| e.into_inner());
+                if !failed.iter().any(|(failed_name, _)| failed_name == &name) {
+                    failed.push((name.clone(), message));
+                }
+                (name, false)
+            }
+        };
+
+        if !success {
+            all_success = false;
+            failed_count += 1;
+        }
+        output::line(completion_message(
+            &name,
+            success,
+            done,
+            total,
+            failed_count,
+        ));
+    }
+
+    all_success
+}
+
+/// Извлекает текст сообщения из значения паники
+///
+/// # Параметры
+///
+/// * `panic` - Значение, переданное в `panic!`
+///
+/// # Возвращаемое значение
+///
+/// Текст паники или заглушка, если значение не является строкой
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "<неизвестная паника>".to_string()
     }
 }
 
@@ -722,6 +1139,7 @@ impl Clone for Deployment {
             config: self.config.clone(),
             history_path: self.history_path.clone(),
             parallel_mode: self.parallel_mode,
+            options: self.options.clone(),
         }
     }
 }
@@ -738,7 +1156,90 @@ pub fn create_deployment_template(deployment_name: &str, config_path: &str) {
         Ok(_) => info!("Шаблон деплоя '{}' успешно создан", deployment_name),
         Err(e) => {
             error!("Ошибка создания шаблона: {}", e);
-            exit(1);
+            exit_with(DeployError::Config);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn task_panic_is_attributed_to_deployment() {
+        let mut tasks = JoinSet::new();
+        let mut task_names = HashMap::new();
+
+        let handle = tasks.spawn(async { ("ok".to_string(), true) });
+        task_names.insert(handle.id(), "ok".to_string());
+        let handle = tasks.spawn(async {
+            if true {
+                panic!("сбой задачи");
+            }
+            ("broken".to_string(), true)
+        });
+        task_names.insert(handle.id(), "broken".to_string());
+
+        let failed_deployments: FailedDeployments = Arc::new(Mutex::new(Vec::new()));
+        let all_success =
+            join_deployment_tasks(tasks, &task_names, &failed_deployments, 2, false).await;
+
+        assert!(!all_success);
+        let failed = failed_deployments.lock().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "broken");
+        assert!(failed[0].1.contains("сбой задачи"));
+    }
+
+    #[tokio::test]
+    async fn successful_tasks_leave_no_failures() {
+        let mut tasks = JoinSet::new();
+        let mut task_names = HashMap::new();
+        for name in ["first", "second"] {
+            let handle = tasks.spawn(async move { (name.to_string(), true) });
+            task_names.insert(handle.id(), name.to_string());
+        }
+
+        let failed_deployments: FailedDeployments = Arc::new(Mutex::new(Vec::new()));
+        assert!(join_deployment_tasks(tasks, &task_names, &failed_deployments, 2, false).await);
+        assert!(failed_deployments.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn progress_messages_for_three_deployments() {
+        let messages: Vec<String> = ["web", "api", "worker"]
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| progress_message(idx + 1, 3, name))
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "[1/3] Запуск деплоя 'web'",
+                "[2/3] Запуск деплоя 'api'",
+                "[3/3] Запуск деплоя 'worker'",
+            ]
+        );
+    }
+
+    #[test]
+    fn completion_message_shows_running_totals() {
+        let succeeded = completion_message("api", true, 4, 12, 1);
+        assert!(succeeded.contains("✅"));
+        assert!(succeeded.contains(" api "));
+        assert!(succeeded.contains("(завершено 4/12, с ошибками: 1)"));
+
+        let failed = completion_message("web", false, 5, 12, 2);
+        assert!(failed.contains("❌"));
+        assert!(failed.contains("(завершено 5/12, с ошибками: 2)"));
+    }
+
+    #[test]
+    fn event_failure_keeps_first_error_line() {
+        assert_eq!(
+            event_failure("migrate", "Деплой завершился с ошибками: boom\nподробности"),
+            "событие 'migrate': Деплой завершился с ошибками: boom"
+        );
+    }
+}
@@ -13,8 +13,12 @@ use anyhow::{Context, Result};
 use log::{error, info, warn};
 use std::sync::{Arc, Mutex};
 
-use crate::config::{Config, Deployment};
+use crate::commands;
+use crate::config::{Command, Config, Deployment, Event};
+use crate::executor::RunOptions;
+use crate::output;
 use crate::run::command_runner;
+use crate::run::history;
 use crate::storage;
 
 /// Структура для управления деплоем
@@ -25,6 +29,9 @@ pub struct DeploymentManager<'a> {
     /// Путь к файлу истории деплоев
     history_path: &'a str,
 
+    /// Параметры запуска из командной строки
+    options: &'a RunOptions,
+
     /// Список неудачных деплоев
     failed_deployments: Arc<Mutex<Vec<String>>>,
 }
@@ -37,10 +44,17 @@ impl<'a> DeploymentManager<'a> {
     /// * `config` - Конфигурация деплоя
     /// * `history_path` - Путь к файлу истории деплоев
     /// * `parallel_execution` - Флаг параллельного выполнения (не используется, оставлен для обратной совместимости)
-    pub fn new(config: &'a Config, history_path: &'a str, _parallel_execution: bool) -> Self {
+    /// * `options` - Параметры запуска из командной строки
+    pub fn new(
+        config: &'a Config,
+        history_path: &'a str,
+        _parallel_execution: bool,
+        options: &'a RunOptions,
+    ) -> Self {
         Self {
             config,
             history_path,
+            options,
             failed_deployments: Arc::new(Mutex::new(Vec::new())),
         }
     }
@@ -57,7 +71,7 @@ impl<'a> DeploymentManager<'a> {
     pub fn get_deployment(&self, deployment_name: &str) -> Result<&Deployment> {
         self.config
             .find_deployment(deployment_name)
-            .with_context(|| format!("Деплой с именем '{}' не найден", deployment_name))
+            .with_context(|| self.config.deployment_not_found_message(deployment_name))
     }
 
     /// Записывает событие о начале деплоя
@@ -65,27 +79,21 @@ impl<'a> DeploymentManager<'a> {
     /// # Параметры
     ///
     /// * `deployment_name` - Имя деплоя
-    /// * `event_type` - Тип события
-    fn record_start(&self, deployment_name: &str, event_type: &str) {
-        if let Err(e) =
-            storage::record_deployment(self.history_path, deployment_name, event_type, true, None)
-        {
-            warn!("Ошибка записи события: {}", e);
-        }
-    }
+    /// * `event_name` - Имя события
+    async fn record_start(&self, deployment_name: &str, event_name: &str) {
+        let working_dir = self
+            .config
+            .find_deployment(deployment_name)
+            .and_then(|d| d.working_dir.as_deref());
+        let git_sha = commands::git_commit_sha(working_dir).await;
 
-    /// Записывает успешное завершение деплоя
-    ///
-    /// # Параметры
-    ///
-    /// * `deployment_name` - Имя деплоя
-    /// * `event_type` - Тип события
-    /// * `message` - Опциональное сообщение
-    fn record_success(&self, deployment_name: &str, event_type: &str, message: Option<String>) {
-        if let Err(e) =
-            command_runner::record_success(self.history_path, deployment_name, event_type, message)
-        {
-            warn!("Ошибка записи успешного события: {}", e);
+        if let Err(e) = storage::record_deployment_start(
+            self.history_path,
+            deployment_name,
+            event_name,
+            git_sha,
+        ) {
+            warn!("Ошибка записи события: {}", e);
         }
     }
 
@@ -94,13 +102,13 @@ impl<'a> DeploymentManager<'a> {
     /// # Параметры
     ///
     /// * `deployment_name` - Имя деплоя
-    /// * `event_type` - Тип события
+    /// * `event_name` - Имя события
     /// * `error_message` - Сообщение об ошибке
-    fn record_failure(&self, deployment_name: &str, event_type: &str, error_message: String) {
+    fn record_failure(&self, deployment_name: &str, event_name: &str, error_message: String) {
         if let Err(e) = command_runner::record_failure(
             self.history_path,
             deployment_name,
-            event_type,
+            event_name,
             error_message,
         ) {
             warn!("Ошибка записи события сбоя: {}", e);
@@ -128,6 +136,7 @@ impl<'a> DeploymentManager<'a> {
             deployment_name,
             event_name,
             self.history_path,
+            self.options,
         )
         .await
         {
@@ -145,14 +154,13 @@ impl<'a> DeploymentManager<'a> {
                 );
 
                 // Запись ошибки
-                self.record_failure(
-                    deployment_name,
-                    &format!("failed-{}", event_name),
-                    e.to_string(),
-                );
+                self.record_failure(deployment_name, event_name, e.to_string());
 
                 // Добавляем в список неудачных деплоев
-                let mut failed = self.failed_deployments.lock().unwrap();
+                let mut failed = self
+                    .failed_deployments
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
                 failed.push(deployment_name.to_string());
 
                 false
@@ -179,19 +187,30 @@ impl<'a> DeploymentManager<'a> {
                     "Ошибка получения конфигурации деплоя '{}': {}",
                     deployment_name, e
                 );
-                let mut failed = self.failed_deployments.lock().unwrap();
+                let mut failed = self
+                    .failed_deployments
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
                 failed.push(deployment_name.to_string());
                 return false;
             }
         };
 
         // Запись события начала деплоя
-        self.record_start(deployment_name, "start-full-deploy");
+        self.record_start(deployment_name, storage::FULL_DEPLOY_EVENT)
+            .await;
 
-        let mut success = true;
+        // Снимок окружения снимается до первого события
+        let snapshot_taken = command_runner::snapshot_deployment(deployment, self.options).await;
+        let mut success = snapshot_taken;
 
         // Выполняем все события последовательно
-        for event in &deployment.events {
+        let events = history::select_events(
+            self.history_path,
+            deployment,
+            self.options.since_last_success,
+        );
+        for event in events {
             // Если предыдущее событие не удалось, прерываем выполнение
             if !success {
                 break;
@@ -201,22 +220,27 @@ impl<'a> DeploymentManager<'a> {
             success = self.execute_event(deployment_name, &event.name).await;
         }
 
+        if snapshot_taken && !success {
+            command_runner::restore_deployment(deployment, self.options).await;
+        }
+
         // Запись итогового результата
         if success {
             info!(
                 "Все события для деплоя '{}' успешно выполнены",
                 deployment_name
             );
-            self.record_success(
-                deployment_name,
-                "complete-full-deploy",
-                Some("Все события деплоя успешно завершены".to_string()),
-            );
+            if let Err(e) =
+                command_runner::record_deploy_success(self.history_path, deployment, self.options)
+                    .await
+            {
+                warn!("{}", e);
+            }
         } else {
             error!("Деплой '{}' завершился с ошибками", deployment_name);
             self.record_failure(
                 deployment_name,
-                "failed-full-deploy",
+                storage::FULL_DEPLOY_EVENT,
                 "Одно из событий завершилось с ошибкой".to_string(),
             );
         }
@@ -225,19 +249,177 @@ impl<'a> DeploymentManager<'a> {
     }
 
     /// Отображает список всех деплоев с их событиями и командами
-    pub fn list_deployments(&self) {
+    ///
+    /// # Параметры
+    ///
+    /// * `with_status` - Показать статус последнего запуска из истории
+    /// * `filter` - Подстрока для отбора деплоев, событий и команд (без учета регистра)
+    pub fn list_deployments(&self, with_status: bool, filter: Option<&str>) {
         info!("Список доступных деплоев:");
+
+        // История загружается только при запросе статуса
+        let deployment_history = if with_status {
+            match history::load_history(self.history_path) {
+                Ok(deployment_history) => Some(deployment_history),
+                Err(e) => {
+                    warn!("{:#}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let filter = filter.map(str::to_lowercase);
+        let mut shown = 0;
         for deployment in &self.config.deployments {
-            println!("Деплой: {}", deployment.name);
-            println!("  События:");
-            for event in &deployment.events {
-                println!("    {}", event.name);
-                println!("      Команды:");
-                for command in &event.commands {
-                    println!("        - {}", command.command);
+            let Some(events) = filter_deployment_events(deployment, filter.as_deref()) else {
+                continue;
+            };
+            shown += 1;
+
+            let status = deployment_history
+                .as_ref()
+                .map(|h| history::last_status_suffix(h, &deployment.name))
+                .unwrap_or_default();
+            output::line(format!(
+                "Деплой: {}{}",
+                output::heading(&deployment.name),
+                status
+            ));
+            output::line("  События:");
+            for (event, commands) in events {
+                output::line(format!("    {}", output::heading(&event.name)));
+                output::line("      Команды:");
+                for command in commands {
+                    output::line(format!("        - {}", command.command));
                 }
             }
-            println!();
+            output::blank();
+        }
+
+        if let Some(filter) = filter.filter(|_| shown == 0) {
+            output::line(format!(
+                "Деплои, события и команды, содержащие '{}', не найдены",
+                filter
+            ));
         }
     }
 }
+
+/// Отбирает события и команды деплоя для вывода в списке
+///
+/// При совпадении имени деплоя или события выводятся все его события или команды,
+/// иначе - только команды, имя или текст которых содержит подстроку.
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+/// * `filter` - Подстрока в нижнем регистре (None - без отбора)
+///
+/// # Возвращаемое значение
+///
+/// События с командами для вывода или None, если в деплое нет совпадений
+fn filter_deployment_events<'a>(
+    deployment: &'a Deployment,
+    filter: Option<&str>,
+) -> Option<Vec<(&'a Event, Vec<&'a Command>)>> {
+    let matches = |text: &str| filter.is_none_or(|filter| text.to_lowercase().contains(filter));
+    let deployment_matches = matches(&deployment.name);
+
+    let events: Vec<(&Event, Vec<&Command>)> = deployment
+        .ordered_events()
+        .into_iter()
+        .filter_map(|event| {
+            if deployment_matches || matches(&event.name) {
+                return Some((event, event.commands.iter().collect()));
+            }
+            let commands: Vec<&Command> = event
+                .commands
+                .iter()
+                .filter(|command| {
+                    matches(&command.command) || command.name.as_deref().is_some_and(matches)
+                })
+                .collect();
+            (!commands.is_empty()).then_some((event, commands))
+        })
+        .collect();
+
+    (deployment_matches || !events.is_empty()).then_some(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment() -> Deployment {
+        serde_yaml::from_str(
+            r#"
+name: api
+events:
+  - name: build
+    commands:
+      - command: "cargo build --release"
+      - command: "docker build ."
+        name: image
+  - name: deploy
+    commands:
+      - command: "kubectl apply -f k8s/"
+"#,
+        )
+        .unwrap()
+    }
+
+    /// Имена событий и тексты команд отобранных событий
+    fn shown(deployment: &Deployment, filter: Option<&str>) -> Option<Vec<(String, Vec<String>)>> {
+        filter_deployment_events(deployment, filter).map(|events| {
+            events
+                .into_iter()
+                .map(|(event, commands)| {
+                    (
+                        event.name.clone(),
+                        commands.iter().map(|c| c.command.clone()).collect(),
+                    )
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn filter_matches_command_text_and_name() {
+        let deployment = deployment();
+
+        assert_eq!(
+            shown(&deployment, Some("kubectl")),
+            Some(vec![(
+                "deploy".to_string(),
+                vec!["kubectl apply -f k8s/".to_string()]
+            )])
+        );
+        assert_eq!(
+            shown(&deployment, Some("image")),
+            Some(vec![(
+                "build".to_string(),
+                vec!["docker build .".to_string()]
+            )])
+        );
+    }
+
+    #[test]
+    fn filter_on_deployment_or_event_name_keeps_all_commands() {
+        let deployment = deployment();
+
+        let by_event = shown(&deployment, Some("build")).unwrap();
+        assert_eq!(by_event.len(), 1);
+        assert_eq!(by_event[0].1.len(), 2);
+
+        let by_deployment = shown(&deployment, Some("api")).unwrap();
+        assert_eq!(by_deployment.len(), 2);
+        assert_eq!(shown(&deployment, None), by_deployment.into());
+    }
+
+    #[test]
+    fn filter_without_matches_skips_deployment() {
+        assert_eq!(shown(&deployment(), Some("terraform")), None);
+    }
+}
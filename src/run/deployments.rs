@@ -1,8 +1,10 @@
 use anyhow::{Context, Result};
 use log::error;
+use std::collections::HashSet;
 
 use crate::config::Config;
 use crate::config::Deployment;
+use crate::executor;
 
 /// Модуль с шаблонами для создания деплоев
 pub mod templates {
@@ -46,6 +48,20 @@ pub mod templates {
             working_dir: Some(DEFAULT_WORKING_DIR.to_string()),
             environment: Some(DEFAULT_ENV_VARS.iter().map(|&s| s.to_string()).collect()),
             variables_file: None,
+            variables: None,
+            quote_variables: None,
+            on_missing_var: None,
+            exclude_from_all: None,
+            tags: None,
+            retry_policy: None,
+            maintenance_window: None,
+            min_interval_secs: None,
+            env_files: None,
+            snapshot_command: None,
+            restore_command: None,
+            version_command: None,
+            require_clean_git: None,
+            event_order: None,
             events: vec![
                 create_pre_deploy_event(),
                 create_deploy_event(),
@@ -60,6 +76,7 @@ pub mod templates {
             name: PRE_DEPLOY_NAME.to_string(),
             description: Some(PRE_DEPLOY_DESC.to_string()),
             commands: vec![config::Command {
+                name: None,
                 command: PRE_DEPLOY_CMD.to_string(),
                 description: Some(PRE_DEPLOY_CMD_DESC.to_string()),
                 ignore_errors: Some(true),
@@ -67,6 +84,17 @@ pub mod templates {
                 interactive: Some(false),
                 inputs: None,
                 variables_file: None,
+                variables: None,
+                stdin: None,
+                stdin_file: None,
+                working_dir: None,
+                retry_policy: None,
+                trace: None,
+                quiet: None,
+                group: None,
+                allow_exit_codes: None,
+                assert_output_contains: None,
+                assert_output_matches: None,
             }],
             fail_fast: Some(true),
         }
@@ -79,15 +107,30 @@ pub mod templates {
             description: Some(DEPLOY_DESC.to_string()),
             commands: vec![
                 config::Command {
+                    name: None,
                     command: DEPLOY_GIT_CMD.to_string(),
                     description: Some(DEPLOY_GIT_DESC.to_string()),
                     ignore_errors: None,
-                    rollback_command: Some(DEPLOY_GIT_ROLLBACK.to_string()),
+                    rollback_command: Some(config::RollbackCommand::Single(
+                        DEPLOY_GIT_ROLLBACK.to_string(),
+                    )),
                     interactive: Some(false),
                     inputs: None,
                     variables_file: None,
+                    variables: None,
+                    stdin: None,
+                    stdin_file: None,
+                    working_dir: None,
+                    retry_policy: None,
+                    trace: None,
+                    quiet: None,
+                    group: None,
+                    allow_exit_codes: None,
+                    assert_output_contains: None,
+                    assert_output_matches: None,
                 },
                 config::Command {
+                    name: None,
                     command: DEPLOY_DEPS_CMD.to_string(),
                     description: Some(DEPLOY_DEPS_DESC.to_string()),
                     ignore_errors: None,
@@ -95,8 +138,20 @@ pub mod templates {
                     interactive: Some(false),
                     inputs: None,
                     variables_file: None,
+                    variables: None,
+                    stdin: None,
+                    stdin_file: None,
+                    working_dir: None,
+                    retry_policy: None,
+                    trace: None,
+                    quiet: None,
+                    group: None,
+                    allow_exit_codes: None,
+                    assert_output_contains: None,
+                    assert_output_matches: None,
                 },
                 config::Command {
+                    name: None,
                     command: DEPLOY_BUILD_CMD.to_string(),
                     description: Some(DEPLOY_BUILD_DESC.to_string()),
                     ignore_errors: None,
@@ -104,6 +159,17 @@ pub mod templates {
                     interactive: Some(false),
                     inputs: None,
                     variables_file: None,
+                    variables: None,
+                    stdin: None,
+                    stdin_file: None,
+                    working_dir: None,
+                    retry_policy: None,
+                    trace: None,
+                    quiet: None,
+                    group: None,
+                    allow_exit_codes: None,
+                    assert_output_contains: None,
+                    assert_output_matches: None,
                 },
             ],
             fail_fast: Some(true),
@@ -117,15 +183,30 @@ pub mod templates {
             description: Some(POST_DEPLOY_DESC.to_string()),
             commands: vec![
                 config::Command {
+                    name: None,
                     command: POST_DEPLOY_RESTART_CMD.to_string(),
                     description: Some(POST_DEPLOY_RESTART_DESC.to_string()),
                     ignore_errors: None,
-                    rollback_command: Some(POST_DEPLOY_RESTART_ROLLBACK.to_string()),
+                    rollback_command: Some(config::RollbackCommand::Single(
+                        POST_DEPLOY_RESTART_ROLLBACK.to_string(),
+                    )),
                     interactive: Some(false),
                     inputs: None,
                     variables_file: None,
+                    variables: None,
+                    stdin: None,
+                    stdin_file: None,
+                    working_dir: None,
+                    retry_policy: None,
+                    trace: None,
+                    quiet: None,
+                    group: None,
+                    allow_exit_codes: None,
+                    assert_output_contains: None,
+                    assert_output_matches: None,
                 },
                 config::Command {
+                    name: None,
                     command: POST_DEPLOY_FINISH_CMD.to_string(),
                     description: Some(POST_DEPLOY_FINISH_DESC.to_string()),
                     ignore_errors: Some(true),
@@ -133,6 +214,17 @@ pub mod templates {
                     interactive: Some(false),
                     inputs: None,
                     variables_file: None,
+                    variables: None,
+                    stdin: None,
+                    stdin_file: None,
+                    working_dir: None,
+                    retry_policy: None,
+                    trace: None,
+                    quiet: None,
+                    group: None,
+                    allow_exit_codes: None,
+                    assert_output_contains: None,
+                    assert_output_matches: None,
                 },
             ],
             fail_fast: Some(false),
@@ -147,10 +239,10 @@ pub fn get_deployment_config<'a>(
 ) -> Result<&'a Deployment> {
     config
         .find_deployment(deployment_name)
-        .with_context(|| format!("Деплой с именем '{}' не найден", deployment_name))
+        .with_context(|| config.deployment_not_found_message(deployment_name))
 }
 
-/// Проверяет, что все события в деплое имеют команды
+/// Проверяет, что все события в деплое имеют команды с уникальными именами
 pub fn validate_deployment_events(deployment: &Deployment) -> Result<bool> {
     // Проверка на пустые события
     if deployment.events.is_empty() {
@@ -169,8 +261,308 @@ pub fn validate_deployment_events(deployment: &Deployment) -> Result<bool> {
         }
     }
 
-    Ok(true)
+    // Имена команд (`name`) должны быть уникальны в пределах события
+    let mut valid = true;
+    for event in &deployment.events {
+        let mut seen = HashSet::new();
+        for (idx, cmd) in event.commands.iter().enumerate() {
+            let Some(name) = cmd.name.as_deref() else {
+                continue;
+            };
+            if !seen.insert(name) {
+                error!(
+                    "Команда {} события '{}' в деплое '{}' повторяет имя '{}' другой команды события",
+                    idx + 1,
+                    event.name,
+                    deployment.name,
+                    name
+                );
+                valid = false;
+            }
+        }
+    }
+
+    // Регулярные выражения проверок вывода должны компилироваться
+    for event in &deployment.events {
+        for (idx, cmd) in event.commands.iter().enumerate() {
+            let Some(pattern) = cmd.assert_output_matches.as_deref() else {
+                continue;
+            };
+            if let Err(e) = regex::Regex::new(pattern) {
+                error!(
+                    "Команда {} события '{}' в деплое '{}' содержит некорректное assert_output_matches '{}': {}",
+                    idx + 1,
+                    event.name,
+                    deployment.name,
+                    pattern,
+                    e
+                );
+                valid = false;
+            }
+        }
+    }
+
+    Ok(valid)
+}
+
+/// Находит шаблонные переменные, расположенные вне кавычек
+///
+/// Значения таких переменных подставляются в команду как есть, поэтому
+/// пробелы или символы `;`, `|`, `&` в значении могут разбить аргумент
+/// или привести к выполнению лишних команд.
+///
+/// # Параметры
+///
+/// * `command` - Строка с командой
+///
+/// # Возвращаемое значение
+///
+/// Список шаблонов (например, `{#VAR}`), найденных вне кавычек
+pub fn find_unquoted_placeholders(command: &str) -> Vec<String> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut placeholders = Vec::new();
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if !in_single => i += 1,
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single => in_double = !in_double,
+            '{' if !in_single && !in_double => {
+                if let Some(len) = chars[i + 1..].iter().position(|&c| c == '}') {
+                    let name: String = chars[i + 1..i + 1 + len].iter().collect();
+                    let ident = name.trim_start_matches(['#', '$']);
+                    if !ident.is_empty() && ident.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                        placeholders.push(format!("{{{}}}", name));
+                        i += len + 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    placeholders
+}
+
+/// Проверяет команды деплоя на небезопасную подстановку переменных
+///
+/// Если для деплоя включено `quote_variables`, переменные из файлов
+/// (`{#VAR}`) экранируются автоматически и не считаются небезопасными.
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+///
+/// # Возвращаемое значение
+///
+/// Список предупреждений о небезопасных шаблонах
+pub fn check_unquoted_variables(deployment: &Deployment) -> Vec<String> {
+    let auto_quoted = deployment.quote_variables.unwrap_or(false);
+    let mut warnings = Vec::new();
+
+    for event in &deployment.events {
+        for cmd in &event.commands {
+            for placeholder in find_unquoted_placeholders(&cmd.command) {
+                if auto_quoted && placeholder.starts_with("{#") {
+                    continue;
+                }
+                warnings.push(format!(
+                    "Событие '{}': переменная {} не заключена в кавычки в команде '{}'",
+                    event.name, placeholder, cmd.command
+                ));
+            }
+        }
+    }
+
+    warnings
+}
+
+/// Находит записи `environment` деплоя, не соответствующие формату `KEY=VALUE`
+///
+/// Такие записи не передаются командам, поэтому опечатка вроде `NODE_ENVproduction`
+/// иначе осталась бы незамеченной.
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+///
+/// # Возвращаемое значение
+///
+/// Список ошибок о некорректных записях
+pub fn check_environment_entries(deployment: &Deployment) -> Vec<String> {
+    deployment
+        .environment
+        .iter()
+        .flatten()
+        .filter(|entry| !matches!(entry.split_once('='), Some((key, _)) if !key.trim().is_empty()))
+        .map(|entry| {
+            format!(
+                "Переменная окружения '{}' не соответствует формату KEY=VALUE",
+                entry
+            )
+        })
+        .collect()
+}
+
+/// Проверяет, что файлы `env_files` деплоя читаются и имеют формат `.env`
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+///
+/// # Возвращаемое значение
+///
+/// Список ошибок чтения или формата файлов
+pub fn check_env_files(deployment: &Deployment) -> Vec<String> {
+    deployment
+        .env_files
+        .iter()
+        .flatten()
+        .filter_map(|path| executor::load_env_file(path).err())
+        .map(|e| format!("{:#}", e))
+        .collect()
+}
+
+/// Находит повторяющиеся ключи в `environment` деплоя
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+///
+/// # Возвращаемое значение
+///
+/// Список предупреждений о повторяющихся ключах
+pub fn check_duplicate_environment_keys(deployment: &Deployment) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut reported = HashSet::new();
+
+    deployment
+        .environment
+        .iter()
+        .flatten()
+        .filter_map(|entry| entry.split_once('=').map(|(key, _)| key))
+        .filter(|key| !seen.insert(*key) && reported.insert(*key))
+        .map(|key| {
+            format!(
+                "Переменная окружения '{}' указана несколько раз, используется последнее значение",
+                key
+            )
+        })
+        .collect()
 }
 
 // Реэкспортируем функции из шаблонов для обратной совместимости
 pub use templates::create_new_deployment;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deployment(yaml: &str) -> Deployment {
+        serde_yaml::from_str(yaml).expect("корректный YAML деплоя")
+    }
+
+    #[test]
+    fn finds_only_unquoted_placeholders() {
+        let placeholders =
+            find_unquoted_placeholders("deploy {#HOST} \"{#USER}\" '{#PASS}' {$HOME} \\{#ESCAPED}");
+
+        assert_eq!(placeholders, vec!["{#HOST}", "{$HOME}"]);
+    }
+
+    #[test]
+    fn quote_variables_silences_file_variable_warnings() {
+        let yaml = r#"
+name: api
+events:
+  - name: deploy
+    commands:
+      - command: "scp {#FILE} {$TARGET}"
+"#;
+        let mut dep = deployment(yaml);
+        assert_eq!(check_unquoted_variables(&dep).len(), 2);
+
+        dep.quote_variables = Some(true);
+        let warnings = check_unquoted_variables(&dep);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("{$TARGET}"));
+    }
+
+    #[test]
+    fn environment_entry_without_equals_is_malformed() {
+        let deployment = deployment(
+            r#"
+name: app
+environment:
+  - "NODE_ENVproduction"
+  - "=value"
+  - "PORT=8080"
+events: []
+"#,
+        );
+
+        let errors = check_environment_entries(&deployment);
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("'NODE_ENVproduction'"));
+        assert!(errors[1].contains("'=value'"));
+    }
+
+    #[test]
+    fn duplicate_environment_key_is_reported_once() {
+        let deployment = deployment(
+            r#"
+name: app
+environment:
+  - "PORT=8080"
+  - "HOST=localhost"
+  - "PORT=9090"
+  - "PORT=7070"
+events: []
+"#,
+        );
+
+        let warnings = check_duplicate_environment_keys(&deployment);
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("'PORT'"));
+    }
+
+    #[test]
+    fn duplicate_command_names_within_event_are_invalid() {
+        let duplicate = deployment(
+            r#"
+name: api
+events:
+  - name: deploy
+    commands:
+      - command: "./migrate.sh"
+        name: migrate
+      - command: "make build"
+      - command: "./migrate.sh --again"
+        name: migrate
+"#,
+        );
+        assert!(!validate_deployment_events(&duplicate).unwrap());
+
+        let separate_events = deployment(
+            r#"
+name: api
+events:
+  - name: deploy
+    commands:
+      - command: "./migrate.sh"
+        name: migrate
+  - name: rollback
+    commands:
+      - command: "./migrate.sh down"
+        name: migrate
+"#,
+        );
+        assert!(validate_deployment_events(&separate_events).unwrap());
+    }
+}
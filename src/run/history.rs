@@ -1,25 +1,39 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use log::info;
+use log::{info, warn};
 use std::time::{Duration, UNIX_EPOCH};
 
-use crate::storage::{DeploymentHistory, DeploymentRecord};
+use crate::config::{Deployment, Event};
+use crate::output;
+use crate::storage::{
+    AllDeploymentsSummary, DeploymentHistory, DeploymentRecord, EventOutcomeDiff,
+};
 
 /// Показывает историю деплоев с форматированием
+///
+/// При указании события выводятся только записи этого события, включая записи
+/// о его начале, завершении и ошибке.
 pub fn display_deployment_history(
     history_path: &str,
     deployment_name: &str,
+    event: Option<&str>,
     limit: usize,
 ) -> Result<()> {
     let history = load_history(history_path)?;
-    let records = history.get_records(deployment_name, limit);
+    let records = history.get_records(deployment_name, event, limit);
 
     if records.is_empty() {
-        println!("История деплоя '{}' пуста", deployment_name);
+        match event {
+            Some(event) => output::line(format!(
+                "История события '{}' деплоя '{}' пуста",
+                event, deployment_name
+            )),
+            None => output::line(format!("История деплоя '{}' пуста", deployment_name)),
+        }
         return Ok(());
     }
 
-    print_history_header(deployment_name, limit);
+    print_history_header(deployment_name, event, limit);
     for (i, record) in records.iter().enumerate() {
         print_history_record(i, record);
     }
@@ -27,9 +41,135 @@ pub fn display_deployment_history(
     Ok(())
 }
 
+/// Показывает сравнение последних исходов событий двух деплоев
+///
+/// События, исход которых у деплоев различается, выделяются.
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории деплоев
+/// * `a` - Имя первого деплоя
+/// * `b` - Имя второго деплоя
+/// * `limit` - Количество последних записей каждого деплоя для сравнения
+pub fn display_history_diff(history_path: &str, a: &str, b: &str, limit: usize) -> Result<()> {
+    let history = load_history(history_path)?;
+    let diffs = history.diff_deployments(a, b, limit);
+
+    if diffs.is_empty() {
+        output::line(format!("История деплоев '{}' и '{}' пуста", a, b));
+        return Ok(());
+    }
+
+    output::line(output::heading(&format!(
+        "Сравнение истории деплоев '{}' и '{}' (последние {} записей):",
+        a, b, limit
+    )));
+    for diff in &diffs {
+        print_outcome_diff(diff, a, b);
+    }
+
+    let divergent = diffs.iter().filter(|diff| diff.differs()).count();
+    if divergent == 0 {
+        output::line(output::success("Исходы всех событий совпадают"));
+    } else {
+        output::line(output::failure(&format!(
+            "Исходы различаются у событий: {}",
+            divergent
+        )));
+    }
+
+    Ok(())
+}
+
+/// Выводит строку сравнения исходов события двух деплоев
+fn print_outcome_diff(diff: &EventOutcomeDiff, a: &str, b: &str) {
+    let outcome = |success: Option<bool>| match success {
+        Some(true) => output::success("✅"),
+        Some(false) => output::failure("❌"),
+        None => output::muted("—"),
+    };
+    let line = format!(
+        "{} {}: {} {}, {} {}",
+        if diff.differs() { "≠" } else { " " },
+        diff.event,
+        a,
+        outcome(diff.a),
+        b,
+        outcome(diff.b)
+    );
+
+    if diff.differs() {
+        output::line(output::failure(&line));
+    } else {
+        output::line(line);
+    }
+}
+
+/// Отбирает события деплоя, не завершившиеся успешно после последнего полного деплоя
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории деплоев
+/// * `deployment` - Конфигурация деплоя
+///
+/// # Возвращаемое значение
+///
+/// События для повторного выполнения в порядке `event_order` деплоя
+fn pending_events<'a>(history_path: &str, deployment: &'a Deployment) -> Result<Vec<&'a Event>> {
+    let history = load_history(history_path)?;
+    let names: Vec<String> = deployment.events.iter().map(|e| e.name.clone()).collect();
+    let pending = history.pending_events(&deployment.name, &names);
+
+    info!(
+        "Для деплоя '{}' найдено {} событий, не выполненных после последнего успешного деплоя: {}",
+        deployment.name,
+        pending.len(),
+        pending.join(", ")
+    );
+
+    Ok(deployment
+        .ordered_events()
+        .into_iter()
+        .filter(|e| pending.contains(&e.name))
+        .collect())
+}
+
+/// Определяет события деплоя для полного запуска
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории деплоев
+/// * `deployment` - Конфигурация деплоя
+/// * `since_last_success` - Выполнять только события, не завершившиеся успешно
+///   после последнего полного деплоя
+///
+/// # Возвращаемое значение
+///
+/// События для выполнения в порядке `event_order` деплоя
+pub fn select_events<'a>(
+    history_path: &str,
+    deployment: &'a Deployment,
+    since_last_success: bool,
+) -> Vec<&'a Event> {
+    if !since_last_success {
+        return deployment.ordered_events();
+    }
+
+    match pending_events(history_path, deployment) {
+        Ok(events) => events,
+        Err(e) => {
+            warn!(
+                "Не удалось определить невыполненные события, будут выполнены все: {}",
+                e
+            );
+            deployment.ordered_events()
+        }
+    }
+}
+
 /// Загружает историю деплоев из файла
-fn load_history(history_path: &str) -> Result<DeploymentHistory> {
-    DeploymentHistory::load(history_path).with_context(|| {
+pub fn load_history(history_path: &str) -> Result<DeploymentHistory> {
+    DeploymentHistory::load(history_path, crate::storage::history_format()).with_context(|| {
         format!(
             "Не удалось загрузить историю деплоев из файла {}",
             history_path
@@ -38,27 +178,100 @@ fn load_history(history_path: &str) -> Result<DeploymentHistory> {
 }
 
 /// Выводит заголовок истории деплоев
-fn print_history_header(deployment_name: &str, limit: usize) {
-    println!(
-        "История деплоя '{}' (последние {} записей):",
-        deployment_name, limit
-    );
+fn print_history_header(deployment_name: &str, event: Option<&str>, limit: usize) {
+    let title = match event {
+        Some(event) => format!("История события '{}' деплоя '{}'", event, deployment_name),
+        None => format!("История деплоя '{}'", deployment_name),
+    };
+    output::line(output::heading(&format!(
+        "{} (последние {} записей):",
+        title, limit
+    )));
 }
 
 /// Выводит запись истории деплоя
 fn print_history_record(index: usize, record: &DeploymentRecord) {
     let timestamp = format_timestamp(record.timestamp);
-    let status = if record.success { "✅" } else { "❌" };
+    let status = if record.success {
+        output::success("✅")
+    } else {
+        output::failure("❌")
+    };
     let details = record.details.as_deref().unwrap_or("");
+    // Итоги запуска всех деплоев хранятся в JSON, выводим их в читаемом виде
+    let details = match AllDeploymentsSummary::from_details(details) {
+        Some(summary) => summary.to_string(),
+        None => details.to_string(),
+    };
+    let git_sha = record
+        .git_sha
+        .as_deref()
+        .map(|sha| format!(" {}", output::muted(&format!("(git {})", short_sha(sha)))))
+        .unwrap_or_default();
+    let version = record
+        .deployed_version
+        .as_deref()
+        .map(|version| format!(" {}", output::muted(&format!("(версия {})", version))))
+        .unwrap_or_default();
 
-    println!(
-        "{}. [{} UTC] {} {} {}",
+    output::line(format!(
+        "{}. {} {} {} {}{}{}",
         index + 1,
-        timestamp,
+        output::muted(&format!("[{} UTC]", timestamp)),
         status,
         record.event,
-        details
-    );
+        details,
+        git_sha,
+        version
+    ));
+
+    // Упавшие команды события, чтобы было видно, на какой из них остановился запуск
+    for command in record.commands.iter().flatten().filter(|c| !c.success) {
+        let exit_code = command
+            .exit_code
+            .map(|code| format!("код {}, ", code))
+            .unwrap_or_default();
+        output::line(format!(
+            "   {} {} {}",
+            output::failure("✗"),
+            command.name,
+            output::muted(&format!("({}{} мс)", exit_code, command.duration_ms))
+        ));
+    }
+}
+
+/// Сокращает хеш коммита git до 12 символов для вывода
+fn short_sha(sha: &str) -> &str {
+    sha.get(..12).unwrap_or(sha)
+}
+
+/// Формирует суффикс со статусом последнего запуска деплоя для списка деплоев
+///
+/// # Параметры
+///
+/// * `history` - История деплоев
+/// * `deployment_name` - Имя деплоя
+///
+/// # Возвращаемое значение
+///
+/// Статус, событие и время последней записи истории или пометка об отсутствии запусков
+pub fn last_status_suffix(history: &DeploymentHistory, deployment_name: &str) -> String {
+    match history.get_records(deployment_name, None, 1).first() {
+        Some(record) => {
+            let status = if record.success {
+                output::success("✅")
+            } else {
+                output::failure("❌")
+            };
+            format!(
+                " {} {} {}",
+                status,
+                record.event,
+                output::muted(&format!("[{} UTC]", format_timestamp(record.timestamp)))
+            )
+        }
+        None => format!(" {}", output::muted("(запусков не было)")),
+    }
 }
 
 /// Форматирует временную метку
@@ -68,6 +281,30 @@ fn format_timestamp(timestamp: u64) -> String {
         .to_string()
 }
 
+/// Удаляет записи истории старше указанного возраста
+pub fn prune_history(
+    history_path: &str,
+    older_than: Duration,
+    deployment_name: Option<&str>,
+) -> Result<()> {
+    let removed = crate::storage::prune_deployment_history(
+        history_path,
+        older_than.as_secs(),
+        deployment_name,
+    )
+    .with_context(|| "Не удалось удалить устаревшие записи истории".to_string())?;
+
+    let message = match deployment_name {
+        Some(name) => format!("Из истории деплоя '{}' удалено записей: {}", name, removed),
+        None => format!("Из истории деплоев удалено записей: {}", removed),
+    };
+
+    info!("{}", message);
+    output::line(&message);
+
+    Ok(())
+}
+
 /// Очищает историю деплоев
 pub fn clear_history(history_path: &str, deployment_name: Option<&str>) -> Result<()> {
     crate::storage::clear_deployment_history(history_path, deployment_name).with_context(|| {
@@ -84,7 +321,56 @@ pub fn clear_history(history_path: &str, deployment_name: Option<&str>) -> Resul
     };
 
     info!("{}", success_message);
-    println!("{}", success_message);
+    output::line(output::success(&success_message));
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(event: &str, timestamp: u64, success: bool) -> DeploymentRecord {
+        DeploymentRecord {
+            deployment: "app".to_string(),
+            event: event.to_string(),
+            timestamp,
+            success,
+            details: None,
+            git_sha: None,
+            warnings: None,
+            commands: None,
+            deployed_version: None,
+        }
+    }
+
+    #[test]
+    fn status_suffix_reflects_latest_record() {
+        let mut history = DeploymentHistory::new();
+        history.add_record(record("complete:build", 60, true));
+        history.add_record(record("failed:deploy", 120, false));
+
+        let suffix = last_status_suffix(&history, "app");
+
+        assert!(suffix.contains("❌"));
+        assert!(suffix.contains("failed:deploy"));
+        assert!(suffix.contains("1970-01-01 00:02:00 UTC"));
+        assert!(!suffix.contains("complete:build"));
+    }
+
+    #[test]
+    fn short_sha_keeps_twelve_characters() {
+        assert_eq!(
+            short_sha("0123456789abcdef0123456789abcdef01234567"),
+            "0123456789ab"
+        );
+        assert_eq!(short_sha("abc"), "abc");
+    }
+
+    #[test]
+    fn status_suffix_without_runs() {
+        let suffix = last_status_suffix(&DeploymentHistory::new(), "app");
+
+        assert!(suffix.contains("запусков не было"));
+    }
+}
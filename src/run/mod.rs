@@ -6,26 +6,91 @@
 
 // Подмодули
 mod command_runner;
+mod config_diff;
 mod deployment;
 mod deployment_manager;
 pub mod deployments;
 mod history;
+mod plan;
 
 // Реэкспорт публичных типов и функций из модуля deployment
 pub use deployment::{create_deployment_template, Deployment};
 
 // Создаем публичные функции-обертки для методов структуры Deployment
 use crate::config::Config;
+use crate::executor::RunOptions;
+use crate::exit_codes::{exit_with, DeployError};
+use log::error;
+use std::time::Duration;
+
+/// Прерывает запуск, если файл конфигурации изменился после загрузки
+///
+/// Вызывается перед каждым следующим деплоем или событием пакетного запуска, чтобы
+/// оставшаяся часть пакета не выполнялась по устаревшей конфигурации.
+///
+/// # Параметры
+///
+/// * `options` - Опции запуска с отпечатком конфигурации
+pub fn ensure_config_unchanged(options: &RunOptions) {
+    let Some(fingerprint) = &options.config_fingerprint else {
+        return;
+    };
+
+    match fingerprint.has_changed() {
+        Ok(false) => {}
+        Ok(true) => {
+            error!(
+                "Файл конфигурации {} изменился во время запуска, оставшиеся деплои не будут запущены \
+                 (используйте --ignore-config-change, чтобы продолжить по исходной конфигурации)",
+                fingerprint.path
+            );
+            exit_with(DeployError::Config);
+        }
+        Err(e) => {
+            error!(
+                "Не удалось перечитать файл конфигурации {} во время запуска: {:#}",
+                fingerprint.path, e
+            );
+            exit_with(DeployError::Config);
+        }
+    }
+}
 
 /// Выполняет указанное событие для деплоя
-pub async fn run_event(config: &Config, deployment: &str, event: &str, history_path: &str) {
-    let deployment_obj = Deployment::new(config.clone(), history_path.to_string(), false);
+pub async fn run_event(
+    config: &Config,
+    deployment: &str,
+    event: &str,
+    history_path: &str,
+    options: &RunOptions,
+) {
+    let deployment_obj = Deployment::new(config.clone(), history_path.to_string(), false)
+        .with_options(options.clone());
     deployment_obj.run_specific_event(deployment, event).await;
 }
 
+/// Выполняет событие деплоя несколько раз подряд (`--repeat`) и выводит итог
+pub async fn repeat_event(
+    config: &Config,
+    deployment: &str,
+    event: &str,
+    history_path: &str,
+    options: &RunOptions,
+) {
+    let deployment_obj = Deployment::new(config.clone(), history_path.to_string(), false)
+        .with_options(options.clone());
+    deployment_obj.repeat_event(deployment, event).await;
+}
+
 /// Запускает все события для указанного деплоя последовательно
-pub async fn run_all_events(config: &Config, deployment: &str, history_path: &str) {
-    let deployment_obj = Deployment::new(config.clone(), history_path.to_string(), false);
+pub async fn run_all_events(
+    config: &Config,
+    deployment: &str,
+    history_path: &str,
+    options: &RunOptions,
+) {
+    let deployment_obj = Deployment::new(config.clone(), history_path.to_string(), false)
+        .with_options(options.clone());
     deployment_obj.run_all_events(deployment).await;
 }
 
@@ -35,15 +100,37 @@ pub async fn run_all_deployments(
     history_path: &str,
     event: Option<&str>,
     parallel: bool,
+    options: &RunOptions,
 ) {
-    let deployment_obj = Deployment::new(config.clone(), history_path.to_string(), parallel);
+    let deployment_obj = Deployment::new(config.clone(), history_path.to_string(), parallel)
+        .with_options(options.clone());
     deployment_obj.run_all_deployments(event, parallel).await;
 }
 
+/// Выводит план выполнения деплоев без запуска команд
+///
+/// Возвращает false, если при проверке команд найдены недоступные
+pub async fn show_plan(
+    config: &Config,
+    history_path: &str,
+    events: &[String],
+    parallel: bool,
+    options: &RunOptions,
+) -> bool {
+    plan::show_plan(config, history_path, events, parallel, options).await
+}
+
 /// Выводит список всех доступных деплоев и команд
-pub fn list_deployments(config: &Config) {
-    let deployment_obj = Deployment::new(config.clone(), String::new(), false);
-    deployment_obj.list_deployments();
+///
+/// При указании пути к истории к каждому деплою добавляется статус последнего запуска,
+/// при указании фильтра выводятся только деплои, события и команды с совпадениями
+pub fn list_deployments(config: &Config, history_path: Option<&str>, filter: Option<&str>) {
+    let deployment_obj = Deployment::new(
+        config.clone(),
+        history_path.unwrap_or_default().to_string(),
+        false,
+    );
+    deployment_obj.list_deployments(history_path.is_some(), filter);
 }
 
 /// Проверяет конфигурацию деплоя на корректность
@@ -52,10 +139,27 @@ pub fn verify_deployment_config(config: &Config, deployment_name: &str) {
     deployment_obj.verify_deployment_config(deployment_name);
 }
 
+/// Проверяет конфигурацию нескольких деплоев
+pub fn verify_deployment_configs(config: &Config, deployment_names: &[&str]) {
+    let deployment_obj = Deployment::new(config.clone(), String::new(), false);
+    deployment_obj.verify_deployment_configs(deployment_names);
+}
+
 /// Показывает историю деплоев
-pub fn show_deployment_history(history_path: &str, deployment_name: &str, limit: usize) {
+pub fn show_deployment_history(
+    history_path: &str,
+    deployment_name: &str,
+    event: Option<&str>,
+    limit: usize,
+) {
+    let deployment_obj = Deployment::new(Config::default(), history_path.to_string(), false);
+    deployment_obj.show_deployment_history(deployment_name, event, limit);
+}
+
+/// Показывает сравнение истории двух деплоев по событиям
+pub fn show_history_diff(history_path: &str, a: &str, b: &str, limit: usize) {
     let deployment_obj = Deployment::new(Config::default(), history_path.to_string(), false);
-    deployment_obj.show_deployment_history(deployment_name, limit);
+    deployment_obj.show_history_diff(a, b, limit);
 }
 
 /// Очищает историю деплоев
@@ -63,3 +167,18 @@ pub fn clear_deployment_history(history_path: &str, deployment_name: Option<&str
     let deployment_obj = Deployment::new(Config::default(), history_path.to_string(), false);
     deployment_obj.clear_deployment_history(deployment_name);
 }
+
+/// Показывает изменения конфигурации деплоя с последнего запуска
+pub fn show_config_diff(config: &Config, history_path: &str, deployment_name: &str) {
+    config_diff::show_config_diff(config, history_path, deployment_name);
+}
+
+/// Удаляет записи истории старше указанного возраста
+pub fn prune_deployment_history(
+    history_path: &str,
+    older_than: Duration,
+    deployment_name: Option<&str>,
+) {
+    let deployment_obj = Deployment::new(Config::default(), history_path.to_string(), false);
+    deployment_obj.prune_deployment_history(older_than, deployment_name);
+}
@@ -0,0 +1,341 @@
+/*!
+# Подмодуль Plan
+
+Отвечает за вывод плана выполнения деплоев без запуска команд (`--dry-run`):
+
+- Вывод событий и команд с подставленными переменными из конфигурации и файлов
+- Сводный план для нескольких деплоев в порядке их запуска
+- Проверка наличия внешних команд в PATH (`--check-commands`)
+- Вывод переменных окружения, передаваемых командам (`--print-env`)
+*/
+
+use log::{info, warn};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use crate::commands;
+use crate::config::{Config, Deployment, Event, MissingVarPolicy};
+use crate::executor::{self, RunOptions};
+use crate::output;
+use crate::run::history;
+use crate::settings;
+
+/// Встроенные команды оболочки, которые не ищутся в PATH
+const SHELL_BUILTINS: &[&str] = &[
+    "cd", "echo", "export", "set", "unset", "source", ".", "exit", "true", "false", "test", "[",
+    "if", "for", "while", "read", "eval", "exec", "pwd", "alias", "type", "umask", "wait", "trap",
+];
+
+/// Выводит план выполнения деплоев и при необходимости проверяет наличие команд
+///
+/// Деплои выводятся в том порядке, в котором они будут запущены: при указании событий
+/// для нескольких деплоев каждое событие выполняется по всем деплоям перед следующим.
+///
+/// # Параметры
+///
+/// * `config` - Конфигурация с деплоями для вывода
+/// * `history_path` - Путь к файлу истории (для `--since-last-success`)
+/// * `events` - Имена событий (пустой список - все события)
+/// * `parallel` - Режим параллельного запуска деплоев
+/// * `options` - Параметры запуска из командной строки
+///
+/// # Возвращаемое значение
+///
+/// false, если при проверке найдены недоступные команды
+pub async fn show_plan(
+    config: &Config,
+    history_path: &str,
+    events: &[String],
+    parallel: bool,
+    options: &RunOptions,
+) -> bool {
+    let settings = settings::get_settings(settings::DEFAULT_SETTINGS_PATH).unwrap_or_default();
+    let global_variables_file =
+        Some(settings.variables_file.as_str()).filter(|path| Path::new(path).exists());
+
+    let multiple = config.deployments.len() > 1;
+    if multiple {
+        let mode = if parallel {
+            "параллельно, порядок завершения может отличаться"
+        } else {
+            "последовательно"
+        };
+        output::line(format!(
+            "План выполнения для {} деплоев ({})",
+            config.deployments.len(),
+            mode
+        ));
+    }
+
+    // Каждое событие запускается отдельным этапом по всем деплоям
+    let stages: Vec<Option<&String>> = if events.is_empty() {
+        vec![None]
+    } else {
+        events.iter().map(Some).collect()
+    };
+
+    let mut executables = BTreeSet::new();
+    let mut total_commands = 0;
+
+    for stage in stages {
+        if let (true, Some(event)) = (multiple, stage) {
+            output::line(format!("Этап: событие {}", output::heading(event)));
+        }
+
+        for (idx, deployment) in config.deployments.iter().enumerate() {
+            let selected: Vec<&Event> = match stage {
+                None => {
+                    history::select_events(history_path, deployment, options.since_last_success)
+                }
+                Some(name) => {
+                    let event = deployment.events.iter().find(|e| &e.name == name);
+                    if event.is_none() {
+                        warn!(
+                            "Событие '{}' не найдено в деплое '{}'",
+                            name, deployment.name
+                        );
+                    }
+                    event.into_iter().collect()
+                }
+            };
+
+            if multiple && selected.is_empty() {
+                continue;
+            }
+
+            let position = if multiple {
+                format!("[{}/{}] ", idx + 1, config.deployments.len())
+            } else {
+                String::new()
+            };
+            output::line(format!(
+                "{}Деплой: {}",
+                position,
+                output::heading(&deployment.name)
+            ));
+            if options.print_env {
+                show_environment(deployment, &options.env);
+            }
+
+            for event in selected {
+                total_commands += show_event_plan(
+                    deployment,
+                    event,
+                    global_variables_file,
+                    &options.variables,
+                    &mut executables,
+                );
+            }
+        }
+    }
+
+    if multiple {
+        output::line(output::muted(&format!(
+            "Всего команд в плане: {}",
+            total_commands
+        )));
+    }
+
+    if !options.check_commands {
+        return true;
+    }
+
+    check_executables(&executables).await
+}
+
+/// Выводит команды события с подставленными переменными
+///
+/// # Параметры
+///
+/// * `deployment` - Деплой, к которому относится событие
+/// * `event` - Событие для вывода
+/// * `global_variables_file` - Глобальный файл переменных
+/// * `cli_variables` - Переменные из `--var`
+/// * `executables` - Набор исполняемых файлов для последующей проверки
+///
+/// # Возвращаемое значение
+///
+/// Количество выведенных команд
+fn show_event_plan(
+    deployment: &Deployment,
+    event: &Event,
+    global_variables_file: Option<&str>,
+    cli_variables: &HashMap<String, String>,
+    executables: &mut BTreeSet<String>,
+) -> usize {
+    output::line(format!("  Событие: {}", output::heading(&event.name)));
+
+    for (idx, cmd) in event.commands.iter().enumerate() {
+        let variables_file = cmd
+            .variables_file
+            .as_deref()
+            .or(deployment.variables_file.as_deref());
+        let inline_variables = cmd.inline_variables(deployment.variables.as_ref());
+        let command = resolve_command(
+            &cmd.command,
+            &inline_variables,
+            cli_variables,
+            variables_file,
+            global_variables_file,
+        );
+
+        output::line(format!("    {}. {}", idx + 1, command));
+        if let Some(stdin_file) = &cmd.stdin_file {
+            output::line(output::muted(&format!("       stdin: {}", stdin_file)));
+        } else if cmd.stdin.is_some() {
+            output::line(output::muted("       stdin: данные из конфигурации"));
+        }
+        if let Some(executable) = command_executable(&command) {
+            executables.insert(executable);
+        }
+    }
+
+    event.commands.len()
+}
+
+/// Выводит переменные окружения, которые передаются всем командам деплоя
+///
+/// # Параметры
+///
+/// * `deployment` - Деплой, для которого выводятся переменные
+/// * `overrides` - Переменные из `--env`
+fn show_environment(deployment: &Deployment, overrides: &[(String, String)]) {
+    let env_vars =
+        executor::determine_environment_variables(deployment, &deployment.name, overrides);
+    if env_vars.is_empty() {
+        output::line(output::muted("  Окружение: переменные не заданы"));
+        return;
+    }
+
+    output::line("  Окружение (для всех команд деплоя):");
+    for (name, value) in &env_vars {
+        output::line(format!("    {}={}", name, value));
+    }
+}
+
+/// Подставляет в команду значения переменных из конфигурации и локальных файлов
+///
+/// Удаленные файлы переменных не загружаются, их шаблоны остаются без изменений.
+///
+/// # Параметры
+///
+/// * `command` - Строка с командой
+/// * `inline_variables` - Переменные, заданные в конфигурации деплоя и команды
+/// * `cli_variables` - Переменные из `--var`
+/// * `variables_file` - Файл переменных деплоя или команды
+/// * `global_variables_file` - Глобальный файл переменных
+///
+/// # Возвращаемое значение
+///
+/// Команда с подставленными значениями
+fn resolve_command(
+    command: &str,
+    inline_variables: &HashMap<String, String>,
+    cli_variables: &HashMap<String, String>,
+    variables_file: Option<&str>,
+    global_variables_file: Option<&str>,
+) -> String {
+    let variables_file = variables_file.filter(|path| !executor::is_remote_variables_file(path));
+
+    let mut variables = inline_variables.clone();
+    match executor::load_variables_from_file(variables_file, global_variables_file) {
+        Ok(file_variables) => variables.extend(file_variables),
+        Err(e) => warn!("Не удалось загрузить переменные для плана: {}", e),
+    }
+    variables.extend(cli_variables.clone());
+
+    executor::replace_file_variables(command, &variables, false, MissingVarPolicy::Keep)
+        .unwrap_or_else(|_| command.to_string())
+}
+
+/// Определяет исполняемый файл, вызываемый командой
+///
+/// Пропускает присваивания переменных окружения (`FOO=bar cmd`), встроенные команды
+/// оболочки и токены с неразрешенными шаблонами переменных.
+///
+/// # Параметры
+///
+/// * `command` - Строка с командой
+///
+/// # Возвращаемое значение
+///
+/// Имя исполняемого файла или None
+fn command_executable(command: &str) -> Option<String> {
+    let token = command
+        .split_whitespace()
+        .find(|token| !is_env_assignment(token))?;
+
+    if token.contains('{') || SHELL_BUILTINS.contains(&token) {
+        return None;
+    }
+
+    Some(token.to_string())
+}
+
+/// Проверяет, является ли токен присваиванием переменной окружения вида `NAME=value`
+fn is_env_assignment(token: &str) -> bool {
+    match token.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// Проверяет наличие исполняемых файлов и выводит недоступные
+///
+/// # Параметры
+///
+/// * `executables` - Имена исполняемых файлов
+///
+/// # Возвращаемое значение
+///
+/// true, если все команды доступны
+async fn check_executables(executables: &BTreeSet<String>) -> bool {
+    let mut missing = Vec::new();
+
+    for executable in executables {
+        match commands::validate_command(executable).await {
+            Ok(_) => info!("Команда '{}' доступна", executable),
+            Err(_) => missing.push(executable.as_str()),
+        }
+    }
+
+    if missing.is_empty() {
+        output::line(output::success("Все команды плана доступны"));
+        return true;
+    }
+
+    output::line(output::failure("Команды, не найденные в PATH:"));
+    for executable in &missing {
+        output::line(format!("  - {}", executable));
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn executable_skips_env_assignments_and_builtins() {
+        assert_eq!(
+            command_executable("RUST_LOG=debug cargo build"),
+            Some("cargo".to_string())
+        );
+        assert_eq!(command_executable("cd /srv/app && make"), None);
+        assert_eq!(command_executable("{#TOOL} --version"), None);
+    }
+
+    #[tokio::test]
+    async fn missing_executable_is_reported() {
+        let executables = BTreeSet::from([
+            "sh".to_string(),
+            "deploy-commander-missing-tool".to_string(),
+        ]);
+
+        assert!(!check_executables(&executables).await);
+        assert!(check_executables(&BTreeSet::from(["sh".to_string()])).await);
+    }
+}
@@ -0,0 +1,136 @@
+/*!
+# Модуль Schema
+
+Модуль `schema` формирует JSON Schema формата конфигурации деплоя для проверки
+и автодополнения YAML-файлов в редакторах:
+
+- Описание структур `Config`, `Deployment`, `Event` и `Command`, выводимое
+  из их определений (`#[derive(JsonSchema)]`)
+- Обязательные поля и допустимые значения перечислений
+- Запрет неизвестных полей, как в режиме `--strict-config`
+
+## Основные функции
+
+- `config_schema` - возвращает JSON Schema конфигурации
+- `write_schema` - выводит схему в стандартный вывод или сохраняет в файл
+*/
+
+use anyhow::{Context, Result};
+use schemars::schema::RootSchema;
+use schemars::schema_for;
+use std::fs;
+
+use crate::config::Config;
+
+/// Возвращает JSON Schema конфигурации деплоя
+///
+/// Схема формируется из структур конфигурации, поэтому новые поля попадают
+/// в нее без дополнительной регистрации.
+///
+/// # Возвращаемое значение
+///
+/// Схема в формате JSON Schema draft-07
+pub fn config_schema() -> RootSchema {
+    let mut schema = schema_for!(Config);
+    schema.schema.metadata().title = Some("Deploy Commander configuration".to_string());
+    schema
+}
+
+/// Выводит JSON Schema конфигурации в стандартный вывод или сохраняет в файл
+///
+/// # Параметры
+///
+/// * `output` - Путь к файлу для сохранения схемы (None - стандартный вывод)
+///
+/// # Возвращаемое значение
+///
+/// Результат записи схемы
+pub fn write_schema(output: Option<&str>) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&config_schema())
+        .context("Не удалось сериализовать схему конфигурации")?;
+
+    match output {
+        Some(path) => fs::write(path, schema + "\n")
+            .with_context(|| format!("Не удалось записать схему в файл {}", path)),
+        None => {
+            println!("{}", schema);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonschema::JSONSchema;
+    use serde_json::Value;
+
+    fn compiled_schema() -> JSONSchema {
+        let schema = serde_json::to_value(config_schema()).unwrap();
+        JSONSchema::compile(&schema).unwrap()
+    }
+
+    fn yaml(content: &str) -> Value {
+        serde_yaml::from_str(content).unwrap()
+    }
+
+    #[test]
+    fn schema_accepts_example_config() {
+        let config = yaml(&fs::read_to_string("examples/deploy-config.yml").unwrap());
+
+        assert!(compiled_schema().is_valid(&config));
+    }
+
+    #[test]
+    fn schema_rejects_deployment_without_name() {
+        let config = yaml(
+            r#"
+deployments:
+  - events:
+      - name: deploy
+        commands:
+          - command: "echo deploy"
+"#,
+        );
+
+        assert!(!compiled_schema().is_valid(&config));
+    }
+
+    #[test]
+    fn schema_rejects_unknown_fields_and_enum_values() {
+        let schema = compiled_schema();
+
+        assert!(!schema.is_valid(&yaml(
+            r#"
+deployments:
+  - name: app
+    working_directory: "/srv/app"
+    events: []
+"#,
+        )));
+        assert!(!schema.is_valid(&yaml(
+            r#"
+deployments:
+  - name: app
+    on_missing_var: ignore
+    events: []
+"#,
+        )));
+        assert!(schema.is_valid(&yaml(
+            r#"
+deployments:
+  - name: app
+    on_missing_var: fail
+    environment: ["PORT=8080"]
+    events:
+      - name: deploy
+        commands:
+          - command: "./migrate.sh up"
+            rollback_command: ["./migrate.sh down", "./restart.sh"]
+            retry_policy:
+              retries: 2
+              backoff: exponential
+"#,
+        )));
+    }
+}
@@ -7,13 +7,23 @@
 - Предоставление параметров для других модулей
 - Управление путями к файлам логов и истории
 - Управление путем к файлу глобальных переменных
+- Выбор формата файла истории деплоев
+- Сокращенные имена (алиасы) деплоев
+- Выбор исходов деплоя, о которых отправляются уведомления
+- Переопределение путей через переменные окружения
+- Скрытие конфиденциальных данных в логах по регулярным выражениям
+- Шаблоны имен защищенных деплоев, требующих подтверждения
+- Проверка путей и шаблонов настроек без запуска деплоя
 */
 
 use anyhow::{Context, Result};
-use log::info;
+use log::{info, warn};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::sync::OnceLock;
 
 /// Константы по умолчанию
 pub const DEFAULT_SETTINGS_PATH: &str = "settings.json";
@@ -21,6 +31,40 @@ pub const DEFAULT_LOG_FILE: &str = "deploy-commander.log";
 pub const DEFAULT_HISTORY_FILE: &str = "deploy-history.json";
 pub const DEFAULT_VARIABLES_FILE: &str = "variables.json";
 pub const DEFAULT_LOGS_DIR: &str = "logs";
+pub const DEFAULT_CONFIG_FILE: &str = "deploy-config.yml";
+pub const DEFAULT_CONSOLE_WRAP_WIDTH: usize = 80;
+pub const DEFAULT_PROBE_EVENT: &str = "healthcheck";
+pub const REDACTED_PLACEHOLDER: &str = "****";
+
+/// Переменные окружения для переопределения путей
+pub const CONFIG_ENV_VAR: &str = "DC_CONFIG";
+pub const HISTORY_ENV_VAR: &str = "DC_HISTORY";
+pub const NOTIFY_ON_SUCCESS: &str = "success";
+pub const NOTIFY_ON_FAILURE: &str = "failure";
+
+/// Формат файла истории деплоев
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryFormat {
+    /// Единый JSON-объект, перезаписываемый целиком
+    #[default]
+    Json,
+    /// JSON Lines: одна запись на строку, новые записи дописываются в конец
+    Jsonl,
+}
+
+/// Файлы, в которые записывается вывод команд
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandLogMode {
+    /// Общий дневной файл `logs_dir/YYYYMMDD_commands.log`
+    #[default]
+    Shared,
+    /// Отдельный дневной файл деплоя `logs_dir/<деплой>/YYYYMMDD.log`
+    PerDeployment,
+    /// Общий файл и файл деплоя
+    Both,
+}
 
 /// Структура глобальных настроек приложения
 #[derive(Debug, Serialize, Deserialize)]
@@ -36,6 +80,69 @@ pub struct Settings {
 
     /// Путь к директории логов команд
     pub logs_dir: String,
+
+    /// Формат файла истории деплоев
+    #[serde(default)]
+    pub history_format: HistoryFormat,
+
+    /// Файлы, в которые записывается вывод команд
+    #[serde(default)]
+    pub command_log_mode: CommandLogMode,
+
+    /// Сокращенные имена деплоев: алиас -> имя деплоя
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aliases: Option<HashMap<String, String>>,
+
+    /// Исходы деплоя, о которых отправляются уведомления ("success", "failure")
+    #[serde(default = "default_notify_on")]
+    pub notify_on: Vec<String>,
+
+    /// Длина вывода команды, после которой он выводится в консоль построчно
+    #[serde(default = "default_console_wrap_width")]
+    pub console_wrap_width: usize,
+
+    /// Регулярные выражения для скрытия данных в логах и истории
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact_patterns: Vec<String>,
+
+    /// Шаблоны имен деплоев (`*` и `?`), запуск которых требует подтверждения
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protected_patterns: Vec<String>,
+
+    /// Прерывать запуск деплоя, если не найдены необходимые внешние команды
+    #[serde(default)]
+    pub require_commands: bool,
+
+    /// Событие, выполняемое командой `probe` по умолчанию
+    #[serde(default = "default_probe_event")]
+    pub probe_event: String,
+
+    /// Максимальный размер дневного файла лога команд в мегабайтах (0 - без ограничения)
+    #[serde(default)]
+    pub max_daily_log_mb: u64,
+
+    /// Максимальная длина поля `details` записи истории в символах (0 - без ограничения)
+    #[serde(default)]
+    pub max_detail_len: usize,
+
+    /// Адрес OTLP/HTTP сборщика OpenTelemetry для отправки спанов выполнения
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otel_endpoint: Option<String>,
+}
+
+/// Возвращает событие проверки работоспособности по умолчанию
+fn default_probe_event() -> String {
+    DEFAULT_PROBE_EVENT.to_string()
+}
+
+/// Возвращает ширину вывода в консоль по умолчанию
+fn default_console_wrap_width() -> usize {
+    DEFAULT_CONSOLE_WRAP_WIDTH
+}
+
+/// Возвращает исходы деплоя для уведомлений по умолчанию (успех и ошибка)
+pub fn default_notify_on() -> Vec<String> {
+    vec![NOTIFY_ON_SUCCESS.to_string(), NOTIFY_ON_FAILURE.to_string()]
 }
 
 impl Default for Settings {
@@ -45,6 +152,18 @@ impl Default for Settings {
             history_file: DEFAULT_HISTORY_FILE.to_string(),
             variables_file: DEFAULT_VARIABLES_FILE.to_string(),
             logs_dir: DEFAULT_LOGS_DIR.to_string(),
+            history_format: HistoryFormat::default(),
+            command_log_mode: CommandLogMode::default(),
+            aliases: None,
+            notify_on: default_notify_on(),
+            console_wrap_width: DEFAULT_CONSOLE_WRAP_WIDTH,
+            redact_patterns: Vec::new(),
+            protected_patterns: Vec::new(),
+            require_commands: false,
+            probe_event: DEFAULT_PROBE_EVENT.to_string(),
+            max_daily_log_mb: 0,
+            max_detail_len: 0,
+            otel_endpoint: None,
         }
     }
 }
@@ -91,6 +210,72 @@ impl Settings {
         }
     }
 
+    /// Возвращает имя деплоя, соответствующее алиасу
+    ///
+    /// # Параметры
+    ///
+    /// * `name` - Алиас или имя деплоя
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Имя деплоя из алиаса или исходное имя, если алиас не найден
+    pub fn resolve_deployment_alias<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases
+            .as_ref()
+            .and_then(|aliases| aliases.get(name))
+            .map(String::as_str)
+            .unwrap_or(name)
+    }
+
+    /// Проверяет, совпадает ли имя деплоя с одним из шаблонов `protected_patterns`
+    ///
+    /// # Параметры
+    ///
+    /// * `deployment_name` - Имя деплоя
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// `true`, если деплой защищен и требует подтверждения
+    pub fn is_protected_deployment(&self, deployment_name: &str) -> bool {
+        self.protected_patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern, deployment_name))
+    }
+
+    /// Проверяет настройки: доступность путей для записи и корректность шаблонов
+    ///
+    /// Файл журнала, директория журналов команд и файл истории должны существовать
+    /// и быть доступны для записи либо могут быть созданы.
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Список найденных проблем (пустой, если настройки корректны)
+    pub fn validate(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+
+        let paths = [
+            ("log_file", &self.log_file, false),
+            ("logs_dir", &self.logs_dir, true),
+            ("history_file", &self.history_file, false),
+        ];
+        for (field, path, is_dir) in paths {
+            if let Err(e) = check_writable_path(path, is_dir) {
+                issues.push(format!("{}: {}", field, e));
+            }
+        }
+
+        for pattern in &self.redact_patterns {
+            if let Err(e) = Regex::new(pattern) {
+                issues.push(format!(
+                    "redact_patterns: некорректное регулярное выражение '{}': {}",
+                    pattern, e
+                ));
+            }
+        }
+
+        issues
+    }
+
     /// Сохраняет настройки в файл
     ///
     /// # Параметры
@@ -111,7 +296,8 @@ impl Settings {
     }
 }
 
-/// Обновляет настройки, если они старой версии (без поля variables_file или logs_dir)
+/// Обновляет настройки, если они старой версии (без поля variables_file, logs_dir, history_format,
+/// notify_on или console_wrap_width)
 ///
 /// # Параметры
 ///
@@ -141,6 +327,62 @@ fn upgrade_settings_if_needed(path: &str, mut settings: Settings) -> Result<Sett
         updated = true;
     }
 
+    // Если поле "history_format" отсутствует, добавляем его
+    if !json_obj.contains_key("history_format") {
+        info!("Обновление настроек: добавление поля history_format");
+        settings.history_format = HistoryFormat::default();
+        updated = true;
+    }
+
+    // Если поле "command_log_mode" отсутствует, добавляем его
+    if !json_obj.contains_key("command_log_mode") {
+        info!("Обновление настроек: добавление поля command_log_mode");
+        settings.command_log_mode = CommandLogMode::default();
+        updated = true;
+    }
+
+    // Если поле "notify_on" отсутствует, добавляем его
+    if !json_obj.contains_key("notify_on") {
+        info!("Обновление настроек: добавление поля notify_on");
+        settings.notify_on = default_notify_on();
+        updated = true;
+    }
+
+    // Если поле "console_wrap_width" отсутствует, добавляем его
+    if !json_obj.contains_key("console_wrap_width") {
+        info!("Обновление настроек: добавление поля console_wrap_width");
+        settings.console_wrap_width = DEFAULT_CONSOLE_WRAP_WIDTH;
+        updated = true;
+    }
+
+    // Если поле "require_commands" отсутствует, добавляем его
+    if !json_obj.contains_key("require_commands") {
+        info!("Обновление настроек: добавление поля require_commands");
+        settings.require_commands = false;
+        updated = true;
+    }
+
+    // Если поле "probe_event" отсутствует, добавляем его
+    if !json_obj.contains_key("probe_event") {
+        info!("Обновление настроек: добавление поля probe_event");
+        settings.probe_event = DEFAULT_PROBE_EVENT.to_string();
+        updated = true;
+    }
+
+    // Если поле "max_daily_log_mb" отсутствует, добавляем его
+    if !json_obj.contains_key("max_daily_log_mb") {
+        info!("Обновление настроек: добавление поля max_daily_log_mb");
+        settings.max_daily_log_mb = 0;
+        updated = true;
+    }
+
+    // Если поле "max_detail_len" отсутствует, добавляем его
+    if !json_obj.contains_key("max_detail_len") {
+        info!("Обновление настроек: добавление поля max_detail_len");
+        settings.max_detail_len = 0;
+        updated = true;
+    }
+
     // Сохраняем настройки, если они были обновлены
     if updated {
         settings.save(path)?;
@@ -152,6 +394,9 @@ fn upgrade_settings_if_needed(path: &str, mut settings: Settings) -> Result<Sett
 
 /// Получает настройки из файла или создаёт настройки по умолчанию
 ///
+/// Путь к файлу истории переопределяется переменной окружения `DC_HISTORY`,
+/// если она задана.
+///
 /// # Параметры
 ///
 /// * `settings_path` - Путь к файлу настроек
@@ -160,5 +405,242 @@ fn upgrade_settings_if_needed(path: &str, mut settings: Settings) -> Result<Sett
 ///
 /// Настройки или ошибка
 pub fn get_settings(settings_path: &str) -> Result<Settings> {
-    Settings::load(settings_path)
+    let mut settings = Settings::load(settings_path)?;
+
+    if let Some(history_file) = env_path(HISTORY_ENV_VAR) {
+        settings.history_file = history_file;
+    }
+
+    Ok(settings)
+}
+
+/// Проверяет, что путь доступен для записи или может быть создан
+///
+/// # Параметры
+///
+/// * `path` - Путь к файлу или директории
+/// * `is_dir` - Путь указывает на директорию
+///
+/// # Возвращаемое значение
+///
+/// Описание проблемы, если запись по пути невозможна
+fn check_writable_path(path: &str, is_dir: bool) -> std::result::Result<(), String> {
+    if path.trim().is_empty() {
+        return Err("путь не задан".to_string());
+    }
+
+    let path = Path::new(path);
+    if path.exists() {
+        return match (is_dir, path.is_dir()) {
+            (true, true) => check_writable_dir(path),
+            (true, false) => Err(format!("{} не является директорией", path.display())),
+            (false, true) => Err(format!("{} является директорией", path.display())),
+            (false, false) => fs::OpenOptions::new()
+                .append(true)
+                .open(path)
+                .map(|_| ())
+                .map_err(|e| format!("нет доступа на запись к {}: {}", path.display(), e)),
+        };
+    }
+
+    // Файл или директория будут созданы в ближайшей существующей родительской директории
+    let existing = path
+        .ancestors()
+        .skip(1)
+        .map(|dir| {
+            if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            }
+        })
+        .find(|dir| dir.exists())
+        .unwrap_or(Path::new("."));
+    if !existing.is_dir() {
+        return Err(format!(
+            "не удается создать {}: {} не является директорией",
+            path.display(),
+            existing.display()
+        ));
+    }
+    check_writable_dir(existing)
+}
+
+/// Проверяет запись в директорию созданием и удалением временного файла
+///
+/// # Параметры
+///
+/// * `dir` - Существующая директория
+fn check_writable_dir(dir: &Path) -> std::result::Result<(), String> {
+    let probe = dir.join(format!(
+        ".deploy-commander-write-check-{}",
+        std::process::id()
+    ));
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .map_err(|e| format!("нет доступа на запись в {}: {}", dir.display(), e))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Читает путь из переменной окружения, игнорируя пустые значения
+///
+/// # Параметры
+///
+/// * `name` - Имя переменной окружения
+///
+/// # Возвращаемое значение
+///
+/// Значение переменной или None, если она не задана или пуста
+pub fn env_path(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Скомпилированные шаблоны скрытия, загружаются из настроек один раз за запуск
+static REDACT_REGEXES: OnceLock<Vec<Regex>> = OnceLock::new();
+
+/// Компилирует шаблоны скрытия, пропуская некорректные с предупреждением
+///
+/// # Параметры
+///
+/// * `patterns` - Регулярные выражения из настроек
+///
+/// # Возвращаемое значение
+///
+/// Список скомпилированных регулярных выражений
+fn compile_redact_patterns(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|pattern| match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                warn!("Некорректный шаблон redact_patterns '{}': {}", pattern, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Заменяет в тексте совпадения с шаблонами `redact_patterns` на `****`
+///
+/// # Параметры
+///
+/// * `text` - Исходный текст
+///
+/// # Возвращаемое значение
+///
+/// Текст со скрытыми совпадениями
+pub fn redact(text: &str) -> String {
+    let regexes = REDACT_REGEXES.get_or_init(|| match get_settings(DEFAULT_SETTINGS_PATH) {
+        Ok(settings) => compile_redact_patterns(&settings.redact_patterns),
+        Err(_) => Vec::new(),
+    });
+
+    regexes.iter().fold(text.to_string(), |acc, regex| {
+        regex.replace_all(&acc, REDACTED_PLACEHOLDER).into_owned()
+    })
+}
+
+/// Проверяет совпадение строки с шаблоном, поддерживающим `*` и `?`
+///
+/// # Параметры
+///
+/// * `pattern` - Шаблон, например `*-prod`
+/// * `value` - Проверяемая строка
+///
+/// # Возвращаемое значение
+///
+/// `true`, если строка целиком соответствует шаблону
+pub fn glob_matches(pattern: &str, value: &str) -> bool {
+    let regex = pattern
+        .split('*')
+        .map(|part| {
+            part.split('?')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".")
+        })
+        .collect::<Vec<_>>()
+        .join(".*");
+
+    Regex::new(&format!("^{}$", regex))
+        .map(|regex| regex.is_match(value))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_deployment_alias() {
+        let settings = Settings {
+            aliases: Some(HashMap::from([(
+                "w".to_string(),
+                "web-production".to_string(),
+            )])),
+            ..Settings::default()
+        };
+
+        assert_eq!(settings.resolve_deployment_alias("w"), "web-production");
+        assert_eq!(settings.resolve_deployment_alias("api"), "api");
+    }
+
+    #[test]
+    fn protected_patterns_match_deployment_names() {
+        let settings = Settings {
+            protected_patterns: vec!["*-prod".to_string(), "db-?".to_string()],
+            ..Settings::default()
+        };
+
+        assert!(settings.is_protected_deployment("web-prod"));
+        assert!(settings.is_protected_deployment("db-1"));
+        assert!(!settings.is_protected_deployment("web-dev"));
+        assert!(!settings.is_protected_deployment("db-10"));
+    }
+
+    #[test]
+    fn validate_accepts_creatable_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+        let settings = Settings {
+            log_file: path("deploy.log"),
+            logs_dir: path("nested/logs"),
+            history_file: path("history/deploy-history.json"),
+            ..Settings::default()
+        };
+
+        assert_eq!(settings.validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn validate_reports_unwritable_paths_and_invalid_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+        fs::write(dir.path().join("blocker"), "").unwrap();
+        fs::create_dir(dir.path().join("history")).unwrap();
+        let settings = Settings {
+            log_file: path("blocker/deploy.log"),
+            logs_dir: path("blocker"),
+            history_file: path("history"),
+            redact_patterns: vec!["token=\\w+".to_string(), "(".to_string()],
+            ..Settings::default()
+        };
+
+        let issues = settings.validate();
+
+        assert_eq!(issues.len(), 4, "{:?}", issues);
+        assert!(issues[0].starts_with("log_file: не удается создать"));
+        assert!(
+            issues[1].starts_with("logs_dir:") && issues[1].ends_with("не является директорией")
+        );
+        assert!(
+            issues[2].starts_with("history_file:") && issues[2].ends_with("является директорией")
+        );
+        assert!(issues[3].contains("redact_patterns: некорректное регулярное выражение '('"));
+    }
 }
@@ -3,7 +3,7 @@
 
 Модуль `storage` отвечает за хранение и управление историей деплоев:
 
-- Сохранение и загрузка истории деплоев в JSON-формате
+- Сохранение и загрузка истории деплоев в формате JSON или JSON Lines
 - Ведение записей о выполненных деплоях и их статусе
 - Форматирование и отображение истории деплоев
 
@@ -11,19 +11,26 @@
 
 - `DeploymentHistory` - основной класс для работы с историей деплоев
 - `DeploymentRecord` - запись о выполнении деплоя или его части
+- `RecordKind` - вид записи истории (начало, завершение, ошибка, результат цепочки)
+- `AllDeploymentsSummary` - итоги запуска всех деплоев в поле `details`
 - `record_deployment` - функция для записи события деплоя
 - `record_chain_result` - функция для записи результата выполнения цепочки команд
+- `save_config_snapshot` - функция для сохранения снимка конфигурации деплоя
 */
 
 use anyhow::{Context, Result};
-use log::info;
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::fs::File;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::config::Deployment;
+use crate::settings::{self, HistoryFormat};
+
 use command_system::chain::command_chain::ChainResult as ChainExecutionResult;
 use command_system::command::CommandResult;
 
@@ -34,6 +41,209 @@ pub struct DeploymentHistory {
     records: HashMap<String, Vec<DeploymentRecord>>,
 }
 
+/// Имя события в записях о полном деплое (все события деплоя)
+pub const FULL_DEPLOY_EVENT: &str = "full-deploy";
+
+/// Имя события в записях о запуске всех деплоев
+pub const FULL_DEPLOY_ALL_EVENT: &str = "full-deploy-all";
+
+/// Имя деплоя, под которым записываются запуски всех деплоев
+pub const ALL_DEPLOYMENTS: &str = "all-deployments";
+
+/// Отметка об обрезке поля `details`, за которой следует число обрезанных символов
+pub const DETAILS_TRUNCATED_MARKER: &str = "… [обрезано символов: ";
+
+/// Итоги запуска всех деплоев
+///
+/// Сохраняется в поле `details` итоговой записи `all-deployments` в виде JSON,
+/// чтобы результаты можно было разбирать программно.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AllDeploymentsSummary {
+    /// Количество запущенных деплоев
+    pub total: usize,
+    /// Количество успешно выполненных деплоев
+    pub succeeded: usize,
+    /// Количество деплоев, завершившихся с ошибкой
+    pub failed: usize,
+    /// Имена деплоев, завершившихся с ошибкой
+    pub failed_deployments: Vec<String>,
+}
+
+impl AllDeploymentsSummary {
+    /// Формирует итоги запуска всех деплоев
+    ///
+    /// # Параметры
+    ///
+    /// * `total` - Количество запущенных деплоев
+    /// * `failed_deployments` - Имена деплоев, завершившихся с ошибкой
+    pub fn new(total: usize, failed_deployments: Vec<String>) -> Self {
+        let failed = failed_deployments.len();
+        Self {
+            total,
+            succeeded: total.saturating_sub(failed),
+            failed,
+            failed_deployments,
+        }
+    }
+
+    /// Сериализует итоги в JSON для поля `details`
+    pub fn to_details(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Разбирает итоги из поля `details` записи истории
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Итоги или None, если поле содержит не итоги (например, запись прежнего формата)
+    pub fn from_details(details: &str) -> Option<Self> {
+        serde_json::from_str(details).ok()
+    }
+}
+
+impl std::fmt::Display for AllDeploymentsSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "всего {}, успешно {}, с ошибками {}",
+            self.total, self.succeeded, self.failed
+        )?;
+        if !self.failed_deployments.is_empty() {
+            write!(f, ": {}", self.failed_deployments.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Последние исходы события в истории двух сравниваемых деплоев
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventOutcomeDiff {
+    /// Имя события
+    pub event: String,
+    /// Успешен ли последний запуск события первого деплоя (None - событие не запускалось)
+    pub a: Option<bool>,
+    /// Успешен ли последний запуск события второго деплоя (None - событие не запускалось)
+    pub b: Option<bool>,
+}
+
+impl EventOutcomeDiff {
+    /// Проверяет, различаются ли исходы события у деплоев
+    pub fn differs(&self) -> bool {
+        self.a != self.b
+    }
+}
+
+/// Вид записи истории деплоев
+///
+/// В записи вид хранится вместе с именем события в формате `{вид}:{событие}`,
+/// например `start:deploy` или `complete:full-deploy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    /// Начало выполнения события или полного деплоя
+    Start,
+    /// Успешное завершение события или полного деплоя
+    Complete,
+    /// Завершение события или полного деплоя с ошибкой
+    Failed,
+    /// Результат выполнения цепочки команд события
+    Result,
+    /// Ошибка подготовки или запуска цепочки команд события
+    Error,
+}
+
+impl RecordKind {
+    /// Все виды записей
+    const ALL: [RecordKind; 5] = [
+        RecordKind::Start,
+        RecordKind::Complete,
+        RecordKind::Failed,
+        RecordKind::Result,
+        RecordKind::Error,
+    ];
+
+    /// Возвращает имя вида записи, используемое в истории
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordKind::Start => "start",
+            RecordKind::Complete => "complete",
+            RecordKind::Failed => "failed",
+            RecordKind::Result => "result",
+            RecordKind::Error => "error",
+        }
+    }
+
+    /// Формирует значение поля `event` записи истории
+    ///
+    /// # Параметры
+    ///
+    /// * `event` - Имя события
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Строка вида `{вид}:{событие}`
+    pub fn record_event(&self, event: &str) -> String {
+        format!("{}:{}", self.as_str(), event)
+    }
+}
+
+/// Разбирает значение поля `event` записи истории
+///
+/// # Параметры
+///
+/// * `event` - Значение поля `event` в формате `{вид}:{событие}`
+///
+/// # Возвращаемое значение
+///
+/// Вид записи (None, если строка не в формате `{вид}:{событие}`) и имя события
+pub fn parse_record_event(event: &str) -> (Option<RecordKind>, &str) {
+    RecordKind::ALL
+        .iter()
+        .find_map(|kind| {
+            event
+                .strip_prefix(kind.as_str())
+                .and_then(|rest| rest.strip_prefix(':'))
+                .map(|name| (Some(*kind), name))
+        })
+        .unwrap_or((None, event))
+}
+
+/// Префиксы записей в прежнем формате истории в порядке проверки
+///
+/// Раньше вид записи добавлялся к имени события через дефис, причем при записи
+/// успешного завершения и ошибки префикс мог повторяться (`success-complete-`,
+/// `failed-failed-`). Записи без префикса содержали результат цепочки команд.
+const LEGACY_RECORD_PREFIXES: &[(&str, RecordKind)] = &[
+    ("success-complete-", RecordKind::Complete),
+    ("failed-failed-", RecordKind::Failed),
+    ("start-", RecordKind::Start),
+    ("complete-", RecordKind::Complete),
+    ("success-", RecordKind::Complete),
+    ("failed-", RecordKind::Failed),
+    ("error-", RecordKind::Error),
+];
+
+/// Приводит значение поля `event` записи прежнего формата к формату `{вид}:{событие}`
+///
+/// # Параметры
+///
+/// * `event` - Значение поля `event`
+///
+/// # Возвращаемое значение
+///
+/// Значение в новом формате или None, если запись уже в новом формате
+fn migrate_record_event(event: &str) -> Option<String> {
+    if parse_record_event(event).0.is_some() {
+        return None;
+    }
+
+    let (kind, name) = LEGACY_RECORD_PREFIXES
+        .iter()
+        .find_map(|(prefix, kind)| event.strip_prefix(prefix).map(|name| (*kind, name)))
+        .unwrap_or((RecordKind::Result, event));
+
+    Some(kind.record_event(name))
+}
+
 /// Запись в истории деплоев
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DeploymentRecord {
@@ -47,6 +257,56 @@ pub struct DeploymentRecord {
     pub success: bool,
     /// Дополнительные детали (опционально)
     pub details: Option<String>,
+    /// Коммит git рабочей директории на момент начала запуска
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_sha: Option<String>,
+    /// Количество команд с `ignore_errors`, завершившихся ошибкой
+    /// (частичный успех события)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warnings: Option<usize>,
+    /// Результаты отдельных команд события (включая повторные попытки)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commands: Option<Vec<CommandRecord>>,
+    /// Версия, выведенная командой `version_command` после успешного деплоя
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployed_version: Option<String>,
+}
+
+/// Результат отдельной команды в записи истории
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CommandRecord {
+    /// Имя команды
+    pub name: String,
+    /// Успешно ли выполнение
+    pub success: bool,
+    /// Код завершения команды (отсутствует, если процесс не был запущен или завершен сигналом)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Длительность выполнения в миллисекундах
+    pub duration_ms: u64,
+}
+
+impl From<&CommandResult> for CommandRecord {
+    fn from(result: &CommandResult) -> Self {
+        Self {
+            name: result.command_name.clone(),
+            success: result.success,
+            exit_code: result.exit_code,
+            duration_ms: result.duration_ms,
+        }
+    }
+}
+
+impl DeploymentRecord {
+    /// Возвращает вид записи
+    pub fn kind(&self) -> Option<RecordKind> {
+        parse_record_event(&self.event).0
+    }
+
+    /// Возвращает имя события записи без вида записи
+    pub fn event_name(&self) -> &str {
+        parse_record_event(&self.event).1
+    }
 }
 
 impl DeploymentHistory {
@@ -62,11 +322,12 @@ impl DeploymentHistory {
     /// # Параметры
     ///
     /// * `path` - Путь к файлу истории
+    /// * `format` - Формат файла истории
     ///
     /// # Возвращаемое значение
     ///
     /// История деплоев или ошибка загрузки
-    pub fn load(path: &str) -> Result<Self> {
+    pub fn load(path: &str, format: HistoryFormat) -> Result<Self> {
         if !Path::new(path).exists() {
             info!("История деплоев не найдена, создаем новую: {}", path);
             return Ok(Self::new());
@@ -75,8 +336,54 @@ impl DeploymentHistory {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Не удалось прочитать файл истории деплоев: {}", path))?;
 
-        serde_json::from_str(&content)
-            .with_context(|| format!("Неверный формат файла истории деплоев: {}", path))
+        let mut history: Self = match format {
+            HistoryFormat::Json => serde_json::from_str(&content)
+                .with_context(|| format!("Неверный формат файла истории деплоев: {}", path))?,
+            HistoryFormat::Jsonl => {
+                let mut history = Self::new();
+                for (idx, line) in content.lines().enumerate() {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let record: DeploymentRecord =
+                        serde_json::from_str(line).with_context(|| {
+                            format!(
+                                "Неверный формат записи в строке {} файла истории деплоев: {}",
+                                idx + 1,
+                                path
+                            )
+                        })?;
+                    history.add_record(record);
+                }
+                history
+            }
+        };
+
+        let migrated = history.migrate_legacy_records();
+        if migrated > 0 {
+            debug!(
+                "Записей истории в прежнем формате приведено к формату 'вид:событие': {}",
+                migrated
+            );
+        }
+
+        Ok(history)
+    }
+
+    /// Приводит записи прежнего формата к формату `{вид}:{событие}`
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Количество измененных записей
+    fn migrate_legacy_records(&mut self) -> usize {
+        let mut migrated = 0;
+        for record in self.records.values_mut().flatten() {
+            if let Some(event) = migrate_record_event(&record.event) {
+                record.event = event;
+                migrated += 1;
+            }
+        }
+        migrated
     }
 
     /// Сохраняет историю деплоев в файл
@@ -84,20 +391,88 @@ impl DeploymentHistory {
     /// # Параметры
     ///
     /// * `path` - Путь к файлу истории
+    /// * `format` - Формат файла истории
     ///
     /// # Возвращаемое значение
     ///
     /// Результат сохранения или ошибка
-    pub fn save(&self, path: &str) -> Result<()> {
-        let file = File::create(path)
+    pub fn save(&self, path: &str, format: HistoryFormat) -> Result<()> {
+        let mut file = File::create(path)
             .with_context(|| format!("Не удалось создать файл истории деплоев: {}", path))?;
 
-        serde_json::to_writer_pretty(file, &self)
-            .with_context(|| "Не удалось сериализовать историю деплоев в JSON".to_string())?;
+        match format {
+            HistoryFormat::Json => {
+                serde_json::to_writer_pretty(file, &self).with_context(|| {
+                    "Не удалось сериализовать историю деплоев в JSON".to_string()
+                })?;
+            }
+            HistoryFormat::Jsonl => {
+                // Записи разных деплоев упорядочиваются по времени
+                let mut records: Vec<&DeploymentRecord> = self.records.values().flatten().collect();
+                records.sort_by_key(|r| r.timestamp);
+
+                for record in records {
+                    let line = serde_json::to_string(record).with_context(|| {
+                        "Не удалось сериализовать запись истории деплоев".to_string()
+                    })?;
+                    writeln!(file, "{}", line).with_context(|| {
+                        format!("Не удалось записать файл истории деплоев: {}", path)
+                    })?;
+                }
+            }
+        }
 
         Ok(())
     }
 
+    /// Добавляет запись в файл истории деплоев
+    ///
+    /// В формате JSON Lines запись дописывается в конец файла без чтения и
+    /// перезаписи существующих строк, поэтому стоимость добавления не зависит от
+    /// размера истории. Формат JSON хранит записи в объекте, сгруппированными по
+    /// деплоям, и не допускает дописывания в конец: файл загружается и
+    /// перезаписывается целиком. Для больших или часто пополняемых историй
+    /// следует использовать `history_format: jsonl`.
+    ///
+    /// # Параметры
+    ///
+    /// * `path` - Путь к файлу истории
+    /// * `format` - Формат файла истории
+    /// * `record` - Запись для добавления
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Результат записи или ошибка
+    pub fn append_record(
+        path: &str,
+        format: HistoryFormat,
+        record: DeploymentRecord,
+    ) -> Result<()> {
+        match format {
+            HistoryFormat::Json => {
+                let mut history = Self::load(path, format)?;
+                history.add_record(record);
+                history.save(path, format)
+            }
+            HistoryFormat::Jsonl => {
+                let line = serde_json::to_string(&record).with_context(|| {
+                    "Не удалось сериализовать запись истории деплоев".to_string()
+                })?;
+
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| {
+                        format!("Не удалось открыть файл истории деплоев: {}", path)
+                    })?;
+
+                writeln!(file, "{}", line)
+                    .with_context(|| format!("Не удалось записать файл истории деплоев: {}", path))
+            }
+        }
+    }
+
     /// Добавляет запись в историю деплоев
     ///
     /// # Параметры
@@ -115,23 +490,183 @@ impl DeploymentHistory {
     /// # Параметры
     ///
     /// * `deployment` - Имя деплоя
+    /// * `event` - Имя события для отбора записей (None - все записи деплоя)
     /// * `limit` - Максимальное количество записей
     ///
     /// # Возвращаемое значение
     ///
     /// Вектор записей истории, ограниченный указанным лимитом
-    pub fn get_records(&self, deployment: &str, limit: usize) -> Vec<&DeploymentRecord> {
-        self.records
+    pub fn get_records(
+        &self,
+        deployment: &str,
+        event: Option<&str>,
+        limit: usize,
+    ) -> Vec<&DeploymentRecord> {
+        let records: Vec<&DeploymentRecord> = self
+            .records
             .get(deployment)
             .map(|records| {
-                let start = if records.len() > limit {
-                    records.len() - limit
-                } else {
-                    0
-                };
-                records[start..].iter().collect()
+                records
+                    .iter()
+                    .filter(|r| event.is_none_or(|event| r.event_name() == event))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let start = records.len().saturating_sub(limit);
+        records[start..].to_vec()
+    }
+
+    /// Определяет события, которые не были успешно выполнены после последнего
+    /// успешного полного деплоя
+    ///
+    /// # Параметры
+    ///
+    /// * `deployment` - Имя деплоя
+    /// * `events` - Имена событий деплоя в порядке выполнения
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Имена событий, которые нужно выполнить повторно, в исходном порядке
+    pub fn pending_events(&self, deployment: &str, events: &[String]) -> Vec<String> {
+        let records = match self.records.get(deployment) {
+            Some(records) => records,
+            None => return events.to_vec(),
+        };
+
+        // Записи после последнего успешного полного деплоя
+        let since = records
+            .iter()
+            .rposition(|r| {
+                r.success
+                    && r.kind() == Some(RecordKind::Complete)
+                    && r.event_name() == FULL_DEPLOY_EVENT
+            })
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+
+        let succeeded: HashSet<&str> = records[since..]
+            .iter()
+            .filter(|r| {
+                r.success && matches!(r.kind(), Some(RecordKind::Complete | RecordKind::Result))
             })
-            .unwrap_or_default()
+            .map(|r| r.event_name())
+            .collect();
+
+        events
+            .iter()
+            .filter(|event| !succeeded.contains(event.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Возвращает время последнего успешного выполнения деплоя
+    ///
+    /// Учитываются успешные завершения событий, цепочек команд и полных деплоев.
+    ///
+    /// # Параметры
+    ///
+    /// * `deployment` - Имя деплоя
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Временная метка (UNIX timestamp) или None, если успешных запусков не было
+    pub fn last_success_timestamp(&self, deployment: &str) -> Option<u64> {
+        self.records
+            .get(deployment)?
+            .iter()
+            .filter(|r| {
+                r.success && matches!(r.kind(), Some(RecordKind::Complete | RecordKind::Result))
+            })
+            .map(|r| r.timestamp)
+            .max()
+    }
+
+    /// Возвращает исход последнего запуска каждого события деплоя
+    ///
+    /// Учитываются записи о завершении событий и полных деплоев среди последних
+    /// `limit` записей деплоя.
+    ///
+    /// # Параметры
+    ///
+    /// * `deployment` - Имя деплоя
+    /// * `limit` - Количество последних записей для анализа
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Хэш-карта "имя события" -> успешен ли последний запуск
+    fn latest_outcomes(&self, deployment: &str, limit: usize) -> BTreeMap<String, bool> {
+        self.get_records(deployment, None, limit)
+            .into_iter()
+            .filter(|r| {
+                matches!(
+                    r.kind(),
+                    Some(
+                        RecordKind::Complete
+                            | RecordKind::Failed
+                            | RecordKind::Result
+                            | RecordKind::Error
+                    )
+                )
+            })
+            .map(|r| (r.event_name().to_string(), r.success))
+            .collect()
+    }
+
+    /// Сопоставляет последние исходы событий двух деплоев
+    ///
+    /// # Параметры
+    ///
+    /// * `a` - Имя первого деплоя
+    /// * `b` - Имя второго деплоя
+    /// * `limit` - Количество последних записей каждого деплоя для анализа
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Исходы всех событий, встречающихся в истории хотя бы одного деплоя,
+    /// в алфавитном порядке событий
+    pub fn diff_deployments(&self, a: &str, b: &str, limit: usize) -> Vec<EventOutcomeDiff> {
+        let outcomes_a = self.latest_outcomes(a, limit);
+        let outcomes_b = self.latest_outcomes(b, limit);
+
+        let mut events: Vec<&String> = outcomes_a.keys().chain(outcomes_b.keys()).collect();
+        events.sort();
+        events.dedup();
+
+        events
+            .into_iter()
+            .map(|event| EventOutcomeDiff {
+                event: event.clone(),
+                a: outcomes_a.get(event).copied(),
+                b: outcomes_b.get(event).copied(),
+            })
+            .collect()
+    }
+
+    /// Возвращает деплои, последний полный запуск которых завершился ошибкой
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Отсортированный список имен деплоев
+    pub fn latest_failed_deployments(&self) -> Vec<String> {
+        let mut failed: Vec<String> = self
+            .records
+            .iter()
+            .filter_map(|(deployment, records)| {
+                records
+                    .iter()
+                    .filter(|r| {
+                        r.event_name() == FULL_DEPLOY_EVENT
+                            && matches!(r.kind(), Some(RecordKind::Complete | RecordKind::Failed))
+                    })
+                    .max_by_key(|r| r.timestamp)
+                    .filter(|r| !r.success)
+                    .map(|_| deployment.clone())
+            })
+            .collect();
+
+        failed.sort();
+        failed
     }
 
     /// Очищает историю для указанного деплоя
@@ -147,6 +682,84 @@ impl DeploymentHistory {
     pub fn clear_all(&mut self) {
         self.records.clear();
     }
+
+    /// Удаляет записи, созданные раньше указанного момента
+    ///
+    /// # Параметры
+    ///
+    /// * `cutoff_ts` - Граница (UNIX timestamp), записи старше которой удаляются
+    /// * `deployment` - Имя деплоя (если None, обрабатываются все деплои)
+    ///
+    /// # Возвращаемое значение
+    ///
+    /// Количество удаленных записей
+    pub fn prune(&mut self, cutoff_ts: u64, deployment: Option<&str>) -> usize {
+        let mut removed = 0;
+
+        for (name, records) in self.records.iter_mut() {
+            if deployment.is_some_and(|d| d != name) {
+                continue;
+            }
+
+            let before = records.len();
+            records.retain(|r| r.timestamp >= cutoff_ts);
+            removed += before - records.len();
+        }
+
+        self.records.retain(|_, records| !records.is_empty());
+
+        removed
+    }
+}
+
+/// Определяет формат файла истории из глобальных настроек
+///
+/// # Возвращаемое значение
+///
+/// Формат файла истории (JSON по умолчанию)
+pub fn history_format() -> HistoryFormat {
+    match settings::get_settings(settings::DEFAULT_SETTINGS_PATH) {
+        Ok(settings) => settings.history_format,
+        Err(e) => {
+            warn!(
+                "Ошибка загрузки настроек, используется формат истории по умолчанию: {}",
+                e
+            );
+            HistoryFormat::default()
+        }
+    }
+}
+
+/// Определяет максимальную длину поля `details` из глобальных настроек
+///
+/// # Возвращаемое значение
+///
+/// Максимальная длина в символах (0 - без ограничения)
+fn max_detail_len() -> usize {
+    settings::get_settings(settings::DEFAULT_SETTINGS_PATH)
+        .map(|settings| settings.max_detail_len)
+        .unwrap_or_default()
+}
+
+/// Обрезает детали записи истории до `max_len` символов, добавляя отметку об обрезке
+///
+/// # Параметры
+///
+/// * `details` - Детали записи
+/// * `max_len` - Максимальная длина в символах (0 - без ограничения)
+///
+/// # Возвращаемое значение
+///
+/// Исходные детали или их начало с отметкой о количестве обрезанных символов
+fn truncate_details(details: String, max_len: usize) -> String {
+    let len = details.chars().count();
+    if max_len == 0 || len <= max_len {
+        return details;
+    }
+
+    let mut truncated: String = details.chars().take(max_len).collect();
+    truncated.push_str(&format!("{}{}]", DETAILS_TRUNCATED_MARKER, len - max_len));
+    truncated
 }
 
 /// Записывает событие деплоя в историю
@@ -155,6 +768,7 @@ impl DeploymentHistory {
 ///
 /// * `path` - Путь к файлу истории
 /// * `deployment` - Имя деплоя
+/// * `kind` - Вид записи
 /// * `event` - Имя события
 /// * `success` - Успешность выполнения
 /// * `details` - Дополнительные детали (опционально)
@@ -165,27 +779,95 @@ impl DeploymentHistory {
 pub fn record_deployment(
     path: &str,
     deployment: &str,
+    kind: RecordKind,
     event: &str,
     success: bool,
     details: Option<String>,
 ) -> Result<()> {
-    let mut history = DeploymentHistory::load(path)?;
+    let record = new_record(deployment, kind, event, success, details);
+    DeploymentHistory::append_record(path, history_format(), record)
+}
+
+/// Записывает начало запуска события с коммитом git, который развертывается
+///
+/// # Параметры
+///
+/// * `path` - Путь к файлу истории
+/// * `deployment` - Имя деплоя
+/// * `event` - Имя события
+/// * `git_sha` - Коммит git рабочей директории деплоя (None - не репозиторий git)
+///
+/// # Возвращаемое значение
+///
+/// Результат записи или ошибка
+pub fn record_deployment_start(
+    path: &str,
+    deployment: &str,
+    event: &str,
+    git_sha: Option<String>,
+) -> Result<()> {
+    let mut record = new_record(deployment, RecordKind::Start, event, true, None);
+    record.git_sha = git_sha;
+    DeploymentHistory::append_record(path, history_format(), record)
+}
+
+/// Записывает успешное завершение события с развернутой версией
+///
+/// # Параметры
+///
+/// * `path` - Путь к файлу истории
+/// * `deployment` - Имя деплоя
+/// * `event` - Имя события
+/// * `details` - Дополнительные детали
+/// * `deployed_version` - Версия, выведенная `version_command` (None - не определена)
+///
+/// # Возвращаемое значение
+///
+/// Результат записи или ошибка
+pub fn record_deployment_complete(
+    path: &str,
+    deployment: &str,
+    event: &str,
+    details: Option<String>,
+    deployed_version: Option<String>,
+) -> Result<()> {
+    let mut record = new_record(deployment, RecordKind::Complete, event, true, details);
+    record.deployed_version = deployed_version;
+    DeploymentHistory::append_record(path, history_format(), record)
+}
 
-    let record = DeploymentRecord {
+/// Создает запись истории с текущим временем
+///
+/// Детали скрываются по `redact_patterns` и обрезаются по `max_detail_len`; итоги
+/// запуска всех деплоев не обрезаются, чтобы их можно было разобрать.
+fn new_record(
+    deployment: &str,
+    kind: RecordKind,
+    event: &str,
+    success: bool,
+    details: Option<String>,
+) -> DeploymentRecord {
+    DeploymentRecord {
         deployment: deployment.to_string(),
-        event: event.to_string(),
+        event: kind.record_event(event),
         timestamp: SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs(),
         success,
-        details,
-    };
-
-    history.add_record(record);
-    history.save(path)?;
-
-    Ok(())
+        details: details.map(|details| {
+            let details = settings::redact(&details);
+            if deployment == ALL_DEPLOYMENTS {
+                details
+            } else {
+                truncate_details(details, max_detail_len())
+            }
+        }),
+        git_sha: None,
+        warnings: None,
+        commands: None,
+        deployed_version: None,
+    }
 }
 
 /// Очищает историю деплоев
@@ -199,7 +881,8 @@ pub fn record_deployment(
 ///
 /// Результат очистки или ошибка
 pub fn clear_deployment_history(path: &str, deployment: Option<&str>) -> Result<()> {
-    let mut history = DeploymentHistory::load(path)?;
+    let format = history_format();
+    let mut history = DeploymentHistory::load(path, format)?;
 
     match deployment {
         Some(dep) => {
@@ -210,11 +893,72 @@ pub fn clear_deployment_history(path: &str, deployment: Option<&str>) -> Result<
         }
     }
 
-    history.save(path)?;
+    history.save(path, format)?;
 
     Ok(())
 }
 
+/// Удаляет из истории записи старше указанного возраста
+///
+/// # Параметры
+///
+/// * `path` - Путь к файлу истории
+/// * `max_age_secs` - Максимальный возраст записей в секундах
+/// * `deployment` - Имя деплоя (если None, обрабатываются все деплои)
+///
+/// # Возвращаемое значение
+///
+/// Количество удаленных записей или ошибка
+pub fn prune_deployment_history(
+    path: &str,
+    max_age_secs: u64,
+    deployment: Option<&str>,
+) -> Result<usize> {
+    let format = history_format();
+    let mut history = DeploymentHistory::load(path, format)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let removed = history.prune(now.saturating_sub(max_age_secs), deployment);
+
+    if removed > 0 {
+        history.save(path, format)?;
+    }
+
+    Ok(removed)
+}
+
+/// Находит деплои, последний полный запуск которых завершился ошибкой
+///
+/// # Параметры
+///
+/// * `path` - Путь к файлу истории
+///
+/// # Возвращаемое значение
+///
+/// Отсортированный список имен деплоев или ошибка загрузки истории
+pub fn latest_failed_deployments(path: &str) -> Result<Vec<String>> {
+    let history = DeploymentHistory::load(path, history_format())?;
+    Ok(history.latest_failed_deployments())
+}
+
+/// Возвращает время последнего успешного выполнения деплоя из файла истории
+///
+/// # Параметры
+///
+/// * `path` - Путь к файлу истории
+/// * `deployment` - Имя деплоя
+///
+/// # Возвращаемое значение
+///
+/// Временная метка (None, если успешных запусков не было) или ошибка загрузки истории
+pub fn last_success_timestamp(path: &str, deployment: &str) -> Result<Option<u64>> {
+    let history = DeploymentHistory::load(path, history_format())?;
+    Ok(history.last_success_timestamp(deployment))
+}
+
 /// Записывает результат выполнения цепочки команд в историю
 ///
 /// # Параметры
@@ -234,7 +978,19 @@ pub fn record_chain_result(
     result: &ChainExecutionResult,
 ) -> Result<()> {
     let success = result.success;
-    let details = if success {
+    // Ошибки, оставшиеся в успешной цепочке, проигнорированы через ignore_errors
+    let ignored = if success {
+        failed_commands(&result.results).len()
+    } else {
+        0
+    };
+    let details = if ignored > 0 {
+        Some(format!(
+            "Выполнено {} команд, проигнорировано ошибок: {}",
+            result.results.len(),
+            ignored
+        ))
+    } else if success {
         Some(format!("Успешно выполнено {} команд", result.results.len()))
     } else {
         match &result.error {
@@ -252,7 +1008,34 @@ pub fn record_chain_result(
         }
     };
 
-    record_deployment(path, deployment, event, success, details)
+    let mut record = new_record(deployment, RecordKind::Result, event, success, details);
+    record.warnings = Some(ignored).filter(|count| *count > 0);
+    record.commands = Some(result.results.iter().map(CommandRecord::from).collect());
+    DeploymentHistory::append_record(path, history_format(), record)
+}
+
+/// Возвращает команды, последняя попытка выполнения которых завершилась ошибкой
+///
+/// Команда, успешно выполненная при повторе, не считается упавшей.
+///
+/// # Параметры
+///
+/// * `results` - Результаты выполнения команд цепочки, включая повторы
+///
+/// # Возвращаемое значение
+///
+/// Имена упавших команд в порядке выполнения
+pub fn failed_commands(results: &[CommandResult]) -> Vec<&str> {
+    let mut failed: Vec<&str> = Vec::new();
+
+    for result in results {
+        failed.retain(|name| *name != result.command_name);
+        if !result.success {
+            failed.push(&result.command_name);
+        }
+    }
+
+    failed
 }
 
 /// Форматирует результат выполнения команды в строку
@@ -277,3 +1060,591 @@ fn format_command_result(result: &CommandResult) -> String {
         )
     }
 }
+
+/// Возвращает путь к файлу снимков конфигурации, расположенному рядом с историей
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории
+///
+/// # Возвращаемое значение
+///
+/// Путь к файлу снимков (например, `deploy-history.snapshots.json`)
+fn snapshots_path(history_path: &str) -> String {
+    Path::new(history_path)
+        .with_extension("snapshots.json")
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Загружает снимки конфигурации деплоев
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории
+///
+/// # Возвращаемое значение
+///
+/// Хэш-карта "имя деплоя" -> нормализованная конфигурация или ошибка
+fn load_config_snapshots(history_path: &str) -> Result<HashMap<String, String>> {
+    let path = snapshots_path(history_path);
+    if !Path::new(&path).exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Не удалось прочитать файл снимков конфигурации: {}", path))?;
+
+    serde_json::from_str(&content)
+        .with_context(|| format!("Неверный формат файла снимков конфигурации: {}", path))
+}
+
+/// Преобразует конфигурацию деплоя в нормализованный YAML
+///
+/// Ключи сортируются, поэтому результат не зависит от порядка полей в исходном файле.
+///
+/// # Параметры
+///
+/// * `deployment` - Конфигурация деплоя
+///
+/// # Возвращаемое значение
+///
+/// Нормализованное текстовое представление или ошибка
+pub fn normalize_deployment_config(deployment: &Deployment) -> Result<String> {
+    let value =
+        serde_json::to_value(deployment).context("Не удалось сериализовать конфигурацию деплоя")?;
+
+    serde_yaml::to_string(&value).context("Не удалось преобразовать конфигурацию деплоя в YAML")
+}
+
+/// Сохраняет снимок конфигурации деплоя, использованной при запуске
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории
+/// * `deployment` - Конфигурация деплоя
+///
+/// # Возвращаемое значение
+///
+/// Результат сохранения или ошибка
+pub fn save_config_snapshot(history_path: &str, deployment: &Deployment) -> Result<()> {
+    let mut snapshots = load_config_snapshots(history_path)?;
+    snapshots.insert(
+        deployment.name.clone(),
+        normalize_deployment_config(deployment)?,
+    );
+
+    let path = snapshots_path(history_path);
+    let content = serde_json::to_string_pretty(&snapshots)
+        .context("Не удалось сериализовать снимки конфигурации")?;
+    fs::write(&path, content)
+        .with_context(|| format!("Не удалось сохранить файл снимков конфигурации: {}", path))
+}
+
+/// Загружает снимок конфигурации деплоя с последнего запуска
+///
+/// # Параметры
+///
+/// * `history_path` - Путь к файлу истории
+/// * `deployment_name` - Имя деплоя
+///
+/// # Возвращаемое значение
+///
+/// Нормализованная конфигурация или None, если деплой еще не запускался
+pub fn load_config_snapshot(history_path: &str, deployment_name: &str) -> Result<Option<String>> {
+    Ok(load_config_snapshots(history_path)?.remove(deployment_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: RecordKind, event: &str, timestamp: u64, success: bool) -> DeploymentRecord {
+        DeploymentRecord {
+            deployment: "app".to_string(),
+            event: kind.record_event(event),
+            timestamp,
+            success,
+            details: None,
+            git_sha: None,
+            warnings: None,
+            commands: None,
+            deployed_version: None,
+        }
+    }
+
+    fn history(records: Vec<DeploymentRecord>) -> DeploymentHistory {
+        let mut history = DeploymentHistory::new();
+        for record in records {
+            history.add_record(record);
+        }
+        history
+    }
+
+    fn events(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn pending_events_after_last_full_deploy() {
+        let history = history(vec![
+            record(RecordKind::Complete, "build", 10, true),
+            record(RecordKind::Complete, "test", 11, true),
+            record(RecordKind::Complete, "deploy", 12, true),
+            record(RecordKind::Complete, FULL_DEPLOY_EVENT, 13, true),
+            record(RecordKind::Complete, "build", 20, true),
+            record(RecordKind::Failed, "test", 21, false),
+        ]);
+
+        let pending = history.pending_events("app", &events(&["build", "test", "deploy"]));
+
+        assert_eq!(pending, events(&["test", "deploy"]));
+    }
+
+    #[test]
+    fn pending_events_without_successful_full_deploy() {
+        let history = history(vec![
+            record(RecordKind::Complete, "build", 10, true),
+            record(RecordKind::Failed, FULL_DEPLOY_EVENT, 11, false),
+            record(RecordKind::Result, "test", 12, true),
+        ]);
+
+        // Без успешного полного деплоя учитываются все записи
+        assert_eq!(
+            history.pending_events("app", &events(&["build", "test", "deploy"])),
+            events(&["deploy"])
+        );
+        assert_eq!(
+            history.pending_events("other", &events(&["build"])),
+            events(&["build"])
+        );
+    }
+
+    #[test]
+    fn history_round_trips_in_both_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in [HistoryFormat::Json, HistoryFormat::Jsonl] {
+            let path = dir.path().join(format!("history-{:?}", format));
+            let path = path.to_str().unwrap();
+            let original = history(vec![
+                record(RecordKind::Start, "deploy", 10, true),
+                record(RecordKind::Complete, "deploy", 11, true),
+            ]);
+
+            original.save(path, format).unwrap();
+            let loaded = DeploymentHistory::load(path, format).unwrap();
+
+            let events: Vec<&str> = loaded
+                .get_records("app", None, 10)
+                .iter()
+                .map(|r| r.event.as_str())
+                .collect();
+            assert_eq!(events, vec!["start:deploy", "complete:deploy"]);
+        }
+    }
+
+    #[test]
+    fn jsonl_append_keeps_existing_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        // Нестандартное форматирование строки сохранится только без перезаписи файла
+        let existing = r#"{ "deployment": "app", "event": "start:deploy", "timestamp": 1, "success": true, "details": null }"#;
+        fs::write(&path, format!("{}\n", existing)).unwrap();
+        let path = path.to_str().unwrap();
+
+        DeploymentHistory::append_record(
+            path,
+            HistoryFormat::Jsonl,
+            record(RecordKind::Complete, "deploy", 2, true),
+        )
+        .unwrap();
+
+        let content = fs::read_to_string(path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], existing);
+
+        let loaded = DeploymentHistory::load(path, HistoryFormat::Jsonl).unwrap();
+        assert_eq!(loaded.get_records("app", None, 10).len(), 2);
+    }
+
+    #[test]
+    fn jsonl_append_record_does_not_read_existing_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        // Большая история с нечитаемой строкой: при загрузке файла запись завершилась бы ошибкой
+        let line = serde_json::to_string(&record(RecordKind::Start, "deploy", 1, true)).unwrap();
+        let mut existing = format!("{}\n", line).repeat(10_000);
+        existing.push_str("не JSON\n");
+        fs::write(&path, &existing).unwrap();
+        let path = path.to_str().unwrap();
+
+        let appended = record(RecordKind::Complete, "deploy", 2, true);
+        let appended_line = serde_json::to_string(&appended).unwrap();
+        DeploymentHistory::append_record(path, HistoryFormat::Jsonl, appended).unwrap();
+
+        let content = fs::read_to_string(path).unwrap();
+        assert_eq!(content.len(), existing.len() + appended_line.len() + 1);
+        assert!(content.starts_with(&existing));
+        assert!(content.ends_with(&format!("{}\n", appended_line)));
+    }
+
+    #[test]
+    fn prune_removes_only_old_records() {
+        let mut history = history(vec![
+            record(RecordKind::Start, "deploy", 10, true),
+            record(RecordKind::Complete, "deploy", 20, true),
+            record(RecordKind::Start, "deploy", 30, true),
+            DeploymentRecord {
+                deployment: "db".to_string(),
+                ..record(RecordKind::Start, "migrate", 10, true)
+            },
+        ]);
+
+        assert_eq!(history.prune(20, Some("app")), 1);
+        let timestamps: Vec<u64> = history
+            .get_records("app", None, 10)
+            .iter()
+            .map(|r| r.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![20, 30]);
+        assert_eq!(history.get_records("db", None, 10).len(), 1);
+
+        assert_eq!(history.prune(25, None), 2);
+        assert_eq!(history.get_records("app", None, 10).len(), 1);
+        assert!(history.get_records("db", None, 10).is_empty());
+    }
+
+    #[test]
+    fn latest_failed_deployments_uses_last_full_deploy() {
+        let in_deployment = |deployment: &str, record: DeploymentRecord| DeploymentRecord {
+            deployment: deployment.to_string(),
+            ..record
+        };
+        let history = history(vec![
+            // Последний полный деплой api завершился ошибкой
+            in_deployment(
+                "api",
+                record(RecordKind::Complete, FULL_DEPLOY_EVENT, 10, true),
+            ),
+            in_deployment(
+                "api",
+                record(RecordKind::Failed, FULL_DEPLOY_EVENT, 20, false),
+            ),
+            // web исправлен повторным запуском
+            in_deployment(
+                "web",
+                record(RecordKind::Failed, FULL_DEPLOY_EVENT, 10, false),
+            ),
+            in_deployment(
+                "web",
+                record(RecordKind::Complete, FULL_DEPLOY_EVENT, 20, true),
+            ),
+            // Ошибка отдельного события не считается ошибкой полного деплоя
+            in_deployment(
+                "db",
+                record(RecordKind::Complete, FULL_DEPLOY_EVENT, 10, true),
+            ),
+            in_deployment("db", record(RecordKind::Failed, "migrate", 20, false)),
+            in_deployment(
+                "cache",
+                record(RecordKind::Failed, FULL_DEPLOY_EVENT, 30, false),
+            ),
+        ]);
+
+        assert_eq!(history.latest_failed_deployments(), vec!["api", "cache"]);
+    }
+
+    #[test]
+    fn config_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let history_path = dir.path().join("history.json");
+        let history_path = history_path.to_str().unwrap();
+        let deployment: Deployment =
+            serde_yaml::from_str("name: app\nevents:\n  - name: deploy\n    commands: []\n")
+                .unwrap();
+
+        assert_eq!(load_config_snapshot(history_path, "app").unwrap(), None);
+
+        save_config_snapshot(history_path, &deployment).unwrap();
+
+        assert_eq!(
+            load_config_snapshot(history_path, "app").unwrap(),
+            Some(normalize_deployment_config(&deployment).unwrap())
+        );
+    }
+
+    #[test]
+    fn get_records_filters_by_event_name() {
+        let history = history(vec![
+            record(RecordKind::Start, "deploy", 10, true),
+            record(RecordKind::Start, "build", 20, true),
+            record(RecordKind::Result, "deploy", 30, true),
+            record(RecordKind::Complete, "deploy", 40, true),
+            record(RecordKind::Complete, "build", 50, true),
+        ]);
+
+        let timestamps = |event, limit| -> Vec<u64> {
+            history
+                .get_records("app", event, limit)
+                .iter()
+                .map(|r| r.timestamp)
+                .collect()
+        };
+
+        assert_eq!(timestamps(Some("deploy"), 10), vec![10, 30, 40]);
+        assert_eq!(timestamps(Some("deploy"), 2), vec![30, 40]);
+        assert_eq!(timestamps(Some("build"), 10), vec![20, 50]);
+        assert_eq!(timestamps(None, 10).len(), 5);
+        assert!(timestamps(Some("migrate"), 10).is_empty());
+    }
+
+    #[test]
+    fn record_events_use_kind_prefix_format() {
+        assert_eq!(RecordKind::Start.record_event("deploy"), "start:deploy");
+        assert_eq!(
+            RecordKind::Complete.record_event(FULL_DEPLOY_EVENT),
+            "complete:full-deploy"
+        );
+        assert_eq!(
+            parse_record_event("failed:pre-deploy"),
+            (Some(RecordKind::Failed), "pre-deploy")
+        );
+        assert_eq!(parse_record_event("deploy"), (None, "deploy"));
+    }
+
+    #[test]
+    fn last_success_timestamp_ignores_failures_and_starts() {
+        let recorded = history(vec![
+            record(RecordKind::Complete, "deploy", 10, true),
+            record(RecordKind::Result, "deploy", 20, true),
+            record(RecordKind::Failed, "deploy", 30, false),
+            record(RecordKind::Start, "deploy", 40, true),
+        ]);
+
+        assert_eq!(recorded.last_success_timestamp("app"), Some(20));
+        assert_eq!(recorded.last_success_timestamp("other"), None);
+        assert_eq!(
+            history(vec![record(RecordKind::Failed, "deploy", 5, false)])
+                .last_success_timestamp("app"),
+            None
+        );
+    }
+
+    #[test]
+    fn all_deployments_summary_round_trips_through_details() {
+        let summary = AllDeploymentsSummary::new(3, vec!["api".to_string()]);
+        assert_eq!(summary.succeeded, 2);
+        assert_eq!(summary.failed, 1);
+
+        let details = summary.to_details();
+        let parsed: serde_json::Value = serde_json::from_str(&details).unwrap();
+        assert_eq!(parsed["total"], 3);
+        assert_eq!(parsed["failed_deployments"][0], "api");
+        assert_eq!(AllDeploymentsSummary::from_details(&details), Some(summary));
+
+        assert_eq!(
+            AllDeploymentsSummary::from_details("Деплои с ошибками: api"),
+            None
+        );
+    }
+
+    #[test]
+    fn all_deployments_summary_display_lists_failed_deployments() {
+        assert_eq!(
+            AllDeploymentsSummary::new(2, Vec::new()).to_string(),
+            "всего 2, успешно 2, с ошибками 0"
+        );
+        assert_eq!(
+            AllDeploymentsSummary::new(3, vec!["api".to_string(), "web".to_string()]).to_string(),
+            "всего 3, успешно 1, с ошибками 2: api, web"
+        );
+    }
+
+    #[test]
+    fn command_records_are_saved_and_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let commands = vec![
+            CommandRecord {
+                name: "app_deploy_cmd_1".to_string(),
+                success: true,
+                exit_code: Some(0),
+                duration_ms: 12,
+            },
+            CommandRecord {
+                name: "app_deploy_cmd_2".to_string(),
+                success: false,
+                exit_code: None,
+                duration_ms: 3,
+            },
+        ];
+
+        for (format, file) in [
+            (HistoryFormat::Json, "history.json"),
+            (HistoryFormat::Jsonl, "history.jsonl"),
+        ] {
+            let path = dir.path().join(file);
+            let path = path.to_str().unwrap();
+            let mut result = record(RecordKind::Result, "deploy", 1, false);
+            result.commands = Some(commands.clone());
+            DeploymentHistory::append_record(
+                path,
+                format,
+                record(RecordKind::Start, "deploy", 1, true),
+            )
+            .unwrap();
+            DeploymentHistory::append_record(path, format, result).unwrap();
+
+            let history = DeploymentHistory::load(path, format).unwrap();
+            let records = history.get_records("app", None, 10);
+            let reloaded = records
+                .iter()
+                .find(|r| r.kind() == Some(RecordKind::Result))
+                .unwrap();
+            assert_eq!(reloaded.commands.as_ref(), Some(&commands));
+            assert!(records
+                .iter()
+                .any(|r| r.kind() == Some(RecordKind::Start) && r.commands.is_none()));
+        }
+    }
+
+    #[test]
+    fn diff_deployments_reports_divergent_latest_outcomes() {
+        let staging = |kind, event: &str, timestamp, success| {
+            let mut record = record(kind, event, timestamp, success);
+            record.deployment = "staging".to_string();
+            record
+        };
+        let history = history(vec![
+            record(RecordKind::Failed, "build", 1, false),
+            record(RecordKind::Complete, "build", 2, true),
+            record(RecordKind::Result, "deploy", 3, true),
+            record(RecordKind::Start, "migrate", 4, true),
+            staging(RecordKind::Complete, "build", 1, true),
+            staging(RecordKind::Failed, "deploy", 2, false),
+            staging(RecordKind::Complete, "smoke", 3, true),
+        ]);
+
+        let diffs = history.diff_deployments("app", "staging", 50);
+
+        assert_eq!(
+            diffs,
+            vec![
+                EventOutcomeDiff {
+                    event: "build".to_string(),
+                    a: Some(true),
+                    b: Some(true),
+                },
+                EventOutcomeDiff {
+                    event: "deploy".to_string(),
+                    a: Some(true),
+                    b: Some(false),
+                },
+                EventOutcomeDiff {
+                    event: "smoke".to_string(),
+                    a: None,
+                    b: Some(true),
+                },
+            ]
+        );
+        let divergent: Vec<&str> = diffs
+            .iter()
+            .filter(|diff| diff.differs())
+            .map(|diff| diff.event.as_str())
+            .collect();
+        assert_eq!(divergent, vec!["deploy", "smoke"]);
+    }
+
+    #[test]
+    fn long_details_are_truncated_with_marker() {
+        let long = "ошибка ".repeat(10);
+
+        assert_eq!(
+            truncate_details(long.clone(), 6),
+            format!("ошибка{}64]", DETAILS_TRUNCATED_MARKER)
+        );
+        assert_eq!(truncate_details("ошибка".to_string(), 6), "ошибка");
+        assert_eq!(truncate_details(long.clone(), 0), long);
+    }
+
+    #[test]
+    fn git_sha_is_optional_in_history_records() {
+        let legacy: DeploymentRecord = serde_json::from_str(
+            r#"{"deployment":"app","event":"start:deploy","timestamp":1,"success":true}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.git_sha, None);
+        assert!(!serde_json::to_string(&legacy).unwrap().contains("git_sha"));
+
+        let mut record = record(RecordKind::Start, "deploy", 1, true);
+        record.git_sha = Some("0123456789abcdef".to_string());
+        let json = serde_json::to_string(&record).unwrap();
+        let parsed: DeploymentRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.git_sha.as_deref(), Some("0123456789abcdef"));
+    }
+
+    #[test]
+    fn deployed_version_is_optional_in_history_records() {
+        let legacy: DeploymentRecord = serde_json::from_str(
+            r#"{"deployment":"app","event":"complete:full-deploy","timestamp":1,"success":true}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.deployed_version, None);
+        assert!(!serde_json::to_string(&legacy)
+            .unwrap()
+            .contains("deployed_version"));
+
+        let mut record = record(RecordKind::Complete, FULL_DEPLOY_EVENT, 1, true);
+        record.deployed_version = Some("1.2.3".to_string());
+        let json = serde_json::to_string(&record).unwrap();
+        let parsed: DeploymentRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.deployed_version.as_deref(), Some("1.2.3"));
+    }
+
+    #[test]
+    fn legacy_record_events_are_migrated_on_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+        let legacy = [
+            "start-deploy",
+            "success-complete-deploy",
+            "failed-failed-deploy",
+            "error-deploy",
+            "deploy",
+            "start-full-deploy",
+            "result:deploy",
+        ];
+        let lines: Vec<String> = legacy
+            .iter()
+            .enumerate()
+            .map(|(idx, event)| {
+                format!(
+                    r#"{{"deployment":"app","event":"{}","timestamp":{},"success":true,"details":null}}"#,
+                    event, idx
+                )
+            })
+            .collect();
+        fs::write(&path, lines.join("\n")).unwrap();
+
+        let history =
+            DeploymentHistory::load(path.to_str().unwrap(), HistoryFormat::Jsonl).unwrap();
+        let events: Vec<&str> = history
+            .get_records("app", None, 10)
+            .iter()
+            .map(|r| r.event.as_str())
+            .collect();
+
+        assert_eq!(
+            events,
+            vec![
+                "start:deploy",
+                "complete:deploy",
+                "failed:deploy",
+                "error:deploy",
+                "result:deploy",
+                "start:full-deploy",
+                "result:deploy",
+            ]
+        );
+    }
+}
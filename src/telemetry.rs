@@ -0,0 +1,479 @@
+/*!
+# Модуль Telemetry
+
+Модуль `telemetry` отправляет спаны выполнения запуска в формате OpenTelemetry
+(OTLP/HTTP JSON), если в настройках задан `otel_endpoint`:
+
+- Спан деплоя, объединяющий спаны его событий
+- Спан события с вложенными спанами команд, длительностями и статусом
+- Отправка спанов фоновой задачей: спаны, накопившиеся за время предыдущей
+  отправки, отправляются одним запросом, а выполнение событий не ждет сборщика
+
+Ошибки отправки выводятся как предупреждения и не влияют на результат деплоя.
+
+## Основные функции
+
+- `enable` - включает отправку спанов на указанный адрес
+- `record_event` - ставит в очередь спаны события и его команд
+- `finish` - ставит в очередь спаны деплоев и дожидается отправки всех спанов
+*/
+
+use command_system::CommandResult;
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Имя сервиса в ресурсе спанов
+const SERVICE_NAME: &str = "deploy-commander";
+
+/// Время ожидания ответа сборщика спанов
+const EXPORT_TIMEOUT_SECS: u64 = 5;
+
+/// Спан деплоя, открытый до конца запуска
+struct DeploymentSpan {
+    trace_id: String,
+    span_id: String,
+    start_ns: u128,
+    end_ns: u128,
+    success: bool,
+}
+
+/// Состояние отправки: очередь фоновой задачи и открытые спаны деплоев
+struct Telemetry {
+    /// Очередь спанов для отправки (None после `finish`)
+    sender: Option<mpsc::UnboundedSender<Vec<Value>>>,
+    /// Фоновая задача отправки спанов
+    exporter: Option<JoinHandle<()>>,
+    deployments: HashMap<String, DeploymentSpan>,
+}
+
+/// Отправка спанов текущего запуска (включается настройкой `otel_endpoint`)
+static TELEMETRY: OnceLock<Mutex<Telemetry>> = OnceLock::new();
+
+/// Включает отправку спанов сборщику OpenTelemetry
+///
+/// Запускает фоновую задачу отправки, поэтому должна вызываться внутри среды
+/// выполнения tokio.
+///
+/// # Параметры
+///
+/// * `endpoint` - Адрес OTLP/HTTP сборщика (например, `http://localhost:4318`)
+pub fn enable(endpoint: &str) {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(EXPORT_TIMEOUT_SECS))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Не удалось создать клиент для отправки спанов: {}", e);
+            return;
+        }
+    };
+
+    let endpoint = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    info!("Спаны выполнения будут отправлены на {}", endpoint);
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let exporter = tokio::spawn(export_batches(receiver, move |spans| {
+        let client = client.clone();
+        let endpoint = endpoint.clone();
+        async move { export(&client, &endpoint, spans).await }
+    }));
+
+    let _ = TELEMETRY.set(Mutex::new(Telemetry {
+        sender: Some(sender),
+        exporter: Some(exporter),
+        deployments: HashMap::new(),
+    }));
+}
+
+/// Ставит в очередь отправки спаны события и его команд
+///
+/// Команды события выполняются последовательно, поэтому их спаны располагаются
+/// друг за другом от начала события. Ничего не делает, если отправка не включена.
+///
+/// # Параметры
+///
+/// * `deployment` - Имя деплоя
+/// * `event` - Имя события
+/// * `duration` - Длительность выполнения события
+/// * `results` - Результаты выполненных команд события
+/// * `failure` - Сообщение об ошибке или None при успехе
+pub fn record_event(
+    deployment: &str,
+    event: &str,
+    duration: Duration,
+    results: &[CommandResult],
+    failure: Option<&str>,
+) {
+    let Some(telemetry) = TELEMETRY.get() else {
+        return;
+    };
+
+    let mut telemetry = telemetry.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(sender) = telemetry.sender.clone() else {
+        return;
+    };
+
+    let commands: Vec<CommandSpan> = results
+        .iter()
+        .map(|result| CommandSpan {
+            name: &result.command_name,
+            duration_ms: result.duration_ms,
+            exit_code: result.exit_code,
+            failure: (!result.success).then(|| result.error.as_deref().unwrap_or_default()),
+        })
+        .collect();
+    let spans = event_spans(
+        &mut telemetry.deployments,
+        deployment,
+        event,
+        duration,
+        &commands,
+        failure,
+    );
+
+    let _ = sender.send(spans);
+}
+
+/// Ставит в очередь спаны деплоев и дожидается отправки всех спанов запуска
+///
+/// Вызывается в конце запуска, в том числе перед завершением программы с ошибкой.
+/// Ничего не делает, если отправка не включена или уже завершена.
+pub async fn finish() {
+    let Some(telemetry) = TELEMETRY.get() else {
+        return;
+    };
+
+    let exporter = {
+        let mut telemetry = telemetry.lock().unwrap_or_else(|e| e.into_inner());
+        let spans = deployment_spans(&mut telemetry.deployments);
+        // Закрытие очереди завершает фоновую задачу после отправки оставшихся спанов
+        if let Some(sender) = telemetry.sender.take() {
+            if !spans.is_empty() {
+                let _ = sender.send(spans);
+            }
+        }
+        telemetry.exporter.take()
+    };
+
+    if let Some(exporter) = exporter {
+        if let Err(e) = exporter.await {
+            warn!("Фоновая отправка спанов завершилась с ошибкой: {}", e);
+        }
+    }
+}
+
+/// Команда события, для которой формируется спан
+struct CommandSpan<'a> {
+    name: &'a str,
+    duration_ms: u64,
+    exit_code: Option<i32>,
+    failure: Option<&'a str>,
+}
+
+/// Формирует спаны события и его команд, открывая спан деплоя при первом событии
+///
+/// # Параметры
+///
+/// * `deployments` - Открытые спаны деплоев
+/// * `deployment` - Имя деплоя
+/// * `event` - Имя события
+/// * `duration` - Длительность выполнения события
+/// * `commands` - Выполненные команды события
+/// * `failure` - Сообщение об ошибке или None при успехе
+///
+/// # Возвращаемое значение
+///
+/// Спан события и спаны его команд
+fn event_spans(
+    deployments: &mut HashMap<String, DeploymentSpan>,
+    deployment: &str,
+    event: &str,
+    duration: Duration,
+    commands: &[CommandSpan],
+    failure: Option<&str>,
+) -> Vec<Value> {
+    let end_ns = now_ns();
+    let start_ns = end_ns.saturating_sub(duration.as_nanos());
+
+    let deployment_span = deployments
+        .entry(deployment.to_string())
+        .or_insert_with(|| DeploymentSpan {
+            trace_id: trace_id(),
+            span_id: span_id(),
+            start_ns,
+            end_ns,
+            success: true,
+        });
+    deployment_span.start_ns = deployment_span.start_ns.min(start_ns);
+    deployment_span.end_ns = deployment_span.end_ns.max(end_ns);
+    deployment_span.success &= failure.is_none();
+
+    let trace_id = &deployment_span.trace_id;
+    let event_span_id = span_id();
+    let mut spans = vec![span(
+        trace_id,
+        &event_span_id,
+        Some(&deployment_span.span_id),
+        event,
+        (start_ns, end_ns),
+        vec![
+            attribute("deployment", deployment),
+            attribute("event", event),
+        ],
+        failure,
+    )];
+
+    let mut cursor = start_ns;
+    for command in commands {
+        let command_end = cursor + u128::from(command.duration_ms) * 1_000_000;
+        let mut attributes = vec![attribute("command", command.name)];
+        if let Some(exit_code) = command.exit_code {
+            attributes.push(
+                json!({ "key": "exit_code", "value": { "intValue": exit_code.to_string() } }),
+            );
+        }
+        spans.push(span(
+            trace_id,
+            &span_id(),
+            Some(&event_span_id),
+            command.name,
+            (cursor, command_end),
+            attributes,
+            command.failure,
+        ));
+        cursor = command_end;
+    }
+
+    spans
+}
+
+/// Закрывает спаны деплоев, события которых были выполнены
+///
+/// # Параметры
+///
+/// * `deployments` - Открытые спаны деплоев
+///
+/// # Возвращаемое значение
+///
+/// Корневые спаны деплоев
+fn deployment_spans(deployments: &mut HashMap<String, DeploymentSpan>) -> Vec<Value> {
+    deployments
+        .drain()
+        .map(|(name, deployment)| {
+            span(
+                &deployment.trace_id,
+                &deployment.span_id,
+                None,
+                &name,
+                (deployment.start_ns, deployment.end_ns),
+                vec![attribute("deployment", &name)],
+                (!deployment.success).then_some("Одно из событий завершилось с ошибкой"),
+            )
+        })
+        .collect()
+}
+
+/// Отправляет спаны из очереди, пока она не будет закрыта
+///
+/// Спаны, поставленные в очередь за время предыдущей отправки, объединяются
+/// в один запрос.
+///
+/// # Параметры
+///
+/// * `receiver` - Очередь спанов
+/// * `export` - Отправка пакета спанов сборщику
+async fn export_batches<F, Fut>(mut receiver: mpsc::UnboundedReceiver<Vec<Value>>, mut export: F)
+where
+    F: FnMut(Vec<Value>) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    while let Some(mut batch) = receiver.recv().await {
+        while let Ok(spans) = receiver.try_recv() {
+            batch.extend(spans);
+        }
+        export(batch).await;
+    }
+}
+
+/// Отправляет спаны сборщику в формате OTLP/HTTP JSON
+async fn export(client: &reqwest::Client, endpoint: &str, spans: Vec<Value>) {
+    let count = spans.len();
+    let body = json!({
+        "resourceSpans": [{
+            "resource": { "attributes": [attribute("service.name", SERVICE_NAME)] },
+            "scopeSpans": [{
+                "scope": { "name": SERVICE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans
+            }]
+        }]
+    });
+
+    match client.post(endpoint).json(&body).send().await {
+        Ok(response) if response.status().is_success() => {
+            debug!("Отправлено спанов: {}", count);
+        }
+        Ok(response) => warn!(
+            "Сборщик спанов {} вернул статус {}",
+            endpoint,
+            response.status()
+        ),
+        Err(e) => warn!("Не удалось отправить спаны на {}: {}", endpoint, e),
+    }
+}
+
+/// Формирует спан в формате OTLP JSON
+///
+/// # Параметры
+///
+/// * `trace_id` - Идентификатор трассы
+/// * `span_id` - Идентификатор спана
+/// * `parent_span_id` - Идентификатор родительского спана (None - корневой спан)
+/// * `name` - Имя спана
+/// * `(start_ns, end_ns)` - Время начала и окончания в наносекундах от UNIX epoch
+/// * `attributes` - Атрибуты спана
+/// * `failure` - Сообщение об ошибке или None при успехе
+fn span(
+    trace_id: &str,
+    span_id: &str,
+    parent_span_id: Option<&str>,
+    name: &str,
+    (start_ns, end_ns): (u128, u128),
+    attributes: Vec<Value>,
+    failure: Option<&str>,
+) -> Value {
+    let status = match failure {
+        Some(message) => json!({ "code": 2, "message": message }),
+        None => json!({ "code": 1 }),
+    };
+
+    json!({
+        "traceId": trace_id,
+        "spanId": span_id,
+        "parentSpanId": parent_span_id.unwrap_or_default(),
+        "name": name,
+        "kind": 1,
+        "startTimeUnixNano": start_ns.to_string(),
+        "endTimeUnixNano": end_ns.to_string(),
+        "attributes": attributes,
+        "status": status
+    })
+}
+
+/// Формирует строковый атрибут спана
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// Возвращает текущее время в наносекундах от UNIX epoch
+fn now_ns() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+/// Формирует случайный 128-битный идентификатор трассы
+fn trace_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// Формирует случайный 64-битный идентификатор спана
+fn span_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn command(name: &'static str, failure: Option<&'static str>) -> CommandSpan<'static> {
+        CommandSpan {
+            name,
+            duration_ms: 10,
+            exit_code: Some(if failure.is_some() { 1 } else { 0 }),
+            failure,
+        }
+    }
+
+    #[test]
+    fn spans_form_deployment_event_command_tree() {
+        let mut deployments = HashMap::new();
+        let build = event_spans(
+            &mut deployments,
+            "app",
+            "build",
+            Duration::from_millis(20),
+            &[command("compile", None), command("test", None)],
+            None,
+        );
+        let deploy = event_spans(
+            &mut deployments,
+            "app",
+            "deploy",
+            Duration::from_millis(10),
+            &[command("upload", Some("нет доступа"))],
+            Some("нет доступа"),
+        );
+        let roots = deployment_spans(&mut deployments);
+
+        assert_eq!(roots.len(), 1);
+        let root = &roots[0];
+        assert_eq!(root["name"], "app");
+        assert_eq!(root["parentSpanId"], "");
+        assert_eq!(root["status"]["code"], 2);
+        assert_eq!(root["traceId"].as_str().unwrap().len(), 32);
+        assert_eq!(root["spanId"].as_str().unwrap().len(), 16);
+
+        let names: Vec<&str> = build
+            .iter()
+            .chain(&deploy)
+            .map(|span| span["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["build", "compile", "test", "deploy", "upload"]);
+
+        for (event, commands) in [(&build[0], &build[1..]), (&deploy[0], &deploy[1..])] {
+            assert_eq!(event["traceId"], root["traceId"]);
+            assert_eq!(event["parentSpanId"], root["spanId"]);
+            for command in commands {
+                assert_eq!(command["traceId"], root["traceId"]);
+                assert_eq!(command["parentSpanId"], event["spanId"]);
+            }
+        }
+        assert_eq!(build[0]["status"]["code"], 1);
+        assert_eq!(deploy[1]["status"]["message"], "нет доступа");
+        assert!(deployments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn queued_spans_are_exported_in_one_batch() {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        for name in ["build", "deploy", "app"] {
+            sender.send(vec![json!({ "name": name })]).unwrap();
+        }
+        drop(sender);
+
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let exported = Arc::clone(&batches);
+        export_batches(receiver, move |spans| {
+            exported.lock().unwrap().push(spans);
+            async {}
+        })
+        .await;
+
+        let batches = batches.lock().unwrap();
+        assert_eq!(batches.len(), 1);
+        let names: Vec<&str> = batches[0]
+            .iter()
+            .map(|span| span["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["build", "deploy", "app"]);
+    }
+}
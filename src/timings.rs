@@ -0,0 +1,153 @@
+/*!
+# Модуль Timings
+
+Модуль `timings` сохраняет длительности выполнения запуска в JSON для анализа
+производительности:
+
+- Общая длительность запуска
+- Длительность каждого деплоя (сумма его событий)
+- Длительность каждого события и входящих в него команд
+- Запись файла после каждого события, чтобы он сохранялся даже при аварийном завершении
+
+## Основные функции
+
+- `enable` - включает запись длительностей в указанный файл
+- `record_event` - добавляет длительности события и его команд
+*/
+
+use command_system::CommandResult;
+use log::{info, warn};
+use serde::Serialize;
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Длительности выполнения запуска
+#[derive(Debug, Serialize)]
+pub struct RunTimings {
+    /// Общая длительность запуска в миллисекундах
+    pub total_ms: u128,
+    /// Деплои в порядке первого выполнения
+    pub deployments: Vec<DeploymentTimings>,
+}
+
+/// Длительности выполнения деплоя
+#[derive(Debug, Serialize)]
+pub struct DeploymentTimings {
+    /// Имя деплоя
+    pub name: String,
+    /// Сумма длительностей событий деплоя в миллисекундах
+    pub duration_ms: u128,
+    /// События деплоя в порядке выполнения
+    pub events: Vec<EventTimings>,
+}
+
+/// Длительности выполнения события
+#[derive(Debug, Serialize)]
+pub struct EventTimings {
+    /// Имя события
+    pub name: String,
+    /// Длительность события в миллисекундах, включая подготовку цепочки команд
+    pub duration_ms: u128,
+    /// Команды события в порядке выполнения (повторы команды указываются отдельно)
+    pub commands: Vec<CommandTimings>,
+}
+
+/// Длительность выполнения команды
+#[derive(Debug, Serialize)]
+pub struct CommandTimings {
+    /// Имя команды в цепочке
+    pub name: String,
+    /// Длительность команды в миллисекундах
+    pub duration_ms: u64,
+}
+
+/// Состояние записи: путь к файлу, время начала и накопленные длительности
+struct Timings {
+    path: String,
+    started: Instant,
+    deployments: Vec<DeploymentTimings>,
+}
+
+/// Длительности текущего запуска (включаются флагом `--timings`)
+static TIMINGS: OnceLock<Mutex<Timings>> = OnceLock::new();
+
+/// Включает запись длительностей выполнения в указанный файл
+///
+/// # Параметры
+///
+/// * `path` - Путь к JSON-файлу
+pub fn enable(path: &str) {
+    info!("Длительности выполнения будут записаны в файл: {}", path);
+    let _ = TIMINGS.set(Mutex::new(Timings {
+        path: path.to_string(),
+        started: Instant::now(),
+        deployments: Vec::new(),
+    }));
+}
+
+/// Добавляет длительности события и его команд и перезаписывает файл
+///
+/// Ничего не делает, если запись длительностей не включена.
+///
+/// # Параметры
+///
+/// * `deployment` - Имя деплоя
+/// * `event` - Имя события
+/// * `duration` - Длительность выполнения события
+/// * `results` - Результаты выполненных команд события
+pub fn record_event(deployment: &str, event: &str, duration: Duration, results: &[CommandResult]) {
+    let Some(timings) = TIMINGS.get() else {
+        return;
+    };
+
+    let mut timings = timings.lock().unwrap_or_else(|e| e.into_inner());
+    let index = match timings
+        .deployments
+        .iter()
+        .position(|d| d.name == deployment)
+    {
+        Some(index) => index,
+        None => {
+            timings.deployments.push(DeploymentTimings {
+                name: deployment.to_string(),
+                duration_ms: 0,
+                events: Vec::new(),
+            });
+            timings.deployments.len() - 1
+        }
+    };
+
+    let deployment_timings = &mut timings.deployments[index];
+    deployment_timings.duration_ms += duration.as_millis();
+    deployment_timings.events.push(EventTimings {
+        name: event.to_string(),
+        duration_ms: duration.as_millis(),
+        commands: results
+            .iter()
+            .map(|result| CommandTimings {
+                name: result.command_name.clone(),
+                duration_ms: result.duration_ms,
+            })
+            .collect(),
+    });
+
+    let run_timings = RunTimings {
+        total_ms: timings.started.elapsed().as_millis(),
+        deployments: std::mem::take(&mut timings.deployments),
+    };
+
+    match serde_json::to_string_pretty(&run_timings) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&timings.path, json) {
+                warn!(
+                    "Не удалось записать длительности в файл {}: {}",
+                    timings.path, e
+                );
+            }
+        }
+        Err(e) => warn!("Не удалось сериализовать длительности выполнения: {}", e),
+    }
+
+    timings.deployments = run_timings.deployments;
+}
@@ -0,0 +1,146 @@
+/*!
+# Модуль Util
+
+Модуль `util` содержит небольшие вспомогательные функции общего назначения:
+
+- Расстояние редактирования (Левенштейна) между строками
+- Поиск ближайшего по написанию имени для подсказок "возможно, вы имели в виду"
+- Подстановка переменных окружения `${VAR}` в текст для вывода
+
+## Основные функции
+
+- `edit_distance` - вычисляет расстояние Левенштейна между строками
+- `closest_match` - находит ближайшее имя среди кандидатов
+- `expand_env_vars` - подставляет значения переменных окружения в текст
+*/
+
+use regex::{Captures, Regex};
+use std::sync::OnceLock;
+
+/// Шаблон переменной окружения `${VAR}`
+static ENV_VAR_REGEX: OnceLock<Regex> = OnceLock::new();
+
+/// Вычисляет расстояние Левенштейна между строками
+///
+/// Расстояние равно минимальному количеству вставок, удалений и замен символов,
+/// необходимых для превращения одной строки в другую.
+///
+/// # Параметры
+///
+/// * `a` - Первая строка
+/// * `b` - Вторая строка
+///
+/// # Возвращаемое значение
+///
+/// Расстояние редактирования в символах
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut current = vec![0; b_chars.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b_chars.len()]
+}
+
+/// Находит среди кандидатов имя, ближайшее по написанию к указанному
+///
+/// Имя считается похожим, если расстояние редактирования не превышает трети
+/// его длины, но не меньше двух символов (перестановка соседних букв стоит двух
+/// правок). Регистр символов не учитывается.
+///
+/// # Параметры
+///
+/// * `name` - Имя, введенное пользователем
+/// * `candidates` - Допустимые имена
+///
+/// # Возвращаемое значение
+///
+/// Ближайшее похожее имя или None
+pub fn closest_match<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(2);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&name, &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Подставляет в текст значения переменных окружения вида `${VAR}`
+///
+/// Шаблоны переменных, не заданных в окружении, остаются без изменений.
+///
+/// # Параметры
+///
+/// * `text` - Текст с шаблонами переменных
+///
+/// # Возвращаемое значение
+///
+/// Текст с подставленными значениями
+pub fn expand_env_vars(text: &str) -> String {
+    if !text.contains("${") {
+        return text.to_string();
+    }
+
+    let regex = ENV_VAR_REGEX.get_or_init(|| {
+        Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("Некорректный шаблон переменной")
+    });
+
+    regex
+        .replace_all(text, |caps: &Captures| {
+            std::env::var(&caps[1]).unwrap_or_else(|_| caps[0].to_string())
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("web", "web"), 0);
+        assert_eq!(edit_distance("web", "wbe"), 2);
+        assert_eq!(edit_distance("web", "webs"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "api"), 3);
+        assert_eq!(edit_distance("деплой", "деплои"), 1);
+    }
+
+    #[test]
+    fn closest_match_suggests_similar_name() {
+        let names = ["web", "api-users", "backend"];
+
+        assert_eq!(closest_match("wbe", names), Some("web"));
+        assert_eq!(closest_match("API-user", names), Some("api-users"));
+        assert_eq!(closest_match("database", names), None);
+    }
+
+    #[test]
+    fn expand_env_vars_replaces_set_variables_only() {
+        std::env::set_var("DC_UTIL_TEST_VERSION", "1.2.3");
+        std::env::remove_var("DC_UTIL_TEST_MISSING");
+
+        assert_eq!(
+            expand_env_vars("Деплой версии ${DC_UTIL_TEST_VERSION}"),
+            "Деплой версии 1.2.3"
+        );
+        assert_eq!(
+            expand_env_vars("${DC_UTIL_TEST_MISSING} и $DC_UTIL_TEST_VERSION"),
+            "${DC_UTIL_TEST_MISSING} и $DC_UTIL_TEST_VERSION"
+        );
+    }
+}
@@ -0,0 +1,3090 @@
+//! Интеграционные тесты интерфейса командной строки
+//!
+//! Каждый тест запускает утилиту во временной директории, так как файлы
+//! настроек и истории создаются относительно текущей директории.
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+/// Конфигурация с двумя деплоями для тестов
+const CONFIG: &str = r#"
+deployments:
+  - name: frontend
+    events:
+      - name: deploy
+        commands:
+          - command: "echo frontend"
+  - name: backend
+    events:
+      - name: deploy
+        commands:
+          - command: "echo backend"
+"#;
+
+/// Создает временную директорию с файлом конфигурации `deploy-config.yml`
+fn workspace(config: &str) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("deploy-config.yml"), config).unwrap();
+    dir
+}
+
+/// Команда запуска утилиты в указанной директории
+fn deploy_cmd(dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("deploy-cmd").unwrap();
+    cmd.current_dir(dir.path())
+        .env_remove("DC_CONFIG")
+        .env_remove("DC_HISTORY");
+    cmd
+}
+
+#[test]
+fn completions_mention_subcommands_and_deployments() {
+    let dir = workspace(CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("run"))
+        .stdout(predicate::str::contains("history"))
+        .stdout(predicate::str::contains("frontend"))
+        .stdout(predicate::str::contains("backend"));
+}
+
+/// Конфигурация, события которой дописывают свое имя в файл `order.txt`
+const ORDERED_EVENTS_CONFIG: &str = r#"
+deployments:
+  - name: app
+    events:
+      - name: pre-deploy
+        commands:
+          - command: "echo pre-deploy >> order.txt"
+      - name: deploy
+        commands:
+          - command: "echo deploy >> order.txt"
+      - name: post-deploy
+        commands:
+          - command: "echo post-deploy >> order.txt"
+"#;
+
+/// Читает имена выполненных событий из файла `order.txt`
+fn executed_events(dir: &TempDir) -> Vec<String> {
+    fs::read_to_string(dir.path().join("order.txt"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn repeated_event_flags_run_listed_events_in_order() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+
+    deploy_cmd(&dir)
+        .args([
+            "run",
+            "-d",
+            "app",
+            "--event",
+            "post-deploy",
+            "--event",
+            "deploy",
+        ])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["post-deploy", "deploy"]);
+}
+
+#[test]
+fn run_without_event_runs_all_events() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        executed_events(&dir),
+        vec!["pre-deploy", "deploy", "post-deploy"]
+    );
+}
+
+#[test]
+fn invalid_utf8_output_is_reported_without_failing() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: printf 'ok \377\376 end'
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("недопустимые UTF-8 данные"));
+}
+
+/// Записывает файл настроек `settings.json` с дополнительными полями
+fn write_settings(dir: &TempDir, extra_fields: &str) {
+    let settings = format!(
+        r#"{{
+  "log_file": "deploy-commander.log",
+  "history_file": "deploy-history.json",
+  "variables_file": "variables.json",
+  "logs_dir": "logs",
+  {}
+}}"#,
+        extra_fields
+    );
+    fs::write(dir.path().join("settings.json"), settings).unwrap();
+}
+
+#[test]
+fn alias_runs_target_deployment() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+    write_settings(&dir, r#""aliases": { "a": "app" }"#);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "a", "--event", "deploy"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+}
+
+#[test]
+fn alias_to_missing_deployment_fails() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+    write_settings(&dir, r#""aliases": { "w": "web-production" }"#);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "w", "--event", "deploy"])
+        .assert()
+        .code(2);
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+/// Конфигурация с единственным деплоем `name`
+fn single_deployment_config(name: &str) -> String {
+    format!(
+        "deployments:\n  - name: {}\n    events:\n      - name: deploy\n        commands:\n          - command: \"echo {}\"\n",
+        name, name
+    )
+}
+
+#[test]
+fn config_flag_takes_precedence_over_env() {
+    let dir = workspace(&single_deployment_config("default-app"));
+    fs::write(
+        dir.path().join("env.yml"),
+        single_deployment_config("env-app"),
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("flag.yml"),
+        single_deployment_config("flag-app"),
+    )
+    .unwrap();
+
+    deploy_cmd(&dir)
+        .env("DC_CONFIG", "env.yml")
+        .args(["-c", "flag.yml", "completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("flag-app"))
+        .stdout(predicate::str::contains("env-app").not());
+}
+
+#[test]
+fn config_env_takes_precedence_over_default() {
+    let dir = workspace(&single_deployment_config("default-app"));
+    fs::write(
+        dir.path().join("env.yml"),
+        single_deployment_config("env-app"),
+    )
+    .unwrap();
+
+    deploy_cmd(&dir)
+        .env("DC_CONFIG", "env.yml")
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("env-app"))
+        .stdout(predicate::str::contains("default-app").not());
+
+    deploy_cmd(&dir)
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("default-app"));
+}
+
+#[test]
+fn history_env_takes_precedence_over_settings() {
+    let dir = workspace(&single_deployment_config("app"));
+    fs::write(
+        dir.path().join("settings.json"),
+        r#"{
+  "log_file": "deploy-commander.log",
+  "history_file": "settings-history.json",
+  "variables_file": "variables.json",
+  "logs_dir": "logs"
+}"#,
+    )
+    .unwrap();
+
+    deploy_cmd(&dir)
+        .env("DC_HISTORY", "env-history.json")
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+    assert!(dir.path().join("env-history.json").exists());
+    assert!(!dir.path().join("settings-history.json").exists());
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+    assert!(dir.path().join("settings-history.json").exists());
+}
+
+/// Читает содержимое всех файлов лога команд в директории `logs`
+fn command_logs(dir: &TempDir) -> String {
+    let mut content = String::new();
+    for entry in fs::read_dir(dir.path().join("logs")).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_file() {
+            content.push_str(&fs::read_to_string(path).unwrap());
+        }
+    }
+    content
+}
+
+#[test]
+fn command_description_is_written_to_command_log() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo built"
+            description: "Сборка проекта"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    let logs = command_logs(&dir);
+    assert!(logs.contains("Команда: 'app_deploy_cmd_1' (Сборка проекта)"));
+    assert!(logs.contains("Длительность: "));
+}
+
+#[test]
+fn redact_patterns_hide_values_in_console_log_and_history() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo token-12345; echo token-67890 >&2; exit 1"
+"#,
+    );
+    write_settings(&dir, r#""redact_patterns": ["token-[0-9]+"]"#);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("token-").not())
+        .stderr(predicate::str::contains("token-").not())
+        .stdout(predicate::str::contains("****"));
+
+    let logs = command_logs(&dir);
+    assert!(!logs.contains("token-"));
+    assert!(logs.contains("****"));
+
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert!(!history.contains("token-"));
+    assert!(history.contains("****"));
+}
+
+#[test]
+fn dry_run_reports_missing_commands() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "deploy-commander-missing-tool --apply"
+          - command: "echo done >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--dry-run", "--check-commands"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("не найденные в PATH"))
+        .stdout(predicate::str::contains(
+            "  - deploy-commander-missing-tool",
+        ));
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+/// Конфигурация с защищенным и обычным деплоем
+const PROTECTED_CONFIG: &str = r#"
+deployments:
+  - name: web-prod
+    events:
+      - name: deploy
+        commands:
+          - command: "echo web-prod >> order.txt"
+  - name: web-dev
+    events:
+      - name: deploy
+        commands:
+          - command: "echo web-dev >> order.txt"
+"#;
+
+#[test]
+fn protected_deployment_requires_typed_name() {
+    let dir = workspace(PROTECTED_CONFIG);
+    write_settings(&dir, r#""protected_patterns": ["*-prod"]"#);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "web-prod"])
+        .write_stdin("yes\n")
+        .assert()
+        .code(130);
+    assert!(executed_events(&dir).is_empty());
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "web-prod"])
+        .write_stdin("web-prod\n")
+        .assert()
+        .success();
+    assert_eq!(executed_events(&dir), vec!["web-prod"]);
+}
+
+#[test]
+fn unprotected_deployment_runs_without_confirmation() {
+    let dir = workspace(PROTECTED_CONFIG);
+    write_settings(&dir, r#""protected_patterns": ["*-prod"]"#);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "web-dev"])
+        .write_stdin("")
+        .assert()
+        .success();
+    deploy_cmd(&dir)
+        .args(["run", "-d", "web-prod", "--yes"])
+        .write_stdin("")
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["web-dev", "web-prod"]);
+}
+
+#[test]
+fn report_file_contains_failed_event() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: build
+        commands:
+          - command: "echo build"
+      - name: deploy
+        commands:
+          - command: "exit 3"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--report-file", "report.xml"])
+        .assert()
+        .failure();
+
+    let report = fs::read_to_string(dir.path().join("report.xml")).unwrap();
+    assert_eq!(report.matches("<testcase ").count(), 2);
+    assert_eq!(report.matches("<failure ").count(), 1);
+}
+
+#[test]
+fn color_never_strips_ansi_sequences() {
+    let dir = workspace(CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("frontend"))
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn color_always_includes_ansi_sequences() {
+    let dir = workspace(CONFIG);
+
+    deploy_cmd(&dir)
+        .env("NO_COLOR", "1")
+        .args(["--color", "always", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b["));
+}
+
+#[test]
+fn color_auto_respects_no_color() {
+    let dir = workspace(CONFIG);
+
+    deploy_cmd(&dir)
+        .env("NO_COLOR", "1")
+        .args(["list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn dry_run_all_lists_every_deployment_and_event() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: frontend
+    events:
+      - name: build
+        commands:
+          - command: "echo frontend-build >> order.txt"
+      - name: deploy
+        commands:
+          - command: "echo frontend-deploy >> order.txt"
+  - name: backend
+    events:
+      - name: migrate
+        commands:
+          - command: "echo backend-migrate >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "run", "-d", "all", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[1/2] Деплой: frontend"))
+        .stdout(predicate::str::contains("[2/2] Деплой: backend"))
+        .stdout(predicate::str::contains("Событие: build"))
+        .stdout(predicate::str::contains("Событие: deploy"))
+        .stdout(predicate::str::contains("Событие: migrate"))
+        .stdout(predicate::str::contains("Всего команд в плане: 3"));
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+#[test]
+fn rollback_runs_only_for_started_commands_in_reverse() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo first"
+            rollback_command: "echo rollback-first >> order.txt"
+          - command: "exit 1"
+            rollback_command: "echo rollback-second >> order.txt"
+          - command: "echo third"
+            rollback_command: "echo rollback-third >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .failure();
+
+    assert_eq!(
+        executed_events(&dir),
+        vec!["rollback-second", "rollback-first"]
+    );
+}
+
+/// Конфигурация с двумя деплоями `api-*`, один из которых некорректен
+const API_DEPLOYMENTS_CONFIG: &str = r#"
+deployments:
+  - name: api-users
+    events:
+      - name: deploy
+        commands:
+          - command: "echo api-users"
+  - name: api-orders
+    events:
+      - name: deploy
+        commands: []
+  - name: web
+    events:
+      - name: deploy
+        commands:
+          - command: "echo web"
+"#;
+
+#[test]
+fn verify_pattern_reports_each_matching_deployment() {
+    let dir = workspace(API_DEPLOYMENTS_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "verify", "-d", "api-*"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("✅ api-users"))
+        .stdout(predicate::str::contains("❌ api-orders"))
+        .stdout(predicate::str::contains("web").not());
+}
+
+#[test]
+fn verify_pattern_succeeds_when_all_matches_are_valid() {
+    let dir = workspace(API_DEPLOYMENTS_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "verify", "-d", "api-u*"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✅ api-users"))
+        .stdout(predicate::str::contains("api-orders").not());
+}
+
+/// Создает директорию `bin`, содержащую только `sh`, для запуска без внешних команд в PATH
+#[cfg(unix)]
+fn shell_only_path(dir: &TempDir) -> std::path::PathBuf {
+    let bin = dir.path().join("bin");
+    fs::create_dir(&bin).unwrap();
+    std::os::unix::fs::symlink("/bin/sh", bin.join("sh")).unwrap();
+    bin
+}
+
+#[cfg(unix)]
+#[test]
+fn require_commands_aborts_run_when_commands_are_missing() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+    let path = shell_only_path(&dir);
+
+    deploy_cmd(&dir)
+        .env("PATH", &path)
+        .args(["run", "-d", "app", "--require-commands"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("Деплой не будет запущен"));
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn missing_commands_only_warn_without_require_commands() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+    let path = shell_only_path(&dir);
+
+    deploy_cmd(&dir)
+        .env("PATH", &path)
+        .args(["run", "-d", "app", "-e", "deploy"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Некоторые операции могут быть недоступны",
+        ));
+
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn require_commands_setting_aborts_run() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+    write_settings(&dir, r#""require_commands": true"#);
+    let path = shell_only_path(&dir);
+
+    deploy_cmd(&dir)
+        .env("PATH", &path)
+        .args(["run", "-d", "app"])
+        .assert()
+        .code(3);
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+#[test]
+fn per_deployment_command_log_contains_command_output() {
+    let dir = workspace(&single_deployment_config("api"));
+    write_settings(&dir, r#""command_log_mode": "both""#);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "api"])
+        .assert()
+        .success();
+
+    let mut per_deployment = fs::read_dir(dir.path().join("logs").join("api")).unwrap();
+    let log_file = per_deployment.next().unwrap().unwrap().path();
+    assert!(log_file.to_string_lossy().ends_with(".log"));
+    let content = fs::read_to_string(log_file).unwrap();
+    assert!(content.contains("api_deploy_cmd_1"));
+
+    assert!(command_logs(&dir).contains("api_deploy_cmd_1"));
+}
+
+#[test]
+fn per_deployment_command_log_stays_inside_logs_dir() {
+    let dir = workspace(&single_deployment_config("team/api"));
+    write_settings(&dir, r#""command_log_mode": "per_deployment""#);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "team/api"])
+        .assert()
+        .success();
+
+    assert!(dir.path().join("logs").join("team_api").is_dir());
+    assert!(!dir.path().join("logs").join("team").exists());
+}
+
+/// Конфигурация деплоя с переменными окружения из `environment`
+const ENVIRONMENT_CONFIG: &str = r#"
+deployments:
+  - name: app
+    environment:
+      - "APP_ENV=production"
+      - "APP_PORT=8080"
+    events:
+      - name: deploy
+        commands:
+          - command: "echo deploy >> order.txt"
+"#;
+
+#[test]
+fn print_env_shows_deployment_variables_without_running() {
+    let dir = workspace(ENVIRONMENT_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "run", "-d", "app", "--print-env"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("APP_ENV=production"))
+        .stdout(predicate::str::contains("APP_PORT=8080"));
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+#[test]
+fn print_env_respects_env_overrides() {
+    let dir = workspace(ENVIRONMENT_CONFIG);
+
+    deploy_cmd(&dir)
+        .args([
+            "--color",
+            "never",
+            "run",
+            "-d",
+            "app",
+            "--print-env",
+            "--env",
+            "APP_PORT=9090",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("APP_ENV=production"))
+        .stdout(predicate::str::contains("APP_PORT=9090"))
+        .stdout(predicate::str::contains("APP_PORT=8080").not());
+}
+
+/// Конфигурация с деплоем, исключенным из запуска `all`
+const EXCLUDED_DEPLOYMENT_CONFIG: &str = r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo app >> order.txt"
+  - name: maintenance
+    exclude_from_all: true
+    events:
+      - name: deploy
+        commands:
+          - command: "echo maintenance >> order.txt"
+"#;
+
+#[test]
+fn excluded_deployment_is_skipped_by_all() {
+    let dir = workspace(EXCLUDED_DEPLOYMENT_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Деплои, исключенные из запуска all: maintenance",
+        ));
+
+    assert_eq!(executed_events(&dir), vec!["app"]);
+}
+
+#[test]
+fn excluded_deployment_runs_by_name() {
+    let dir = workspace(EXCLUDED_DEPLOYMENT_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "maintenance"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["maintenance"]);
+}
+
+#[test]
+fn exit_codes_match_error_categories() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "exit 7"
+"#,
+    );
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(3);
+    deploy_cmd(&dir)
+        .args(["run", "-d", "missing"])
+        .assert()
+        .code(2);
+
+    fs::write(dir.path().join("broken.yml"), "deployments: [").unwrap();
+    deploy_cmd(&dir)
+        .args(["-c", "broken.yml", "run", "-d", "app"])
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn config_is_read_from_stdin() {
+    let dir = tempfile::tempdir().unwrap();
+
+    deploy_cmd(&dir)
+        .args(["--config", "-", "run", "-d", "web"])
+        .write_stdin(
+            "deployments:\n  - name: web\n    events:\n      - name: deploy\n        commands:\n          - command: \"echo web >> order.txt\"\n",
+        )
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["web"]);
+    assert!(!dir.path().join("deploy-config.yml").exists());
+}
+
+#[test]
+fn json_config_is_read_from_stdin_with_config_format() {
+    let dir = tempfile::tempdir().unwrap();
+
+    deploy_cmd(&dir)
+        .args(["--config", "-", "--config-format", "json", "run", "-d", "web"])
+        .write_stdin(
+            r#"{"deployments": [{"name": "web", "events": [{"name": "deploy", "commands": [{"command": "echo web >> order.txt"}]}]}]}"#,
+        )
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["web"]);
+}
+
+/// Конфигурация с командой, которая записывает попытку и завершается с кодом `code`
+fn retried_command_config(code: i32) -> String {
+    format!(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo attempt >> order.txt; exit {}"
+            retry_policy:
+              retries: 2
+              retry_on_exit_codes: [75]
+"#,
+        code
+    )
+}
+
+#[test]
+fn command_is_not_retried_on_unlisted_exit_code() {
+    let dir = workspace(&retried_command_config(1));
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(3);
+
+    assert_eq!(executed_events(&dir), vec!["attempt"]);
+}
+
+#[test]
+fn command_is_retried_on_listed_exit_code() {
+    let dir = workspace(&retried_command_config(75));
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(3);
+
+    assert_eq!(executed_events(&dir), vec!["attempt"; 3]);
+}
+
+#[test]
+fn commands_inherit_deployment_retry_policy() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    retry_policy:
+      retries: 2
+    events:
+      - name: deploy
+        commands:
+          - command: "echo attempt >> order.txt; exit 1"
+"#,
+    );
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(3);
+
+    assert_eq!(executed_events(&dir), vec!["attempt"; 3]);
+}
+
+#[test]
+fn command_retry_policy_overrides_deployment_policy() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    retry_policy:
+      retries: 2
+    events:
+      - name: deploy
+        commands:
+          - command: "echo attempt >> order.txt; exit 1"
+            retry_policy:
+              retries: 0
+"#,
+    );
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(3);
+
+    assert_eq!(executed_events(&dir), vec!["attempt"]);
+}
+
+#[test]
+fn timings_file_has_command_durations_within_event_duration() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: build
+        commands:
+          - command: "sleep 0.05"
+          - command: "sleep 0.05"
+      - name: deploy
+        commands:
+          - command: "echo deploy"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--timings", "timings.json"])
+        .assert()
+        .success();
+
+    let timings: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("timings.json")).unwrap())
+            .unwrap();
+    let deployment = &timings["deployments"][0];
+    assert_eq!(deployment["name"], "app");
+
+    let events = deployment["events"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    let mut events_total = 0;
+    for event in events {
+        let event_ms = event["duration_ms"].as_u64().unwrap();
+        let commands_ms: u64 = event["commands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|command| command["duration_ms"].as_u64().unwrap())
+            .sum();
+        assert!(commands_ms <= event_ms);
+        events_total += event_ms;
+    }
+    assert_eq!(events[0]["commands"].as_array().unwrap().len(), 2);
+    assert!(events[0]["duration_ms"].as_u64().unwrap() >= 100);
+    assert_eq!(deployment["duration_ms"].as_u64().unwrap(), events_total);
+    assert!(timings["total_ms"].as_u64().unwrap() >= events_total);
+}
+
+/// Конфигурация с переменными `{#VAR}` деплоя и команды, заданными в YAML
+const INLINE_VARIABLES_CONFIG: &str = r#"
+deployments:
+  - name: app
+    variables:
+      TARGET: "inline-target"
+      BRANCH: "inline-branch"
+    events:
+      - name: deploy
+        commands:
+          - command: "echo {#TARGET} {#BRANCH} >> order.txt"
+            variables:
+              BRANCH: "command-branch"
+"#;
+
+#[test]
+fn inline_variables_are_substituted_with_command_priority() {
+    let dir = workspace(INLINE_VARIABLES_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["inline-target command-branch"]);
+}
+
+#[test]
+fn variables_file_overrides_inline_variables() {
+    let dir = workspace(INLINE_VARIABLES_CONFIG);
+    fs::write(
+        dir.path().join("variables.json"),
+        r#"{ "TARGET": "file-target" }"#,
+    )
+    .unwrap();
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["file-target command-branch"]);
+}
+
+#[test]
+fn mistyped_deployment_name_gets_suggestion() {
+    let dir = workspace(CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "frontnd"])
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains(
+            "Возможно, вы имели в виду 'frontend'?",
+        ));
+}
+
+#[test]
+fn stdin_string_is_piped_into_command() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    variables:
+      NAME: "world"
+    events:
+      - name: deploy
+        commands:
+          - command: "cat >> order.txt # сохраняем ввод"
+            stdin: "hello {#NAME}"
+"#,
+    );
+    let tmp = dir.path().join("tmp");
+    fs::create_dir(&tmp).unwrap();
+
+    deploy_cmd(&dir)
+        .env("TMPDIR", &tmp)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["hello world"]);
+    assert_eq!(fs::read_dir(&tmp).unwrap().count(), 0);
+}
+
+#[test]
+fn stdin_file_is_piped_into_command() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "cat >> order.txt"
+            stdin_file: "input.txt"
+"#,
+    );
+    fs::write(dir.path().join("input.txt"), "from file\n").unwrap();
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["from file"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn offline_verify_skips_external_command_check() {
+    let dir = workspace(CONFIG);
+    let path = shell_only_path(&dir);
+
+    deploy_cmd(&dir)
+        .env("PATH", &path)
+        .args(["--offline", "verify", "-d", "frontend"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("which").not())
+        .stdout(predicate::str::contains("Отсутствуют команды").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn offline_run_ignores_require_commands() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+    let path = shell_only_path(&dir);
+
+    deploy_cmd(&dir)
+        .env("PATH", &path)
+        .args([
+            "--offline",
+            "run",
+            "-d",
+            "app",
+            "-e",
+            "deploy",
+            "--require-commands",
+        ])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+}
+
+#[test]
+fn progress_lines_are_not_printed_to_pipe() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "-e", "deploy"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[1/1]").not());
+}
+
+/// Конфигурация с деплоями, отмеченными разными тегами
+const TAGGED_CONFIG: &str = r#"
+deployments:
+  - name: api
+    tags: [prod, backend]
+    events:
+      - name: deploy
+        commands:
+          - command: "echo api >> order.txt"
+  - name: web
+    tags: [staging]
+    events:
+      - name: deploy
+        commands:
+          - command: "echo web >> order.txt"
+  - name: tools
+    events:
+      - name: deploy
+        commands:
+          - command: "echo tools >> order.txt"
+"#;
+
+/// Имена выполненных деплоев в алфавитном порядке (деплои выполняются параллельно)
+fn executed_sorted(dir: &TempDir) -> Vec<String> {
+    let mut executed = executed_events(dir);
+    executed.sort();
+    executed
+}
+
+#[test]
+fn run_all_with_tag_runs_only_tagged_deployments() {
+    let dir = workspace(TAGGED_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "all", "--tag", "prod"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_sorted(&dir), vec!["api"]);
+}
+
+#[test]
+fn run_all_with_several_tags_runs_deployments_with_any_tag() {
+    let dir = workspace(TAGGED_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "all", "--tag", "prod", "--tag", "staging"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_sorted(&dir), vec!["api", "web"]);
+}
+
+#[test]
+fn run_all_with_unknown_tag_fails() {
+    let dir = workspace(TAGGED_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "all", "--tag", "qa"])
+        .assert()
+        .code(2);
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+#[test]
+fn history_event_filter_shows_only_requested_event() {
+    let dir = workspace(ORDERED_EVENTS_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "history", "-d", "app", "-e", "deploy"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "История события 'deploy' деплоя 'app'",
+        ))
+        .stdout(predicate::str::contains("complete:deploy"))
+        .stdout(predicate::str::contains("pre-deploy").not())
+        .stdout(predicate::str::contains("post-deploy").not());
+}
+
+#[test]
+fn run_all_prints_failure_report_with_error_text() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: api
+    events:
+      - name: migrate
+        commands:
+          - command: "echo api-migration-broken >&2; exit 1"
+  - name: web
+    events:
+      - name: build
+        commands:
+          - command: "echo web-build-broken >&2; exit 2"
+  - name: docs
+    events:
+      - name: deploy
+        commands:
+          - command: "echo docs"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "run", "-d", "all"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "Деплои, завершившиеся с ошибкой (2):",
+        ))
+        .stdout(
+            predicate::str::is_match(r"  - api: событие 'migrate': .*api-migration-broken")
+                .unwrap(),
+        )
+        .stdout(predicate::str::is_match(r"  - web: событие 'build': .*web-build-broken").unwrap())
+        .stdout(predicate::str::contains("  - docs:").not());
+}
+
+#[test]
+fn descriptions_are_logged_with_expanded_env_vars() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        description: "Деплой версии ${DC_TEST_VERSION}"
+        commands:
+          - command: "echo built"
+            description: "Сборка ${DC_TEST_VERSION}"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .env("DC_TEST_VERSION", "1.2.3")
+        .args(["run", "-d", "app"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Деплой версии 1.2.3"));
+
+    let logs = command_logs(&dir);
+    assert!(logs.contains("(Сборка 1.2.3)"));
+    assert!(!logs.contains("${DC_TEST_VERSION}"));
+}
+
+#[test]
+fn event_order_reorders_full_run() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    event_order: [pre-deploy, deploy, post-deploy]
+    events:
+      - name: post-deploy
+        commands:
+          - command: "echo post-deploy >> order.txt"
+      - name: deploy
+        commands:
+          - command: "echo deploy >> order.txt"
+      - name: pre-deploy
+        commands:
+          - command: "echo pre-deploy >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        executed_events(&dir),
+        vec!["pre-deploy", "deploy", "post-deploy"]
+    );
+}
+
+/// Конфигурация с тремя деплоями, дописывающими свое имя в `order.txt`
+const THREE_DEPLOYMENTS_CONFIG: &str = r#"
+deployments:
+  - name: database
+    events:
+      - name: deploy
+        commands:
+          - command: "echo database >> order.txt"
+  - name: api
+    events:
+      - name: deploy
+        commands:
+          - command: "echo api >> order.txt"
+  - name: frontend
+    events:
+      - name: deploy
+        commands:
+          - command: "echo frontend >> order.txt"
+"#;
+
+#[test]
+fn deployment_file_runs_listed_deployments_in_file_order() {
+    let dir = workspace(THREE_DEPLOYMENTS_CONFIG);
+    fs::write(
+        dir.path().join("rollout.txt"),
+        "# порядок выкатки\nfrontend\n\ndatabase\napi\n",
+    )
+    .unwrap();
+
+    deploy_cmd(&dir)
+        .args([
+            "--parallel",
+            "false",
+            "run",
+            "--deployment-file",
+            "rollout.txt",
+        ])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["frontend", "database", "api"]);
+}
+
+#[test]
+fn deployment_file_with_unknown_name_runs_nothing() {
+    let dir = workspace(THREE_DEPLOYMENTS_CONFIG);
+    fs::write(dir.path().join("rollout.txt"), "database\nbackend\napi\n").unwrap();
+
+    deploy_cmd(&dir)
+        .args([
+            "--parallel",
+            "false",
+            "run",
+            "--deployment-file",
+            "rollout.txt",
+        ])
+        .assert()
+        .code(2);
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+#[test]
+fn large_outputs_of_many_commands_are_all_logged() {
+    let mut config = String::from(
+        "deployments:\n  - name: app\n    events:\n      - name: deploy\n        commands:\n",
+    );
+    for _ in 0..10 {
+        config.push_str("          - command: \"head -c 200000 /dev/zero | tr '\\\\0' x\"\n");
+    }
+    let dir = workspace(&config);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    let logs = command_logs(&dir);
+    for idx in 1..=10 {
+        assert!(logs.contains(&format!("'app_deploy_cmd_{}'", idx)));
+    }
+    assert!(logs.len() > 10 * 200_000);
+}
+
+#[test]
+fn verify_rejects_malformed_environment_entry() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    environment:
+      - "NODE_ENVproduction"
+    events:
+      - name: deploy
+        commands:
+          - command: "echo deploy"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["verify", "-d", "app"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "'NODE_ENVproduction' не соответствует формату KEY=VALUE",
+        ));
+}
+
+#[test]
+fn rollback_steps_run_in_order() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo migrate"
+            rollback_command:
+              - "echo step-1 >> order.txt"
+              - "echo step-2 >> order.txt"
+              - "echo step-3 >> order.txt"
+          - command: "exit 1"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .failure();
+
+    assert_eq!(executed_events(&dir), vec!["step-1", "step-2", "step-3"]);
+}
+
+#[test]
+fn parallel_run_reports_each_completion_with_totals() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: api
+    events:
+      - name: deploy
+        commands:
+          - command: "exit 1"
+  - name: web
+    events:
+      - name: deploy
+        commands:
+          - command: "echo web"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "run", "-d", "all"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("❌ api (завершено "))
+        .stdout(predicate::str::contains("✅ web (завершено "))
+        .stdout(predicate::str::contains("(завершено 2/2, с ошибками: 1)"));
+}
+
+#[test]
+fn config_fmt_is_idempotent() {
+    let dir = workspace(
+        r#"
+deployments:
+  - events:
+      - commands: [{command: "echo deploy", retry_policy: {retries: 1}}]
+        name: deploy
+    name: app
+"#,
+    );
+
+    let formatted = deploy_cmd(&dir)
+        .args(["config", "fmt"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let formatted = String::from_utf8(formatted).unwrap();
+    assert!(formatted.contains("name: app"));
+
+    fs::write(dir.path().join("deploy-config.yml"), &formatted).unwrap();
+    deploy_cmd(&dir)
+        .args(["config", "fmt"])
+        .assert()
+        .success()
+        .stdout(formatted);
+}
+
+#[test]
+fn config_fmt_in_place_rewrites_config_file() {
+    let dir = workspace(CONFIG);
+
+    let formatted = deploy_cmd(&dir)
+        .args(["config", "fmt"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+
+    deploy_cmd(&dir)
+        .args(["config", "fmt", "--in-place"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        fs::read(dir.path().join("deploy-config.yml")).unwrap(),
+        formatted
+    );
+}
+
+/// Конфигурация с событиями проверки работоспособности; `ok` завершается успешно, `broken` - с ошибкой
+const PROBE_CONFIG: &str = r#"
+deployments:
+  - name: ok
+    events:
+      - name: healthcheck
+        commands:
+          - command: "echo healthy"
+      - name: ready
+        commands:
+          - command: "exit 1"
+  - name: broken
+    events:
+      - name: healthcheck
+        commands:
+          - command: "exit 1"
+"#;
+
+#[test]
+fn probe_exit_status_reflects_event_result() {
+    let dir = workspace(PROBE_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["probe", "-d", "ok"])
+        .assert()
+        .success()
+        .stdout("");
+    deploy_cmd(&dir)
+        .args(["probe", "-d", "broken"])
+        .assert()
+        .failure()
+        .stdout("");
+}
+
+#[test]
+fn probe_runs_event_from_flag_and_settings() {
+    let dir = workspace(PROBE_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["probe", "-d", "ok", "-e", "ready"])
+        .assert()
+        .failure();
+
+    write_settings(&dir, r#""probe_event": "ready""#);
+    deploy_cmd(&dir)
+        .args(["probe", "-d", "ok"])
+        .assert()
+        .failure();
+    deploy_cmd(&dir)
+        .args(["probe", "-d", "ok", "-e", "healthcheck"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn buffered_output_keeps_each_deployment_contiguous() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: alpha
+    events:
+      - name: deploy
+        commands:
+          - command: "echo alpha-out-1; sleep 0.1"
+          - command: "echo alpha-out-2; sleep 0.1"
+          - command: "echo alpha-out-3"
+  - name: beta
+    events:
+      - name: deploy
+        commands:
+          - command: "echo beta-out-1; sleep 0.1"
+          - command: "echo beta-out-2; sleep 0.1"
+          - command: "echo beta-out-3"
+"#,
+    );
+
+    let output = deploy_cmd(&dir)
+        .args(["run", "-d", "all", "--buffer-output"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+
+    let owners: Vec<&str> = output
+        .lines()
+        .filter_map(|line| {
+            if line.contains("alpha-out") {
+                Some("alpha")
+            } else if line.contains("beta-out") {
+                Some("beta")
+            } else {
+                None
+            }
+        })
+        .collect();
+    let mut blocks = owners.clone();
+    blocks.dedup();
+
+    assert_eq!(owners.len(), 6, "{}", output);
+    assert_eq!(blocks.len(), 2, "{}", output);
+}
+
+/// Выполняет команду git в указанной директории
+///
+/// Возвращает None, если git недоступен или команда завершилась с ошибкой.
+fn git(dir: &TempDir, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir.path())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[test]
+fn run_records_git_commit_of_working_dir() {
+    let dir = workspace(&single_deployment_config("app"));
+    let committed = git(&dir, &["init", "-q"]).and_then(|_| {
+        git(
+            &dir,
+            &[
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "initial",
+            ],
+        )
+    });
+    if committed.is_none() {
+        eprintln!("git недоступен, тест пропущен");
+        return;
+    }
+    let head = git(&dir, &["rev-parse", "HEAD"]).unwrap();
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert!(
+        history.contains(&format!(r#""git_sha": "{}""#, head)),
+        "{}",
+        history
+    );
+}
+
+#[test]
+fn run_outside_git_repository_records_no_commit() {
+    let dir = workspace(&single_deployment_config("app"));
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert!(history.contains("start:deploy"), "{}", history);
+    assert!(!history.contains("git_sha"), "{}", history);
+}
+
+#[test]
+fn prompt_on_fail_aborts_without_terminal() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo first >> order.txt; exit 1"
+          - command: "echo second >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--prompt-on-fail"])
+        .write_stdin("s\n")
+        .assert()
+        .code(3);
+
+    assert_eq!(executed_events(&dir), vec!["first"]);
+}
+
+/// Конфигурация деплоя с окном обслуживания, в которое не попадает ни один момент времени
+const CLOSED_WINDOW_CONFIG: &str = r#"
+deployments:
+  - name: app
+    maintenance_window: "00:00-00:00"
+    events:
+      - name: deploy
+        commands:
+          - command: "echo deploy >> order.txt"
+"#;
+
+#[test]
+fn run_outside_maintenance_window_is_refused() {
+    let dir = workspace(CLOSED_WINDOW_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("окне обслуживания"));
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+#[test]
+fn force_runs_outside_maintenance_window() {
+    let dir = workspace(CLOSED_WINDOW_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--force"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+}
+
+/// Конфигурация деплоя с минимальным интервалом в час между успешными запусками
+const MIN_INTERVAL_CONFIG: &str = r#"
+deployments:
+  - name: app
+    min_interval_secs: 3600
+    events:
+      - name: deploy
+        commands:
+          - command: "echo deploy >> order.txt"
+"#;
+
+/// Записывает историю с одним успешным событием деплоя `app` в момент `timestamp`
+fn write_success_history(dir: &TempDir, timestamp: u64) {
+    let history = format!(
+        r#"{{"records":{{"app":[{{"deployment":"app","event":"complete:deploy","timestamp":{},"success":true,"details":null}}]}}}}"#,
+        timestamp
+    );
+    fs::write(dir.path().join("deploy-history.json"), history).unwrap();
+}
+
+/// Текущее время в секундах UNIX
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[test]
+fn recent_success_blocks_run_within_min_interval() {
+    let dir = workspace(MIN_INTERVAL_CONFIG);
+    write_success_history(&dir, unix_now() - 60);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("min_interval_secs"));
+    assert!(executed_events(&dir).is_empty());
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--force"])
+        .assert()
+        .success();
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+}
+
+#[test]
+fn old_success_permits_run_after_min_interval() {
+    let dir = workspace(MIN_INTERVAL_CONFIG);
+    write_success_history(&dir, unix_now() - 7200);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(1);
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+}
+
+#[test]
+fn traced_command_logs_shell_steps() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "greeting=hello; echo $greeting"
+            trace: true
+          - command: "echo untraced"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("+ [trace] greeting=hello"))
+        .stdout(predicate::str::contains("+ [trace] echo hello"))
+        .stdout(predicate::str::contains("+ [trace] echo untraced").not());
+
+    assert!(command_logs(&dir).contains("+ [trace] echo hello"));
+}
+
+#[test]
+fn run_all_writes_structured_summary_record() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: web
+    events:
+      - name: deploy
+        commands:
+          - command: "echo web"
+  - name: api
+    events:
+      - name: deploy
+        commands:
+          - command: "exit 1"
+  - name: worker
+    events:
+      - name: deploy
+        commands:
+          - command: "echo worker"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "all"])
+        .assert()
+        .failure();
+
+    let history: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("deploy-history.json")).unwrap())
+            .unwrap();
+    let summary_record = history["records"]["all-deployments"]
+        .as_array()
+        .and_then(|records| records.last())
+        .expect("итоговая запись all-deployments");
+    let summary: serde_json::Value =
+        serde_json::from_str(summary_record["details"].as_str().unwrap()).unwrap();
+
+    assert_eq!(summary["total"], 3);
+    assert_eq!(summary["succeeded"], 2);
+    assert_eq!(summary["failed"], 1);
+    assert_eq!(summary["failed_deployments"], serde_json::json!(["api"]));
+}
+
+#[test]
+fn working_dir_does_not_affect_other_deployments() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: nested
+    working_dir: "nested"
+    events:
+      - name: deploy
+        commands:
+          - command: "echo nested > marker.txt"
+  - name: root
+    events:
+      - name: deploy
+        commands:
+          - command: "sleep 0.1; echo root >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "all"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["root"]);
+    assert!(dir.path().join("nested").join("marker.txt").exists());
+    assert!(!dir.path().join("nested").join("order.txt").exists());
+}
+
+#[test]
+fn run_env_override_wins_over_deployment_environment() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    environment:
+      - "APP_PORT=8080"
+    events:
+      - name: deploy
+        commands:
+          - command: "echo $APP_PORT-$EXTRA >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args([
+            "run",
+            "-d",
+            "app",
+            "--env",
+            "APP_PORT=9090",
+            "--env",
+            "EXTRA=1",
+        ])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["9090-1"]);
+}
+
+#[test]
+fn run_rejects_malformed_env_override() {
+    let dir = workspace(ENVIRONMENT_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--env", "APP_PORT"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("KEY=VALUE"));
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+/// Конфигурация с упавшей командой, ошибка которой игнорируется
+const IGNORED_FAILURE_CONFIG: &str = r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "exit 1"
+            ignore_errors: true
+          - command: "echo deploy >> order.txt"
+"#;
+
+#[test]
+fn ignored_failure_is_recorded_as_warning() {
+    let dir = workspace(IGNORED_FAILURE_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+    let history: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("deploy-history.json")).unwrap())
+            .unwrap();
+    let result = history["records"]["app"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|record| record["event"] == "result:deploy")
+        .expect("запись результата события");
+    assert_eq!(result["success"], true);
+    assert_eq!(result["warnings"], 1);
+}
+
+#[test]
+fn strict_ignored_fails_on_ignored_failures() {
+    let dir = workspace(IGNORED_FAILURE_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--strict-ignored"])
+        .assert()
+        .code(5);
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+
+    let dir = workspace(&single_deployment_config("app"));
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--strict-ignored"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn named_command_appears_by_name_in_logs() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo migrated"
+            name: migrate
+          - command: "echo unnamed"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("app_deploy_migrate"))
+        .stdout(predicate::str::contains("app_deploy_cmd_2"));
+
+    let logs = command_logs(&dir);
+    assert!(logs.contains("app_deploy_migrate"), "{}", logs);
+}
+
+#[test]
+fn cli_var_overrides_variables_file() {
+    let dir = workspace(INLINE_VARIABLES_CONFIG);
+    fs::write(
+        dir.path().join("variables.json"),
+        r#"{ "TARGET": "file-target" }"#,
+    )
+    .unwrap();
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--var", "TARGET=cli-target"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["cli-target command-branch"]);
+}
+
+#[test]
+fn cli_var_expands_environment_variables() {
+    let dir = workspace(INLINE_VARIABLES_CONFIG);
+
+    deploy_cmd(&dir)
+        .env("DEPLOY_TARGET", "env-target")
+        .args(["run", "-d", "app", "--var", "TARGET=${DEPLOY_TARGET}"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["env-target command-branch"]);
+}
+
+#[test]
+fn verify_reports_duplicate_command_names() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo one"
+            name: step
+          - command: "echo two"
+            name: step
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "verify", "-d", "app"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("повторяет имя 'step'"))
+        .stdout(predicate::str::contains(
+            "Конфигурация деплоя 'app' некорректна",
+        ));
+}
+
+/// Конфигурация деплоя со снимком окружения; событие `deploy` завершается с кодом `code`
+fn snapshot_config(snapshot: &str, code: i32) -> String {
+    format!(
+        r#"
+deployments:
+  - name: app
+    snapshot_command: "{}"
+    restore_command: "echo restore >> order.txt"
+    events:
+      - name: build
+        commands:
+          - command: "echo build >> order.txt"
+      - name: deploy
+        commands:
+          - command: "echo deploy >> order.txt; exit {}"
+"#,
+        snapshot, code
+    )
+}
+
+#[test]
+fn snapshot_runs_before_events_without_restore_on_success() {
+    let dir = workspace(&snapshot_config("echo snapshot >> order.txt", 0));
+
+    deploy_cmd(&dir)
+        .args(["--parallel", "false", "run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["snapshot", "build", "deploy"]);
+}
+
+#[test]
+fn restore_runs_when_an_event_fails() {
+    let dir = workspace(&snapshot_config("echo snapshot >> order.txt", 1));
+
+    deploy_cmd(&dir)
+        .args(["--parallel", "false", "run", "-d", "app"])
+        .assert()
+        .failure();
+
+    assert_eq!(
+        executed_events(&dir),
+        vec!["snapshot", "build", "deploy", "restore"]
+    );
+}
+
+#[test]
+fn failed_snapshot_prevents_events() {
+    let dir = workspace(&snapshot_config("echo snapshot >> order.txt; exit 1", 0));
+
+    deploy_cmd(&dir)
+        .args(["--parallel", "false", "run", "-d", "app"])
+        .assert()
+        .failure();
+
+    assert_eq!(executed_events(&dir), vec!["snapshot"]);
+}
+
+#[test]
+fn exceeding_max_daily_log_size_rolls_to_numbered_file() {
+    let dir = workspace(&single_deployment_config("app"));
+    write_settings(&dir, r#""max_daily_log_mb": 1"#);
+    let logs = dir.path().join("logs");
+    fs::create_dir_all(&logs).unwrap();
+    let date = chrono::Local::now().format("%Y%m%d");
+    let daily = logs.join(format!("{}_commands.log", date));
+    fs::write(&daily, vec![b'x'; 1024 * 1024]).unwrap();
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(fs::metadata(&daily).unwrap().len(), 1024 * 1024);
+    let rolled = logs.join(format!("{}_commands.1.log", date));
+    assert!(fs::read_to_string(rolled)
+        .unwrap()
+        .contains("app_deploy_cmd_1"));
+}
+
+#[test]
+fn commands_see_variables_from_env_files() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    env_files: ["deploy.env"]
+    events:
+      - name: deploy
+        commands:
+          - command: "echo $DB_HOST >> order.txt"
+"#,
+    );
+    fs::write(dir.path().join("deploy.env"), "DB_HOST=db.local\n").unwrap();
+
+    deploy_cmd(&dir)
+        .env_remove("DB_HOST")
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["db.local"]);
+}
+
+/// Конфигурация, первый деплой которой изменяет команду второго деплоя в файле конфигурации
+const SELF_CHANGING_CONFIG: &str = r#"
+deployments:
+  - name: first
+    events:
+      - name: deploy
+        commands:
+          - command: "sed -i.bak 's/echo second/echo changed/' deploy-config.yml; echo first >> order.txt"
+  - name: second
+    events:
+      - name: deploy
+        commands:
+          - command: "echo second >> order.txt"
+"#;
+
+#[test]
+fn changed_config_aborts_remaining_batch() {
+    let dir = workspace(SELF_CHANGING_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["--parallel", "false", "run", "-d", "all"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("изменился во время запуска"));
+
+    assert_eq!(executed_events(&dir), vec!["first"]);
+}
+
+#[test]
+fn ignore_config_change_continues_with_loaded_config() {
+    let dir = workspace(SELF_CHANGING_CONFIG);
+
+    deploy_cmd(&dir)
+        .args([
+            "--parallel",
+            "false",
+            "run",
+            "-d",
+            "all",
+            "--ignore-config-change",
+        ])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["first", "second"]);
+}
+
+#[test]
+fn history_records_per_command_outcomes() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo ok"
+          - command: "exit 7"
+          - command: "echo never"
+"#,
+    );
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(3);
+
+    let history: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("deploy-history.json")).unwrap())
+            .unwrap();
+    let commands = history["records"]["app"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|record| record["event"] == "result:deploy")
+        .map(|record| record["commands"].as_array().unwrap().clone())
+        .expect("запись результата события");
+
+    assert_eq!(commands.len(), 2);
+    assert_eq!(commands[0]["name"], "app_deploy_cmd_1");
+    assert_eq!(commands[0]["success"], true);
+    assert_eq!(commands[0]["exit_code"], 0);
+    assert_eq!(commands[1]["name"], "app_deploy_cmd_2");
+    assert_eq!(commands[1]["success"], false);
+    assert_eq!(commands[1]["exit_code"], 7);
+    assert!(commands[1]["duration_ms"].is_u64());
+}
+
+#[test]
+fn quiet_command_output_goes_only_to_command_log() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo noisy-output"
+            quiet: true
+          - command: "echo regular-output"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("noisy-output").not())
+        .stdout(predicate::str::contains("regular-output"));
+
+    let logs = command_logs(&dir);
+    assert!(logs.contains("noisy-output"), "{}", logs);
+    assert!(logs.contains("regular-output"), "{}", logs);
+}
+
+#[test]
+fn history_diff_reports_divergent_event() {
+    let dir = workspace(CONFIG);
+    let record = |deployment: &str, event: &str, timestamp: u64, success: bool| {
+        format!(
+            r#"{{"deployment":"{}","event":"{}","timestamp":{},"success":{},"details":null}}"#,
+            deployment, event, timestamp, success
+        )
+    };
+    let history = format!(
+        r#"{{"records":{{"frontend":[{},{}],"backend":[{},{}]}}}}"#,
+        record("frontend", "complete:build", 1, true),
+        record("frontend", "complete:deploy", 2, true),
+        record("backend", "complete:build", 1, true),
+        record("backend", "failed:deploy", 2, false),
+    );
+    fs::write(dir.path().join("deploy-history.json"), history).unwrap();
+
+    deploy_cmd(&dir)
+        .args([
+            "--color",
+            "never",
+            "history-diff",
+            "--a",
+            "frontend",
+            "--b",
+            "backend",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "≠ deploy: frontend ✅, backend ❌",
+        ))
+        .stdout(predicate::str::contains("  build: frontend ✅, backend ✅"))
+        .stdout(predicate::str::contains("Исходы различаются у событий: 1"));
+}
+
+#[test]
+fn failed_group_command_rolls_back_whole_group() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo backup"
+            rollback_command: "echo rollback-backup >> order.txt"
+          - command: "echo migrate"
+            group: release
+            rollback_command: "echo rollback-migrate >> order.txt"
+          - command: "exit 1"
+            group: release
+            rollback_command: "echo rollback-switch >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .failure();
+
+    assert_eq!(
+        executed_events(&dir),
+        vec!["rollback-switch", "rollback-migrate"]
+    );
+}
+
+#[test]
+fn list_filter_shows_only_matching_entries() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: frontend
+    events:
+      - name: build
+        commands:
+          - command: "npm run build"
+      - name: deploy
+        commands:
+          - command: "rsync -a dist/ web:/srv"
+  - name: backend
+    events:
+      - name: deploy
+        commands:
+          - command: "cargo build --release"
+          - command: "systemctl restart api"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "list", "--filter", "RESTART"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Деплой: backend"))
+        .stdout(predicate::str::contains("systemctl restart api"))
+        .stdout(predicate::str::contains("cargo build").not())
+        .stdout(predicate::str::contains("Деплой: frontend").not());
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "list", "--filter", "terraform"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "содержащие 'terraform', не найдены",
+        ));
+}
+
+/// Конфигурация с командами, которые нельзя выполнить по-настоящему
+const SIMULATED_CONFIG: &str = r#"
+deployments:
+  - name: app
+    version_command: "echo 1.2.3"
+    variables:
+      TARGET: "prod"
+    events:
+      - name: deploy
+        commands:
+          - command: "definitely-missing-binary --target {#TARGET}"
+          - command: "echo deploy >> order.txt"
+"#;
+
+#[test]
+fn simulate_records_success_without_running_commands() {
+    let dir = workspace(SIMULATED_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--simulate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[simulate] definitely-missing-binary --target prod",
+        ));
+
+    assert!(executed_events(&dir).is_empty());
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert!(history.contains("complete:full-deploy"), "{}", history);
+    assert!(!history.contains("deployed_version"), "{}", history);
+}
+
+#[test]
+fn simulate_with_exit_code_fails_the_event() {
+    let dir = workspace(SIMULATED_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--simulate", "3"])
+        .assert()
+        .code(3);
+
+    assert!(executed_events(&dir).is_empty());
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert!(history.contains("failed:deploy"), "{}", history);
+}
+
+const CLEAN_GIT_CONFIG: &str = r#"
+deployments:
+  - name: app
+    require_clean_git: true
+    events:
+      - name: deploy
+        commands:
+          - command: "echo deploy >> order.txt"
+"#;
+
+/// Создает репозиторий git с зафиксированными файлами рабочей директории
+///
+/// Возвращает false, если git недоступен
+fn commit_workspace(dir: &TempDir) -> bool {
+    fs::write(
+        dir.path().join(".gitignore"),
+        "order.txt\ndeploy-history.json\nlogs/\nsettings.json\n*.log\n",
+    )
+    .unwrap();
+    git(dir, &["init", "-q"])
+        .and_then(|_| git(dir, &["add", "-A"]))
+        .and_then(|_| {
+            git(
+                dir,
+                &[
+                    "-c",
+                    "user.name=test",
+                    "-c",
+                    "user.email=test@example.com",
+                    "commit",
+                    "-q",
+                    "-m",
+                    "initial",
+                ],
+            )
+        })
+        .is_some()
+}
+
+#[test]
+fn dirty_git_tree_blocks_run() {
+    let dir = workspace(CLEAN_GIT_CONFIG);
+    if !commit_workspace(&dir) {
+        eprintln!("git недоступен, тест пропущен");
+        return;
+    }
+    fs::write(dir.path().join("uncommitted.txt"), "dirty").unwrap();
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("uncommitted.txt"));
+    assert!(executed_events(&dir).is_empty());
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--force"])
+        .assert()
+        .success();
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+}
+
+#[test]
+fn clean_git_tree_permits_run() {
+    let dir = workspace(CLEAN_GIT_CONFIG);
+    if !commit_workspace(&dir) {
+        eprintln!("git недоступен, тест пропущен");
+        return;
+    }
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+}
+
+#[test]
+fn require_clean_git_outside_repository_warns_and_runs() {
+    let dir = workspace(CLEAN_GIT_CONFIG);
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("не является репозиторием git"));
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+}
+
+#[test]
+fn version_command_output_is_recorded_and_shown_in_history() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    version_command: "printf '  1.2.3\n\n'"
+    events:
+      - name: deploy
+        commands:
+          - command: "echo deploy >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert!(
+        history.contains(r#""deployed_version": "1.2.3""#),
+        "{}",
+        history
+    );
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "history", "-d", "app"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(версия 1.2.3)"));
+}
+
+#[test]
+fn failed_version_command_keeps_successful_deploy() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    version_command: "exit 1"
+    events:
+      - name: deploy
+        commands:
+          - command: "echo deploy >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["deploy"]);
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert!(history.contains("complete:full-deploy"), "{}", history);
+    assert!(!history.contains("deployed_version"), "{}", history);
+}
+
+const ENV_INPUT_CONFIG: &str = r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo \"$answer\" >> order.txt # {answer}"
+            interactive: true
+            inputs:
+              "answer": "{$DEPLOY_TEST_SECRET}"
+"#;
+
+#[test]
+fn interactive_input_reads_answer_from_environment() {
+    let dir = workspace(ENV_INPUT_CONFIG);
+
+    deploy_cmd(&dir)
+        .env("DEPLOY_TEST_SECRET", "s3cret")
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["s3cret"]);
+    let config = fs::read_to_string(dir.path().join("deploy-config.yml")).unwrap();
+    assert!(!config.contains("s3cret"));
+}
+
+#[test]
+fn deployment_environment_takes_precedence_for_input_answers() {
+    let dir = workspace(&ENV_INPUT_CONFIG.replace(
+        "    events:",
+        "    environment:\n      - \"DEPLOY_TEST_SECRET=from-deployment\"\n    events:",
+    ));
+
+    deploy_cmd(&dir)
+        .env("DEPLOY_TEST_SECRET", "from-process")
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["from-deployment"]);
+}
+
+/// Запускает сборщик спанов OTLP/HTTP, сохраняющий тела запросов
+///
+/// Возвращает адрес сборщика и полученные тела запросов
+fn start_span_collector() -> (String, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    let bodies = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let received = std::sync::Arc::clone(&bodies);
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let received = std::sync::Arc::clone(&received);
+            std::thread::spawn(move || {
+                let mut writer = stream.try_clone().unwrap();
+                let mut reader = BufReader::new(stream);
+                // Соединение может переиспользоваться для нескольких запросов
+                loop {
+                    let mut content_length = 0;
+                    let mut line = String::new();
+                    loop {
+                        line.clear();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let header = line.trim().to_ascii_lowercase();
+                        if header.is_empty() {
+                            break;
+                        }
+                        if let Some(value) = header.strip_prefix("content-length:") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).unwrap();
+                    received
+                        .lock()
+                        .unwrap()
+                        .push(String::from_utf8(body).unwrap());
+                    writer
+                        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                        .unwrap();
+                }
+            });
+        }
+    });
+
+    (endpoint, bodies)
+}
+
+#[test]
+fn run_exports_spans_to_otel_endpoint() {
+    let dir = workspace(&single_deployment_config("app"));
+    let (endpoint, bodies) = start_span_collector();
+    write_settings(&dir, &format!(r#""otel_endpoint": "{}""#, endpoint));
+
+    deploy_cmd(&dir)
+        .env_remove("HTTP_PROXY")
+        .env_remove("http_proxy")
+        .env_remove("ALL_PROXY")
+        .env_remove("all_proxy")
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    let spans: Vec<serde_json::Value> = bodies
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|body| {
+            let body: serde_json::Value = serde_json::from_str(body).unwrap();
+            body["resourceSpans"][0]["scopeSpans"][0]["spans"]
+                .as_array()
+                .unwrap()
+                .clone()
+        })
+        .collect();
+    let find = |name: &str| {
+        spans
+            .iter()
+            .find(|span| span["name"] == name)
+            .unwrap_or_else(|| panic!("нет спана {}: {:?}", name, spans))
+    };
+
+    let deployment = find("app");
+    let event = find("deploy");
+    let command = find("app_deploy_cmd_1");
+    assert_eq!(deployment["parentSpanId"], "");
+    assert_eq!(event["parentSpanId"], deployment["spanId"]);
+    assert_eq!(command["parentSpanId"], event["spanId"]);
+    assert_eq!(command["traceId"], deployment["traceId"]);
+    assert_eq!(event["status"]["code"], 1);
+}
+
+/// Конфигурация с первой командой, завершающейся кодом `exit_code`, при разрешенном коде 1
+fn allowed_exit_code_config(exit_code: i32, fail_fast: bool) -> String {
+    format!(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        fail_fast: {}
+        commands:
+          - command: "echo first >> order.txt; exit {}"
+            allow_exit_codes: [1]
+          - command: "echo second >> order.txt"
+"#,
+        fail_fast, exit_code
+    )
+}
+
+#[test]
+fn allowed_exit_code_counts_as_success() {
+    for fail_fast in [true, false] {
+        let dir = workspace(&allowed_exit_code_config(1, fail_fast));
+
+        deploy_cmd(&dir)
+            .args(["run", "-d", "app"])
+            .assert()
+            .success();
+
+        assert_eq!(executed_events(&dir), vec!["first", "second"]);
+        let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+        assert!(history.contains("complete:deploy"), "{}", history);
+        assert!(!history.contains("failed:deploy"), "{}", history);
+    }
+}
+
+#[test]
+fn exit_code_not_in_allow_list_fails_the_event() {
+    let dir = workspace(&allowed_exit_code_config(2, true));
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(3);
+
+    assert_eq!(executed_events(&dir), vec!["first"]);
+}
+
+#[test]
+fn command_receives_previous_exit_code() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo check >> order.txt"
+          - command: "echo {#__PREV_EXIT__} >> order.txt"
+          - command: "exit 3"
+            allow_exit_codes: [3]
+          - command: "echo {#__PREV_EXIT__} >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["check", "0", "3"]);
+}
+
+#[test]
+fn ignored_failure_exit_code_is_forwarded() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "exit 7"
+            ignore_errors: true
+          - command: "echo {#__PREV_EXIT__} >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["7"]);
+}
+
+#[test]
+fn validate_settings_reports_unwritable_path() {
+    let dir = workspace(&single_deployment_config("app"));
+    fs::write(dir.path().join("blocker"), "").unwrap();
+    fs::write(
+        dir.path().join("settings.json"),
+        r#"{
+  "log_file": "deploy-commander.log",
+  "history_file": "blocker/deploy-history.json",
+  "variables_file": "variables.json",
+  "logs_dir": "logs"
+}"#,
+    )
+    .unwrap();
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "validate-settings"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("history_file: не удается создать"))
+        .stdout(predicate::str::contains("log_file").not());
+}
+
+#[test]
+fn validate_settings_accepts_valid_settings() {
+    let dir = workspace(&single_deployment_config("app"));
+    write_settings(&dir, r#""redact_patterns": ["token=\\w+"]"#);
+
+    deploy_cmd(&dir)
+        .args(["--color", "never", "validate-settings"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("корректны"));
+    assert!(!dir.path().join("logs").exists());
+}
+
+/// Конфигурация с проверкой вывода первой команды и второй командой, выполняемой после нее
+fn output_assertion_config(assertion: &str) -> String {
+    format!(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo 'status: ok v2.15'"
+            {}
+          - command: "echo next >> order.txt"
+"#,
+        assertion
+    )
+}
+
+#[test]
+fn output_contains_assertion_passes() {
+    let dir = workspace(&output_assertion_config(
+        r#"assert_output_contains: "status: ok""#,
+    ));
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+
+    assert_eq!(executed_events(&dir), vec!["next"]);
+}
+
+#[test]
+fn output_contains_assertion_fails_command_with_zero_exit_code() {
+    let dir = workspace(&output_assertion_config(
+        r#"assert_output_contains: "status: degraded""#,
+    ));
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(
+            "Вывод команды не содержит 'status: degraded'",
+        ));
+
+    assert!(executed_events(&dir).is_empty());
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert!(history.contains("failed:deploy"), "{}", history);
+}
+
+#[test]
+fn output_matches_assertion_uses_regex() {
+    let dir = workspace(&output_assertion_config(
+        r#"assert_output_matches: 'v2\.\d+'"#,
+    ));
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .success();
+    assert_eq!(executed_events(&dir), vec!["next"]);
+
+    let dir = workspace(&output_assertion_config(
+        r#"assert_output_matches: 'v3\.\d+'"#,
+    ));
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(3);
+    assert!(executed_events(&dir).is_empty());
+}
+
+#[test]
+fn repeat_runs_event_n_times_and_reports_counts() {
+    let dir =
+        workspace(&single_deployment_config("app").replace("echo app", "echo run >> order.txt"));
+
+    deploy_cmd(&dir)
+        .args([
+            "--color", "never", "run", "-d", "app", "-e", "deploy", "--repeat", "3",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "запусков 3, успешно 3, с ошибкой 0",
+        ));
+
+    assert_eq!(executed_events(&dir), vec!["run", "run", "run"]);
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert_eq!(history.matches("complete:deploy").count(), 3, "{}", history);
+}
+
+#[test]
+fn repeat_continues_after_failed_iteration() {
+    // Второй запуск завершается с ошибкой
+    let dir = workspace(&single_deployment_config("app").replace(
+        "echo app",
+        "echo run >> order.txt; test $(wc -l < order.txt) -ne 2",
+    ));
+
+    deploy_cmd(&dir)
+        .args([
+            "--color", "never", "run", "-d", "app", "-e", "deploy", "--repeat", "3",
+        ])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(
+            "запусков 3, успешно 2, с ошибкой 1",
+        ));
+
+    assert_eq!(executed_events(&dir).len(), 3);
+    let history = fs::read_to_string(dir.path().join("deploy-history.json")).unwrap();
+    assert_eq!(history.matches("start:deploy").count(), 3, "{}", history);
+    assert_eq!(history.matches("complete:deploy").count(), 2, "{}", history);
+    assert!(history.contains("failed:deploy"), "{}", history);
+}
+
+#[test]
+fn repeat_requires_single_event() {
+    let dir = workspace(&single_deployment_config("app"));
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "--repeat", "2"])
+        .assert()
+        .code(2);
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app", "-e", "deploy", "--repeat", "0"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn long_history_details_are_truncated_by_max_detail_len() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: build
+        commands:
+          - command: "echo build"
+      - name: deploy
+        commands:
+          - command: "printf 'x%.0s' $(seq 500) >&2; exit 1"
+"#,
+    );
+    write_settings(&dir, r#""max_detail_len": 100"#);
+
+    deploy_cmd(&dir).args(["run", "-d", "app"]).assert().code(3);
+
+    let history: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("deploy-history.json")).unwrap())
+            .unwrap();
+    let records = history["records"]["app"].as_array().unwrap();
+    let details = |event: &str| {
+        records
+            .iter()
+            .find(|record| record["event"] == event)
+            .and_then(|record| record["details"].as_str())
+            .unwrap_or_else(|| panic!("нет записи {}: {:?}", event, records))
+            .to_string()
+    };
+
+    let failed = details("failed:deploy");
+    assert!(failed.contains("… [обрезано символов: "), "{}", failed);
+    assert!(failed.chars().count() < 150, "{}", failed);
+    assert!(!details("complete:build").contains("обрезано"));
+}
+
+#[test]
+fn unversioned_config_does_not_turn_stray_rollback_key_into_command() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "exit 1"
+            rollback: "echo rollback >> order.txt"
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(
+            "Неизвестное поле в конфигурации будет проигнорировано",
+        ));
+
+    assert!(executed_events(&dir).is_empty());
+}
+
+#[test]
+fn deprecated_command_retry_fields_still_retry_with_warning() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo attempt >> order.txt; exit 75"
+            retries: 2
+            retry_on_exit_codes: [75]
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains(
+            "Поле команды 'retries' устарело, используйте retry_policy.retries",
+        ));
+
+    assert_eq!(executed_events(&dir), vec!["attempt"; 3]);
+}
+
+#[test]
+fn failed_traced_command_shows_stderr_in_output() {
+    let dir = workspace(
+        r#"
+deployments:
+  - name: app
+    events:
+      - name: deploy
+        commands:
+          - command: "echo boom >&2; exit 4"
+            trace: true
+"#,
+    );
+
+    deploy_cmd(&dir)
+        .args(["run", "-d", "app"])
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("Стандартный вывод команды:"))
+        .stdout(predicate::str::contains("│ + [trace] exit 4"))
+        .stdout(predicate::str::contains("│ boom"));
+}